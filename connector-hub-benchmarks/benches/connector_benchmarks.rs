@@ -115,7 +115,7 @@ fn benchmark_sse_parsing(c: &mut Criterion) {
 }
 
 fn benchmark_provider_selection(c: &mut Criterion) {
-    let providers = vec![
+    let providers = [
         ("openai", true, 100),
         ("anthropic", true, 150),
         ("google", false, 200),
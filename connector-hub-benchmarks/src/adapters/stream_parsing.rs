@@ -5,6 +5,8 @@
 use super::BenchTarget;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use connector_hub_core::streaming::{ChatStreamParser, SseDecoder, StreamAggregator};
+use connector_hub_core::types::CompletionResponse;
 use serde_json::Value;
 use std::process::Stdio;
 use std::time::Instant;
@@ -80,20 +82,14 @@ impl StreamParsingBenchmark {
         let mut parse_times: Vec<u64> = Vec::with_capacity(self.iterations as usize);
         let mut aggregate_times: Vec<u64> = Vec::with_capacity(self.iterations as usize);
 
-        // Sample SSE chunks
-        let chunks = vec![
-            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n",
-            "data: {\"choices\":[{\"delta\":{\"content\":\" world\"}}]}\n\n",
-            "data: {\"choices\":[{\"delta\":{\"content\":\"!\"}}]}\n\n",
-            "data: [DONE]\n\n",
-        ];
+        let chunks = sample_chunks();
 
         // Warmup
         for _ in 0..self.warmup_iterations {
             for chunk in &chunks {
                 std::hint::black_box(self.parse_sse_chunk(chunk));
             }
-            std::hint::black_box(self.aggregate_chunks(&chunks));
+            std::hint::black_box(self.aggregate_chunks(&chunks)?);
         }
 
         // Benchmark single chunk parsing
@@ -108,7 +104,7 @@ impl StreamParsingBenchmark {
         // Benchmark full stream aggregation
         for _ in 0..self.iterations {
             let start = Instant::now();
-            std::hint::black_box(self.aggregate_chunks(&chunks));
+            std::hint::black_box(self.aggregate_chunks(&chunks)?);
             aggregate_times.push(start.elapsed().as_nanos() as u64);
         }
 
@@ -164,17 +160,36 @@ impl StreamParsingBenchmark {
         None
     }
 
-    fn aggregate_chunks(&self, chunks: &[&str]) -> String {
-        let mut result = String::new();
+    /// Rebuild the full response, including tool calls, usage and finish reason
+    fn aggregate_chunks(&self, chunks: &[&str]) -> Result<CompletionResponse> {
+        let mut decoder = SseDecoder::new();
+        let mut parser = ChatStreamParser::new();
+        let mut aggregator = StreamAggregator::new();
         for chunk in chunks {
-            if let Some(content) = self.parse_sse_chunk(chunk) {
-                result.push_str(&content);
+            for event in decoder.push(chunk.as_bytes()) {
+                for event in parser.parse_event(&event)? {
+                    aggregator.push(event);
+                }
             }
         }
-        result
+        Ok(aggregator.finish())
     }
 }
 
+/// Sample OpenAI stream: text, a fragmented tool call, finish and usage
+fn sample_chunks() -> Vec<&'static str> {
+    vec![
+        "data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hello\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\" world\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"!\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"lookup\",\"arguments\":\"{\\\"q\\\":\"}}]}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"1}\"}}]}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":9}}\n\n",
+        "data: [DONE]\n\n",
+    ]
+}
+
 impl Default for StreamParsingBenchmark {
     fn default() -> Self {
         Self::new()
@@ -224,4 +239,20 @@ mod tests {
         let done = "data: [DONE]\n\n";
         assert_eq!(bench.parse_sse_chunk(done), None);
     }
+
+    #[test]
+    fn test_aggregate_chunks_keeps_tool_calls_and_usage() {
+        let bench = StreamParsingBenchmark::new();
+        let response = bench.aggregate_chunks(&sample_chunks()).unwrap();
+
+        assert_eq!(response.text().as_deref(), Some("Hello world!"));
+        let calls = &response.choices[0].message.tool_calls;
+        assert_eq!(calls[0].function.name, "lookup");
+        assert_eq!(calls[0].function.arguments, "{\"q\":1}");
+        assert_eq!(response.usage.total_tokens, 21);
+        assert_eq!(
+            response.finish_reason(),
+            Some(connector_hub_core::types::FinishReason::ToolCalls)
+        );
+    }
 }
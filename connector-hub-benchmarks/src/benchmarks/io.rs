@@ -172,7 +172,7 @@ pub fn list_result_files(base_path: &Path) -> Result<Vec<PathBuf>> {
         .map(|entry| entry.path())
         .filter(|path| {
             path.extension()
-                .map_or(false, |ext| ext == "json")
+                .is_some_and(|ext| ext == "json")
         })
         .collect();
 
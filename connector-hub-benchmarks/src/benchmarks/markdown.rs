@@ -35,7 +35,7 @@ pub fn generate_markdown_report(results: &[BenchmarkResult], title: Option<&str>
     let failed = total - successful;
 
     md.push_str("## Summary\n\n");
-    md.push_str(&format!("| Metric | Value |\n"));
+    md.push_str("| Metric | Value |\n");
     md.push_str("|--------|-------|\n");
     md.push_str(&format!("| Total Benchmarks | {} |\n", total));
     md.push_str(&format!("| Successful | {} |\n", successful));
@@ -71,7 +71,7 @@ pub fn generate_markdown_report(results: &[BenchmarkResult], title: Option<&str>
         ));
     }
 
-    md.push_str("\n");
+    md.push('\n');
 
     // Individual benchmark details
     md.push_str("## Benchmark Details\n\n");
@@ -107,7 +107,7 @@ pub fn generate_summary_table(results: &[BenchmarkResult]) -> String {
         let status = if result.is_success() { "OK" } else { "FAIL" };
         let mean = result
             .mean_ns()
-            .map(|v| format_duration_ns(v))
+            .map(format_duration_ns)
             .unwrap_or_else(|| "-".to_string());
         let throughput = result
             .throughput()
@@ -100,7 +100,7 @@ impl BenchmarkResult {
     ///
     /// `true` if the metrics don't contain an error status.
     pub fn is_success(&self) -> bool {
-        !self.metrics.get("status").map_or(false, |s| s == "failed")
+        !self.metrics.get("status").is_some_and(|s| s == "failed")
     }
 
     /// Get a specific metric value.
@@ -9,7 +9,7 @@ use connector_hub_benchmarks::{
     adapters::all_targets,
     benchmarks::{io, run_all_benchmarks, run_benchmarks_by_id},
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -167,7 +167,7 @@ fn list_targets_command() {
     println!("  run_benchmarks run --targets provider-resolution,cache-operations");
 }
 
-fn show_summary_command(crate_path: &PathBuf) -> Result<()> {
+fn show_summary_command(crate_path: &Path) -> Result<()> {
     let latest_path = crate_path.join("benchmarks/output/raw/results-latest.json");
 
     if !latest_path.exists() {
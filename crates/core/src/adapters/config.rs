@@ -34,6 +34,10 @@ pub struct ConfigAdapter {
     environment: Environment,
     /// Cached configurations
     cache: HashMap<String, ProviderConfig>,
+    /// Explicitly registered configurations (survive environment changes)
+    registered: HashMap<String, ProviderConfig>,
+    /// Explicitly registered routing policies
+    routing: HashMap<String, RoutingPolicy>,
 }
 
 /// Provider configuration
@@ -64,6 +68,8 @@ impl ConfigAdapter {
             _namespace: "connector-hub".to_string(),
             environment: Environment::Production,
             cache: HashMap::new(),
+            registered: HashMap::new(),
+            routing: HashMap::new(),
         }
     }
 
@@ -73,6 +79,8 @@ impl ConfigAdapter {
             _namespace: namespace.into(),
             environment: Environment::Production,
             cache: HashMap::new(),
+            registered: HashMap::new(),
+            routing: HashMap::new(),
         }
     }
 
@@ -83,6 +91,17 @@ impl ConfigAdapter {
         self.cache.clear();
     }
 
+    /// Register a configuration for a provider name
+    ///
+    /// Registered configurations take precedence over the built-in defaults
+    /// and are kept across environment changes. This is how custom provider
+    /// names (e.g. a self-hosted "my-vllm" endpoint) are configured.
+    pub fn register_provider_config(&mut self, config: ProviderConfig) {
+        info!(provider = %config.provider, "Registering provider configuration");
+        self.cache.remove(&config.provider);
+        self.registered.insert(config.provider.clone(), config);
+    }
+
     /// Get provider configuration
     ///
     /// # Arguments
@@ -114,7 +133,10 @@ impl ConfigAdapter {
         // - Cache result
 
         // Placeholder: Create default config
-        let config = self.create_default_config(provider);
+        let config = match self.registered.get(provider) {
+            Some(config) => config.clone(),
+            None => self.create_default_config(provider),
+        };
         self.cache.insert(provider.to_string(), config);
 
         Ok(self.cache.get(provider).unwrap())
@@ -137,7 +159,7 @@ impl ConfigAdapter {
         // - Return plaintext credential
 
         // Placeholder: Return environment variable pattern
-        let env_var = format!("{}_{}", provider.to_uppercase(), credential_name.to_uppercase());
+        let env_var = env_var_name(provider, credential_name);
         std::env::var(&env_var).map_err(|_| {
             ConnectorError::Config(format!(
                 "Credential not found: {} (looked for env var: {})",
@@ -172,25 +194,72 @@ impl ConfigAdapter {
         Ok(())
     }
 
+    /// Register a routing policy for a provider name
+    ///
+    /// Registered policies take precedence over environment variables.
+    pub fn set_routing_policy(&mut self, provider: impl Into<String>, policy: RoutingPolicy) {
+        let provider = provider.into();
+        info!(provider = %provider, "Registering routing policy");
+        self.routing.insert(provider, policy);
+    }
+
     /// Load routing policy for provider
     ///
-    /// Retrieves routing configuration from config manager
+    /// Retrieves routing configuration from config manager. Without a
+    /// registered policy, `{PROVIDER}_RATE_LIMIT` and
+    /// `{PROVIDER}_TOKEN_RATE_LIMIT` environment variables set the
+    /// per-minute limits.
     pub fn get_routing_policy(&self, provider: &str) -> Result<RoutingPolicy> {
         debug!(provider = provider, "Loading routing policy");
 
         // Integration point with llm-config-core
         // Load routing rules, rate limits, fallback chains, etc.
+        if let Some(policy) = self.routing.get(provider) {
+            return Ok(policy.clone());
+        }
 
-        Ok(RoutingPolicy::default())
+        let limit = |name: &str| -> Result<Option<u32>> {
+            let var = env_var_name(provider, name);
+            match std::env::var(&var) {
+                Ok(value) => value
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| ConnectorError::Config(format!("Invalid {}: {}", var, value))),
+                Err(_) => Ok(None),
+            }
+        };
+        Ok(RoutingPolicy {
+            rate_limit: limit("rate_limit")?,
+            token_rate_limit: limit("token_rate_limit")?,
+            ..Default::default()
+        })
     }
 
     /// Helper: Create default provider config
+    ///
+    /// `{PROVIDER}_ENDPOINT` and `{PROVIDER}_MODELS` (comma-separated)
+    /// environment variables override the built-in defaults, which lets
+    /// custom provider names be configured without code.
     fn create_default_config(&self, provider: &str) -> ProviderConfig {
+        let endpoint = std::env::var(env_var_name(provider, "endpoint"))
+            .ok()
+            .or_else(|| self.get_default_endpoint(provider));
+        let models = match std::env::var(env_var_name(provider, "models")) {
+            Ok(models) => models
+                .split(',')
+                .map(str::trim)
+                .filter(|model| !model.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => self.get_default_models(provider),
+        };
+
         ProviderConfig {
             provider: provider.to_string(),
-            endpoint: self.get_default_endpoint(provider),
+            endpoint,
             api_key: None, // Load from credentials separately
-            models: self.get_default_models(provider),
+            models,
             settings: HashMap::new(),
         }
     }
@@ -201,6 +270,13 @@ impl ConfigAdapter {
             "openai" => Some("https://api.openai.com/v1".to_string()),
             "anthropic" => Some("https://api.anthropic.com/v1".to_string()),
             "google" => Some("https://generativelanguage.googleapis.com/v1".to_string()),
+            "mistral" => Some("https://api.mistral.ai/v1".to_string()),
+            "cohere" => Some("https://api.cohere.com".to_string()),
+            // Default ports of common self-hosted OpenAI-compatible servers
+            "ollama" => Some("http://localhost:11434/v1".to_string()),
+            "vllm" => Some("http://localhost:8000/v1".to_string()),
+            "lmstudio" => Some("http://localhost:1234/v1".to_string()),
+            "llamacpp" => Some("http://localhost:8080/v1".to_string()),
             _ => None,
         }
     }
@@ -219,16 +295,42 @@ impl ConfigAdapter {
                 "claude-3-haiku-20240307".to_string(),
             ],
             "google" => vec!["gemini-pro".to_string(), "gemini-ultra".to_string()],
+            "mistral" => vec![
+                "mistral-large-latest".to_string(),
+                "mistral-small-latest".to_string(),
+                "codestral-latest".to_string(),
+            ],
+            "cohere" => vec![
+                "command-r-plus-08-2024".to_string(),
+                "command-r-08-2024".to_string(),
+                "command-r7b-12-2024".to_string(),
+            ],
+            // Azure serves models through per-resource deployments, so there
+            // is no default endpoint; these are the commonly deployed models.
+            "azure" => vec![
+                "gpt-4o".to_string(),
+                "gpt-4".to_string(),
+                "gpt-35-turbo".to_string(),
+            ],
             _ => vec![],
         }
     }
 }
 
+/// Environment variable used for a provider setting, e.g. `MY_VLLM_API_KEY`
+fn env_var_name(provider: &str, name: &str) -> String {
+    format!("{}_{}", provider, name)
+        .to_uppercase()
+        .replace(['-', '.'], "_")
+}
+
 /// Routing policy configuration
 #[derive(Debug, Clone, Default)]
 pub struct RoutingPolicy {
     /// Maximum requests per minute
     pub rate_limit: Option<u32>,
+    /// Maximum tokens (prompt and completion) per minute
+    pub token_rate_limit: Option<u32>,
     /// Fallback providers
     pub fallbacks: Vec<String>,
     /// Load balancing strategy
@@ -236,7 +338,7 @@ pub struct RoutingPolicy {
 }
 
 /// Load balancing strategy
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum LoadBalancingStrategy {
     /// Round-robin
     #[default]
@@ -245,6 +347,12 @@ pub enum LoadBalancingStrategy {
     LeastLatency,
     /// Cost-optimized
     CostOptimized,
+    /// Random, weighted
+    Random,
+    /// Fewest requests in flight
+    LeastOutstanding,
+    /// Fewer requests in flight of two random providers
+    PowerOfTwoChoices,
 }
 
 /// Provider config loader trait
@@ -270,6 +378,29 @@ impl ProviderConfigLoader for ConfigAdapter {
 mod tests {
     use super::*;
 
+    /// Sets environment variables for one test, removing them on drop
+    ///
+    /// Tests run in parallel in one process, so each test must use
+    /// variable names no other test reads.
+    struct EnvVars(Vec<&'static str>);
+
+    impl EnvVars {
+        fn set(vars: &[(&'static str, &str)]) -> Self {
+            for (name, value) in vars {
+                std::env::set_var(name, value);
+            }
+            Self(vars.iter().map(|(name, _)| *name).collect())
+        }
+    }
+
+    impl Drop for EnvVars {
+        fn drop(&mut self) {
+            for name in &self.0 {
+                std::env::remove_var(name);
+            }
+        }
+    }
+
     #[test]
     fn test_config_adapter_creation() {
         let adapter = ConfigAdapter::new();
@@ -303,6 +434,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mistral_and_cohere_defaults() {
+        let mut adapter = ConfigAdapter::new();
+
+        let mistral = adapter.get_provider_config("mistral").unwrap();
+        assert_eq!(
+            mistral.endpoint.as_deref(),
+            Some("https://api.mistral.ai/v1")
+        );
+        assert!(mistral.models.contains(&"codestral-latest".to_string()));
+
+        let cohere = adapter.get_provider_config("cohere").unwrap();
+        assert_eq!(cohere.endpoint.as_deref(), Some("https://api.cohere.com"));
+        assert!(!cohere.models.is_empty());
+    }
+
+    #[test]
+    fn test_azure_defaults() {
+        let mut adapter = ConfigAdapter::new();
+        let azure_config = adapter.get_provider_config("azure").unwrap();
+
+        assert_eq!(azure_config.endpoint, None);
+        assert!(azure_config.models.contains(&"gpt-35-turbo".to_string()));
+    }
+
+    #[test]
+    fn test_registered_provider_config() {
+        let mut adapter = ConfigAdapter::new();
+        adapter.register_provider_config(ProviderConfig {
+            provider: "my-vllm".to_string(),
+            endpoint: Some("http://gpu-box:8000/v1".to_string()),
+            api_key: None,
+            models: vec!["meta-llama/Llama-3.1-8B-Instruct".to_string()],
+            settings: HashMap::new(),
+        });
+
+        let config = adapter.get_provider_config("my-vllm").unwrap();
+        assert_eq!(config.endpoint.as_deref(), Some("http://gpu-box:8000/v1"));
+        assert_eq!(config.models.len(), 1);
+
+        // Registered configs survive cache invalidation
+        adapter.set_environment(Environment::Development);
+        assert!(adapter
+            .get_provider_config("my-vllm")
+            .unwrap()
+            .endpoint
+            .is_some());
+    }
+
+    #[test]
+    fn test_custom_provider_from_env() {
+        let _env = EnvVars::set(&[
+            ("LOCAL_LLAMA_ENDPOINT", "http://127.0.0.1:9000/v1"),
+            ("LOCAL_LLAMA_MODELS", "llama3, qwen2.5 ,"),
+        ]);
+
+        let mut adapter = ConfigAdapter::new();
+        let config = adapter.get_provider_config("local-llama").unwrap();
+        assert_eq!(config.endpoint.as_deref(), Some("http://127.0.0.1:9000/v1"));
+        assert_eq!(
+            config.models,
+            vec!["llama3".to_string(), "qwen2.5".to_string()]
+        );
+
+        let ollama = adapter.get_provider_config("ollama").unwrap();
+        assert_eq!(
+            ollama.endpoint.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+    }
+
     #[test]
     fn test_config_caching() {
         let mut adapter = ConfigAdapter::new();
@@ -334,5 +536,32 @@ mod tests {
 
         // Default policy should be created
         assert!(matches!(policy.strategy, LoadBalancingStrategy::RoundRobin));
+        assert_eq!(policy.rate_limit, None);
+    }
+
+    #[test]
+    fn test_routing_policy_overrides() {
+        let _env = EnvVars::set(&[
+            ("LIMITED_LLM_RATE_LIMIT", "120"),
+            ("LIMITED_LLM_TOKEN_RATE_LIMIT", "40000"),
+            ("BROKEN_LLM_RATE_LIMIT", "lots"),
+        ]);
+
+        let mut adapter = ConfigAdapter::new();
+        let policy = adapter.get_routing_policy("limited-llm").unwrap();
+        assert_eq!(policy.rate_limit, Some(120));
+        assert_eq!(policy.token_rate_limit, Some(40000));
+        assert!(adapter.get_routing_policy("broken-llm").is_err());
+
+        adapter.set_routing_policy(
+            "limited-llm",
+            RoutingPolicy {
+                rate_limit: Some(10),
+                ..Default::default()
+            },
+        );
+        let policy = adapter.get_routing_policy("limited-llm").unwrap();
+        assert_eq!(policy.rate_limit, Some(10));
+        assert_eq!(policy.token_rate_limit, None);
     }
 }
@@ -21,7 +21,9 @@ pub mod telemetry;
 // Re-export commonly used adapter types
 pub use config::{ConfigAdapter, ProviderConfigLoader};
 pub use schema::{SchemaValidator, ValidationAdapter};
-pub use telemetry::{SpanAdapter, TelemetryCollector};
+pub use telemetry::{
+    FinishedSpan, SharedSpanAdapter, SpanAdapter, SpanKind, SpanListener, TelemetryCollector,
+};
 
 /// Adapter result type
 pub type AdapterResult<T> = Result<T, crate::error::ConnectorError>;
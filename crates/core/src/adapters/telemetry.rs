@@ -27,9 +27,46 @@ use llm_observatory_core::span::{LlmInput, LlmOutput, LlmSpan, SpanEvent, SpanSt
 use llm_observatory_core::types::{Cost, Latency, Metadata, Provider, TokenUsage};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, info};
 
+/// Span adapter shared between concurrent operations
+pub type SharedSpanAdapter = Arc<Mutex<SpanAdapter>>;
+
+/// Summary of a finished span, passed to span listeners
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishedSpan {
+    /// Span ID
+    pub span_id: String,
+    /// Provider name the span was started with
+    pub provider: String,
+    /// Model name
+    pub model: String,
+    /// Time from start to finish
+    pub latency: Duration,
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// What the span measures
+    pub kind: SpanKind,
+}
+
+/// What a span measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpanKind {
+    /// A single provider operation
+    #[default]
+    Provider,
+    /// A routed request that may cover several provider calls, such as a
+    /// fallback chain or a hedged request
+    Routed,
+    /// A health probe
+    Probe,
+}
+
+/// Callback invoked for every finished span
+pub type SpanListener = Arc<dyn Fn(&FinishedSpan) + Send + Sync>;
+
 /// Telemetry adapter for provider operations
 pub struct SpanAdapter {
     /// Telemetry collection enabled
@@ -38,12 +75,18 @@ pub struct SpanAdapter {
     environment: String,
     /// Active spans
     active_spans: HashMap<String, ActiveSpan>,
+    /// Callbacks for finished spans
+    listeners: Vec<SpanListener>,
 }
 
 /// Active span tracking
 struct ActiveSpan {
     /// Observatory span
     span: LlmSpan,
+    /// Provider name as given when the span started
+    provider: String,
+    /// What the span measures
+    kind: SpanKind,
     /// Start time for latency calculation
     start_time: Instant,
 }
@@ -61,6 +104,7 @@ impl SpanAdapter {
             enabled: true,
             environment: "production".to_string(),
             active_spans: HashMap::new(),
+            listeners: Vec::new(),
         }
     }
 
@@ -70,6 +114,7 @@ impl SpanAdapter {
             enabled: true,
             environment: env.into(),
             active_spans: HashMap::new(),
+            listeners: Vec::new(),
         }
     }
 
@@ -81,6 +126,26 @@ impl SpanAdapter {
         }
     }
 
+    /// Call `listener` for every span finished from now on
+    ///
+    /// Listeners run while the adapter is borrowed, so they must not lock a
+    /// [`SharedSpanAdapter`] holding this adapter.
+    pub fn add_listener(&mut self, listener: impl Fn(&FinishedSpan) + Send + Sync + 'static) {
+        self.listeners.push(Arc::new(listener));
+    }
+
+    /// Number of spans started but not yet finished
+    pub fn active_span_count(&self) -> usize {
+        self.active_spans.len()
+    }
+
+    /// Events recorded so far on an active span
+    pub fn span_events(&self, span_id: &str) -> Option<&[SpanEvent]> {
+        self.active_spans
+            .get(span_id)
+            .map(|active| active.span.events.as_slice())
+    }
+
     /// Start a new provider operation span
     ///
     /// # Arguments
@@ -163,6 +228,8 @@ impl SpanAdapter {
             span_id.clone(),
             ActiveSpan {
                 span,
+                provider: provider_name.to_string(),
+                kind: SpanKind::Provider,
                 start_time: Instant::now(),
             },
         );
@@ -177,6 +244,21 @@ impl SpanAdapter {
         span_id
     }
 
+    /// Set what an active span measures
+    pub fn set_span_kind(&mut self, span_id: &str, kind: SpanKind) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let active_span = self
+            .active_spans
+            .get_mut(span_id)
+            .ok_or_else(|| ConnectorError::Observatory(format!("Span not found: {}", span_id)))?;
+        active_span.kind = kind;
+
+        Ok(())
+    }
+
     /// Record request input
     pub fn record_request(&mut self, span_id: &str, request: &Value) -> Result<()> {
         if !self.enabled {
@@ -245,7 +327,7 @@ impl SpanAdapter {
         active_span.span.token_usage = Some(TokenUsage {
             prompt_tokens,
             completion_tokens,
-            total_tokens: prompt_tokens + completion_tokens,
+            total_tokens: prompt_tokens.saturating_add(completion_tokens),
         });
 
         Ok(())
@@ -301,6 +383,18 @@ impl SpanAdapter {
             "Finishing provider span"
         );
 
+        let finished = FinishedSpan {
+            span_id: span_id.to_string(),
+            provider: active_span.provider,
+            model: active_span.span.model.clone(),
+            latency: elapsed,
+            success,
+            kind: active_span.kind,
+        };
+        for listener in &self.listeners {
+            listener(&finished);
+        }
+
         let mut span = active_span.span;
 
         // Set final status
@@ -437,6 +531,10 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 100);
         assert_eq!(usage.completion_tokens, 50);
         assert_eq!(usage.total_tokens, 150);
+
+        adapter.record_usage(&span_id, u32::MAX, 50).unwrap();
+        let active_span = adapter.active_spans.get(&span_id).unwrap();
+        assert_eq!(active_span.span.token_usage.as_ref().unwrap().total_tokens, u32::MAX);
     }
 
     #[test]
@@ -491,4 +589,38 @@ mod tests {
         assert_eq!(active_span.span.events.len(), 1);
         assert_eq!(active_span.span.events[0].name, "retry_attempt");
     }
+
+    #[test]
+    fn test_span_listener() {
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let mut adapter = SpanAdapter::new();
+        let sink = finished.clone();
+        adapter.add_listener(move |span| sink.lock().unwrap().push(span.clone()));
+
+        let span_id = adapter.start_provider_span("my-vllm", "llama-3", None);
+        adapter.finish_span(&span_id, false).unwrap();
+
+        let finished = finished.lock().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].span_id, span_id);
+        assert_eq!(finished[0].provider, "my-vllm");
+        assert_eq!(finished[0].model, "llama-3");
+        assert!(!finished[0].success);
+        assert_eq!(finished[0].kind, SpanKind::Provider);
+    }
+
+    #[test]
+    fn test_span_kind() {
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let mut adapter = SpanAdapter::new();
+        let sink = finished.clone();
+        adapter.add_listener(move |span| sink.lock().unwrap().push(span.kind));
+
+        let span_id = adapter.start_provider_span("openai", "gpt-4", None);
+        adapter.set_span_kind(&span_id, SpanKind::Routed).unwrap();
+        adapter.finish_span(&span_id, true).unwrap();
+
+        assert_eq!(*finished.lock().unwrap(), vec![SpanKind::Routed]);
+        assert!(adapter.set_span_kind("missing", SpanKind::Routed).is_err());
+    }
 }
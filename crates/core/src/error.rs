@@ -0,0 +1,336 @@
+//! # Errors
+//!
+//! Errors reported by or on the way to a provider carry a [`ProviderError`]
+//! with the provider name, HTTP status and raw response body, so callers can
+//! tell a rate limit from an authentication failure or a context-length
+//! overflow. [`ConnectorError::is_retryable`] and [`ConnectorError::code`]
+//! classify errors for retry logic and for logs and metrics.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::error::ConnectorError;
+//!
+//! match provider.complete(&request).await {
+//!     Err(e) if e.is_retryable() => {
+//!         let delay = e.retry_after().unwrap_or(Duration::from_secs(1));
+//!         // ... retry after `delay`
+//!     }
+//!     Err(e) => warn!(code = e.code(), error = %e, "Completion failed"),
+//!     Ok(response) => { /* ... */ }
+//! }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Details of an error reported by a provider
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderError {
+    /// Provider name ("openai", "bedrock", ...); empty if not yet known
+    pub provider: String,
+    /// Human-readable message
+    pub message: String,
+    /// HTTP status code, if the error came from an HTTP response
+    pub status: Option<u16>,
+    /// Raw response body, if any
+    pub body: Option<String>,
+}
+
+impl ProviderError {
+    /// Create an error for a provider
+    pub fn new(provider: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+            message: message.into(),
+            status: None,
+            body: None,
+        }
+    }
+
+    /// Set the HTTP status code
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Set the raw response body
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.provider.is_empty() {
+            write!(f, "{}: ", self.provider)?;
+        }
+        f.write_str(&self.message)?;
+        if let Some(status) = self.status {
+            write!(f, " ({})", status)?;
+        }
+        Ok(())
+    }
+}
+
+/// Primary error type for connector hub operations
+#[derive(Error, Debug)]
+pub enum ConnectorError {
+    /// Configuration-related errors
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    /// Schema validation errors from schema-registry-core
+    #[error("Schema validation error: {0}")]
+    Schema(String),
+
+    /// Observability errors from llm-observatory-core
+    #[error("Observability error: {0}")]
+    Observatory(String),
+
+    /// Missing, invalid or insufficient credentials
+    #[error("Authentication failed: {0}")]
+    Authentication(ProviderError),
+
+    /// Provider rate limit exceeded
+    #[error("Rate limited: {error}")]
+    RateLimited {
+        /// Error details
+        error: ProviderError,
+        /// How long the provider asked callers to wait
+        retry_after: Option<Duration>,
+    },
+
+    /// Account quota or credit exhausted; retrying will not help
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(ProviderError),
+
+    /// Provider rejected the request as malformed or invalid
+    #[error("Invalid request: {0}")]
+    InvalidRequest(ProviderError),
+
+    /// Prompt plus requested output exceed the model's context window
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(ProviderError),
+
+    /// Provider blocked the prompt or output (safety, recitation, ...)
+    #[error("Content filtered: {0}")]
+    ContentFiltered(ProviderError),
+
+    /// Model or deployment does not exist
+    #[error("Model not found: {0}")]
+    ModelNotFound(ProviderError),
+
+    /// Provider is overloaded or failing (5xx)
+    #[error("Provider unavailable: {0}")]
+    ProviderUnavailable(ProviderError),
+
+    /// Request did not complete in time
+    #[error("Request timed out: {0}")]
+    Timeout(ProviderError),
+
+    /// Connection could not be established or was lost
+    #[error("Network error: {0}")]
+    Network(ProviderError),
+
+    /// Streaming response ended abnormally after it started
+    #[error("Stream interrupted: {0}")]
+    StreamInterrupted(ProviderError),
+
+    /// Internal system errors
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl ConnectorError {
+    /// Classify a non-2xx HTTP response by status code alone
+    ///
+    /// Used when a provider's error body carries nothing more specific.
+    pub fn from_status(error: ProviderError) -> Self {
+        match error.status {
+            Some(401 | 403) => Self::Authentication(error),
+            Some(404) => Self::ModelNotFound(error),
+            Some(408) => Self::Timeout(error),
+            Some(413) => Self::ContextLengthExceeded(error),
+            Some(429) => Self::RateLimited {
+                error,
+                retry_after: None,
+            },
+            Some(status) if status >= 500 => Self::ProviderUnavailable(error),
+            _ => Self::InvalidRequest(error),
+        }
+    }
+
+    /// Whether the same request may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. }
+                | Self::ProviderUnavailable(_)
+                | Self::Timeout(_)
+                | Self::Network(_)
+                | Self::StreamInterrupted(_)
+        )
+    }
+
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "config",
+            Self::Schema(_) => "schema",
+            Self::Observatory(_) => "observatory",
+            Self::Authentication(_) => "authentication",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::QuotaExceeded(_) => "quota_exceeded",
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::ContextLengthExceeded(_) => "context_length_exceeded",
+            Self::ContentFiltered(_) => "content_filtered",
+            Self::ModelNotFound(_) => "model_not_found",
+            Self::ProviderUnavailable(_) => "provider_unavailable",
+            Self::Timeout(_) => "timeout",
+            Self::Network(_) => "network",
+            Self::StreamInterrupted(_) => "stream_interrupted",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// Provider error details, for errors that carry them
+    pub fn provider_error(&self) -> Option<&ProviderError> {
+        match self {
+            Self::Authentication(error)
+            | Self::RateLimited { error, .. }
+            | Self::QuotaExceeded(error)
+            | Self::InvalidRequest(error)
+            | Self::ContextLengthExceeded(error)
+            | Self::ContentFiltered(error)
+            | Self::ModelNotFound(error)
+            | Self::ProviderUnavailable(error)
+            | Self::Timeout(error)
+            | Self::Network(error)
+            | Self::StreamInterrupted(error) => Some(error),
+            Self::Config(_) | Self::Schema(_) | Self::Observatory(_) | Self::Internal(_) => None,
+        }
+    }
+
+    fn provider_error_mut(&mut self) -> Option<&mut ProviderError> {
+        match self {
+            Self::Authentication(error)
+            | Self::RateLimited { error, .. }
+            | Self::QuotaExceeded(error)
+            | Self::InvalidRequest(error)
+            | Self::ContextLengthExceeded(error)
+            | Self::ContentFiltered(error)
+            | Self::ModelNotFound(error)
+            | Self::ProviderUnavailable(error)
+            | Self::Timeout(error)
+            | Self::Network(error)
+            | Self::StreamInterrupted(error) => Some(error),
+            Self::Config(_) | Self::Schema(_) | Self::Observatory(_) | Self::Internal(_) => None,
+        }
+    }
+
+    /// Name of the provider that reported the error, if known
+    pub fn provider(&self) -> Option<&str> {
+        self.provider_error()
+            .map(|error| error.provider.as_str())
+            .filter(|provider| !provider.is_empty())
+    }
+
+    /// HTTP status code, if the error came from an HTTP response
+    pub fn status(&self) -> Option<u16> {
+        self.provider_error().and_then(|error| error.status)
+    }
+
+    /// Delay requested by a rate-limited provider
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Attribute the error to a provider, unless it already names one
+    ///
+    /// Format decoders shared by several providers report errors without a
+    /// provider name; the provider fills it in.
+    pub fn with_provider(mut self, provider: &str) -> Self {
+        if let Some(error) = self.provider_error_mut() {
+            if error.provider.is_empty() {
+                error.provider = provider.to_string();
+            }
+        }
+        self
+    }
+}
+
+/// Result type alias for connector hub operations
+pub type Result<T> = std::result::Result<T, ConnectorError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_classification() {
+        let cases = [
+            (401, "authentication", false),
+            (403, "authentication", false),
+            (404, "model_not_found", false),
+            (408, "timeout", true),
+            (413, "context_length_exceeded", false),
+            (422, "invalid_request", false),
+            (429, "rate_limited", true),
+            (500, "provider_unavailable", true),
+            (529, "provider_unavailable", true),
+        ];
+        for (status, code, retryable) in cases {
+            let error = ConnectorError::from_status(
+                ProviderError::new("openai", "failed")
+                    .with_status(status)
+                    .with_body("{}"),
+            );
+            assert_eq!(error.code(), code, "status {}", status);
+            assert_eq!(error.is_retryable(), retryable, "status {}", status);
+            assert_eq!(error.status(), Some(status));
+            assert_eq!(error.provider(), Some("openai"));
+            assert_eq!(error.provider_error().unwrap().body.as_deref(), Some("{}"));
+        }
+    }
+
+    #[test]
+    fn test_display_and_accessors() {
+        let error = ConnectorError::RateLimited {
+            error: ProviderError::new("anthropic", "Too many requests").with_status(429),
+            retry_after: Some(Duration::from_secs(3)),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Rate limited: anthropic: Too many requests (429)"
+        );
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(3)));
+
+        let error = ConnectorError::StreamInterrupted(ProviderError::new("", "connection reset"))
+            .with_provider("mistral")
+            .with_provider("openai");
+        assert_eq!(error.provider(), Some("mistral"));
+        assert_eq!(
+            error.to_string(),
+            "Stream interrupted: mistral: connection reset"
+        );
+
+        let error = ConnectorError::QuotaExceeded(
+            ProviderError::new("openai", "You exceeded your current quota").with_status(429),
+        );
+        assert!(!error.is_retryable());
+        assert_eq!(error.code(), "quota_exceeded");
+        assert_eq!(error.status(), Some(429));
+
+        let error = ConnectorError::Config("missing key".to_string());
+        assert!(!error.is_retryable());
+        assert_eq!(error.code(), "config");
+        assert_eq!(error.provider(), None);
+        assert_eq!(error.with_provider("openai").provider(), None);
+    }
+}
@@ -52,51 +52,16 @@ pub use llm_observatory_core;
 pub use schema_registry_core;
 
 /// Core error types for the connector hub
-pub mod error {
-    use thiserror::Error;
-
-    /// Primary error type for connector hub operations
-    #[derive(Error, Debug)]
-    pub enum ConnectorError {
-        /// Configuration-related errors
-        #[error("Configuration error: {0}")]
-        Config(String),
-
-        /// Schema validation errors from schema-registry-core
-        #[error("Schema validation error: {0}")]
-        Schema(String),
-
-        /// Observability errors from llm-observatory-core
-        #[error("Observability error: {0}")]
-        Observatory(String),
-
-        /// Internal system errors
-        #[error("Internal error: {0}")]
-        Internal(String),
-    }
-
-    /// Result type alias for connector hub operations
-    pub type Result<T> = std::result::Result<T, ConnectorError>;
-}
+///
+/// [`ConnectorError`](error::ConnectorError) with structured provider
+/// errors, retryability and stable error codes.
+pub mod error;
 
 /// Core types module
 ///
-/// Phase 2A: Minimal type definitions
-/// Phase 2B: Will be expanded with runtime types and traits
-pub mod types {
-    use serde::{Deserialize, Serialize};
-
-    /// Connector metadata type
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    pub struct ConnectorMetadata {
-        /// Connector name
-        pub name: String,
-        /// Connector version
-        pub version: String,
-        /// Provider type (OpenAI, Anthropic, Google, etc.)
-        pub provider: String,
-    }
-}
+/// Unified request/response data model (`CompletionRequest`, `Message`,
+/// `CompletionResponse`, ...) shared by all providers.
+pub mod types;
 
 /// Phase 2A Verification Module
 ///
@@ -119,6 +84,29 @@ pub mod verification {
 /// - Observability telemetry (llm-observatory-core)
 pub mod adapters;
 
+/// LLM provider abstraction
+///
+/// `Provider` trait and `ProviderRegistry` for performing completions
+/// against LLM backends.
+pub mod providers;
+
+/// Middleware pipeline
+///
+/// Composable async middleware (logging, retries, validation, ...) around
+/// provider completions.
+pub mod middleware;
+
+/// Provider routing
+///
+/// Fallback chains and provider selection driven by routing policies.
+pub mod routing;
+
+/// Streaming building blocks
+///
+/// Incremental decoders for provider streaming wire formats, typed stream
+/// events, and response aggregation.
+pub mod streaming;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,808 @@
+//! Circuit breaking per provider and model.
+//!
+//! Each provider/model pair has its own circuit. While **closed**, calls
+//! pass through and their outcomes are kept over a sliding time window;
+//! when the failure rate or the slow-call rate in the window reaches its
+//! threshold the circuit **opens** and calls are rejected without reaching
+//! the provider. After a cool-down the circuit is **half-open** and lets a
+//! few probe calls through: if they all succeed it closes, if one fails it
+//! opens again.
+//!
+//! Time is measured with `tokio::time`, so tests can run against a paused
+//! clock.
+
+use super::pipeline::{Middleware, Next, RequestContext};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::types::CompletionResponse;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// State of a circuit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls pass through
+    #[default]
+    Closed,
+    /// Calls are rejected
+    Open,
+    /// A limited number of probe calls pass through
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Name used in events and logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half_open",
+        }
+    }
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// When a circuit opens and how it recovers
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerPolicy {
+    /// Fraction of failed calls in the window that opens the circuit
+    pub failure_rate_threshold: f64,
+    /// Fraction of slow calls in the window that opens the circuit
+    pub slow_call_rate_threshold: f64,
+    /// Calls taking at least this long count as slow
+    pub slow_call_duration: Duration,
+    /// Length of the sliding window of call outcomes
+    pub window: Duration,
+    /// Calls needed in the window before the rates are evaluated
+    pub minimum_calls: u32,
+    /// How long the circuit stays open before probing
+    pub open_duration: Duration,
+    /// Successful probes needed to close a half-open circuit
+    pub half_open_probes: u32,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self {
+            failure_rate_threshold: 0.5,
+            slow_call_rate_threshold: 1.0,
+            slow_call_duration: Duration::from_secs(30),
+            window: Duration::from_secs(60),
+            minimum_calls: 10,
+            open_duration: Duration::from_secs(30),
+            half_open_probes: 3,
+        }
+    }
+}
+
+impl CircuitBreakerPolicy {
+    /// Create the default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the failure rate (0.0 to 1.0) that opens the circuit
+    pub fn with_failure_rate_threshold(mut self, threshold: f64) -> Self {
+        self.failure_rate_threshold = threshold;
+        self
+    }
+
+    /// Set the slow-call rate (0.0 to 1.0) that opens the circuit
+    pub fn with_slow_call_rate_threshold(mut self, threshold: f64) -> Self {
+        self.slow_call_rate_threshold = threshold;
+        self
+    }
+
+    /// Set the duration from which a call counts as slow
+    pub fn with_slow_call_duration(mut self, duration: Duration) -> Self {
+        self.slow_call_duration = duration;
+        self
+    }
+
+    /// Set the length of the sliding window
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the number of calls needed before rates are evaluated
+    pub fn with_minimum_calls(mut self, minimum_calls: u32) -> Self {
+        self.minimum_calls = minimum_calls;
+        self
+    }
+
+    /// Set how long the circuit stays open
+    pub fn with_open_duration(mut self, duration: Duration) -> Self {
+        self.open_duration = duration;
+        self
+    }
+
+    /// Set the number of successful probes that close the circuit
+    pub fn with_half_open_probes(mut self, probes: u32) -> Self {
+        self.half_open_probes = probes.max(1);
+        self
+    }
+}
+
+/// A change of circuit state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitTransition {
+    /// Provider name
+    pub provider: String,
+    /// Model name
+    pub model: String,
+    /// State before the change
+    pub from: CircuitState,
+    /// State after the change
+    pub to: CircuitState,
+    /// Failure rate in the window when the change happened
+    pub failure_rate: f64,
+    /// Slow-call rate in the window when the change happened
+    pub slow_call_rate: f64,
+}
+
+impl CircuitTransition {
+    /// Span event attributes
+    pub fn attributes(&self) -> HashMap<String, Value> {
+        HashMap::from([
+            ("provider".to_string(), Value::from(self.provider.as_str())),
+            ("model".to_string(), Value::from(self.model.as_str())),
+            ("from".to_string(), Value::from(self.from.as_str())),
+            ("to".to_string(), Value::from(self.to.as_str())),
+            ("failure_rate".to_string(), Value::from(self.failure_rate)),
+            (
+                "slow_call_rate".to_string(),
+                Value::from(self.slow_call_rate),
+            ),
+        ])
+    }
+
+    fn log(&self) {
+        match self.to {
+            CircuitState::Open => warn!(
+                provider = %self.provider,
+                model = %self.model,
+                from = %self.from,
+                failure_rate = self.failure_rate,
+                slow_call_rate = self.slow_call_rate,
+                "Circuit opened"
+            ),
+            to => info!(
+                provider = %self.provider,
+                model = %self.model,
+                from = %self.from,
+                to = %to,
+                "Circuit state changed"
+            ),
+        }
+    }
+}
+
+/// Point-in-time view of a circuit, for dashboards
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CircuitStatus {
+    /// Provider name
+    pub provider: String,
+    /// Model name
+    pub model: String,
+    /// Current state
+    pub state: CircuitState,
+    /// Calls in the sliding window
+    pub calls: u32,
+    /// Failure rate in the sliding window
+    pub failure_rate: f64,
+    /// Slow-call rate in the sliding window
+    pub slow_call_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Call {
+    at: Instant,
+    failed: bool,
+    slow: bool,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    calls: VecDeque<Call>,
+    opened_at: Instant,
+    /// Incremented on every transition, so outcomes of calls admitted in an
+    /// earlier state are recognised
+    generation: u64,
+    probes_in_flight: u32,
+    probes_succeeded: u32,
+}
+
+impl Circuit {
+    fn new(now: Instant) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            calls: VecDeque::new(),
+            opened_at: now,
+            generation: 0,
+            probes_in_flight: 0,
+            probes_succeeded: 0,
+        }
+    }
+
+    fn evict(&mut self, now: Instant, window: Duration) {
+        while let Some(call) = self.calls.front() {
+            if now.saturating_duration_since(call.at) < window {
+                break;
+            }
+            self.calls.pop_front();
+        }
+    }
+
+    /// Failure and slow-call rates in the window
+    fn rates(&self) -> (f64, f64) {
+        if self.calls.is_empty() {
+            return (0.0, 0.0);
+        }
+        let total = self.calls.len() as f64;
+        let failed = self.calls.iter().filter(|call| call.failed).count() as f64;
+        let slow = self.calls.iter().filter(|call| call.slow).count() as f64;
+        (failed / total, slow / total)
+    }
+
+    fn transition(&mut self, key: &Key, to: CircuitState, now: Instant) -> CircuitTransition {
+        let (failure_rate, slow_call_rate) = self.rates();
+        let transition = CircuitTransition {
+            provider: key.0.clone(),
+            model: key.1.clone(),
+            from: self.state,
+            to,
+            failure_rate,
+            slow_call_rate,
+        };
+        self.state = to;
+        self.generation += 1;
+        self.probes_in_flight = 0;
+        self.probes_succeeded = 0;
+        if to == CircuitState::Open {
+            self.opened_at = now;
+        }
+        if to != CircuitState::HalfOpen {
+            self.calls.clear();
+        }
+        transition.log();
+        transition
+    }
+}
+
+type Key = (String, String);
+
+type Classifier = Arc<dyn Fn(&ConnectorError) -> bool + Send + Sync>;
+
+/// Circuits for every provider/model pair, shared between requests
+///
+/// By default errors count as failures when [`ConnectorError::is_retryable`]:
+/// invalid requests and authentication failures say nothing about the
+/// upstream's health.
+pub struct CircuitBreaker {
+    policy: CircuitBreakerPolicy,
+    classifier: Option<Classifier>,
+    circuits: Mutex<HashMap<Key, Circuit>>,
+}
+
+impl CircuitBreaker {
+    /// Create a circuit breaker with the given policy
+    pub fn new(policy: CircuitBreakerPolicy) -> Self {
+        Self {
+            policy,
+            classifier: None,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Decide which errors count as failures instead of using `is_retryable`
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&ConnectorError) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Circuit breaker policy
+    pub fn policy(&self) -> &CircuitBreakerPolicy {
+        &self.policy
+    }
+
+    /// Current state of a circuit
+    ///
+    /// An open circuit whose cool-down has elapsed reports `HalfOpen`, since
+    /// the next call will be let through as a probe.
+    pub fn state(&self, provider: &str, model: &str) -> CircuitState {
+        let circuits = self.lock();
+        match circuits.get(&(provider.to_string(), model.to_string())) {
+            Some(circuit) => self.effective_state(circuit, Instant::now()),
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Status of every circuit that has seen a call, sorted by provider and
+    /// model
+    pub fn statuses(&self) -> Vec<CircuitStatus> {
+        let now = Instant::now();
+        let mut circuits = self.lock();
+        let mut statuses: Vec<_> = circuits
+            .iter_mut()
+            .map(|((provider, model), circuit)| {
+                circuit.evict(now, self.policy.window);
+                let (failure_rate, slow_call_rate) = circuit.rates();
+                CircuitStatus {
+                    provider: provider.clone(),
+                    model: model.clone(),
+                    state: self.effective_state(circuit, now),
+                    calls: circuit.calls.len() as u32,
+                    failure_rate,
+                    slow_call_rate,
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| (&a.provider, &a.model).cmp(&(&b.provider, &b.model)));
+        statuses
+    }
+
+    /// Ask to make a call, failing with `ProviderUnavailable` if the circuit
+    /// rejects it
+    ///
+    /// The returned permit must be given the call's result with
+    /// [`CallPermit::record`]. A permit dropped without a result does not
+    /// count towards the circuit's rates.
+    pub fn acquire(&self, provider: &str, model: &str) -> Result<CallPermit<'_>> {
+        let now = Instant::now();
+        let key = (provider.to_string(), model.to_string());
+        let mut circuits = self.lock();
+        let circuit = circuits
+            .entry(key.clone())
+            .or_insert_with(|| Circuit::new(now));
+
+        let mut transition = None;
+        if self.effective_state(circuit, now) == CircuitState::HalfOpen
+            && circuit.state == CircuitState::Open
+        {
+            transition = Some(circuit.transition(&key, CircuitState::HalfOpen, now));
+        }
+
+        let probe = match circuit.state {
+            CircuitState::Closed => false,
+            CircuitState::HalfOpen
+                if circuit.probes_in_flight + circuit.probes_succeeded
+                    < self.policy.half_open_probes =>
+            {
+                circuit.probes_in_flight += 1;
+                true
+            }
+            state => {
+                let message = match state {
+                    CircuitState::Open => {
+                        let remaining = (circuit.opened_at + self.policy.open_duration)
+                            .saturating_duration_since(now);
+                        format!(
+                            "circuit open for {}, probing in {}ms",
+                            model,
+                            remaining.as_millis()
+                        )
+                    }
+                    _ => format!("circuit half-open for {}, probes in flight", model),
+                };
+                return Err(ConnectorError::ProviderUnavailable(ProviderError::new(
+                    provider, message,
+                )));
+            }
+        };
+
+        Ok(CallPermit {
+            breaker: self,
+            key,
+            generation: circuit.generation,
+            probe,
+            started: now,
+            transition,
+            finished: false,
+        })
+    }
+
+    fn effective_state(&self, circuit: &Circuit, now: Instant) -> CircuitState {
+        match circuit.state {
+            CircuitState::Open
+                if now.saturating_duration_since(circuit.opened_at)
+                    >= self.policy.open_duration =>
+            {
+                CircuitState::HalfOpen
+            }
+            state => state,
+        }
+    }
+
+    fn is_failure(&self, error: &ConnectorError) -> bool {
+        match &self.classifier {
+            Some(classifier) => classifier(error),
+            None => error.is_retryable(),
+        }
+    }
+
+    /// Record the outcome of a permitted call
+    fn record(&self, permit: &CallPermit<'_>, failed: bool) -> Option<CircuitTransition> {
+        let now = Instant::now();
+        let slow = now.saturating_duration_since(permit.started) >= self.policy.slow_call_duration;
+        let mut circuits = self.lock();
+        let circuit = circuits.get_mut(&permit.key)?;
+        if circuit.generation != permit.generation {
+            return None;
+        }
+
+        match circuit.state {
+            CircuitState::Closed => {
+                circuit.calls.push_back(Call {
+                    at: now,
+                    failed,
+                    slow,
+                });
+                circuit.evict(now, self.policy.window);
+                if circuit.calls.len() < self.policy.minimum_calls as usize {
+                    return None;
+                }
+                let (failure_rate, slow_call_rate) = circuit.rates();
+                if failure_rate >= self.policy.failure_rate_threshold
+                    || slow_call_rate >= self.policy.slow_call_rate_threshold
+                {
+                    return Some(circuit.transition(&permit.key, CircuitState::Open, now));
+                }
+                None
+            }
+            CircuitState::HalfOpen => {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+                circuit.calls.push_back(Call {
+                    at: now,
+                    failed,
+                    slow,
+                });
+                if failed || slow {
+                    return Some(circuit.transition(&permit.key, CircuitState::Open, now));
+                }
+                circuit.probes_succeeded += 1;
+                if circuit.probes_succeeded >= self.policy.half_open_probes {
+                    return Some(circuit.transition(&permit.key, CircuitState::Closed, now));
+                }
+                None
+            }
+            CircuitState::Open => None,
+        }
+    }
+
+    /// Return an unused probe slot
+    fn release(&self, permit: &CallPermit<'_>) {
+        let mut circuits = self.lock();
+        if let Some(circuit) = circuits.get_mut(&permit.key) {
+            if circuit.generation == permit.generation {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Key, Circuit>> {
+        self.circuits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(CircuitBreakerPolicy::default())
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("policy", &self.policy)
+            .field("classifier", &self.classifier.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Permission to make one call through a circuit
+#[derive(Debug)]
+pub struct CallPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    key: Key,
+    generation: u64,
+    probe: bool,
+    started: Instant,
+    transition: Option<CircuitTransition>,
+    finished: bool,
+}
+
+impl CallPermit<'_> {
+    /// Whether the call is a half-open probe
+    pub fn is_probe(&self) -> bool {
+        self.probe
+    }
+
+    /// Transition caused by granting the permit (open to half-open), if any
+    pub fn take_transition(&mut self) -> Option<CircuitTransition> {
+        self.transition.take()
+    }
+
+    /// Record the call's result, returning the transition it caused, if any
+    pub fn record<T>(mut self, result: &Result<T>) -> Option<CircuitTransition> {
+        self.finished = true;
+        let failed = match result {
+            Ok(_) => false,
+            Err(e) => self.breaker.is_failure(e),
+        };
+        self.breaker.record(&self, failed)
+    }
+}
+
+impl Drop for CallPermit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.finished {
+            self.breaker.release(self);
+        }
+    }
+}
+
+/// Runs completions through a shared [`CircuitBreaker`]
+///
+/// State transitions are recorded as `circuit_state_change` events on the
+/// request's span, with the provider, model, `from` and `to` states and the
+/// window's failure and slow-call rates.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerMiddleware {
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl CircuitBreakerMiddleware {
+    /// Create a middleware using the given circuits
+    pub fn new(breaker: Arc<CircuitBreaker>) -> Self {
+        Self { breaker }
+    }
+
+    /// Shared circuits
+    pub fn breaker(&self) -> &Arc<CircuitBreaker> {
+        &self.breaker
+    }
+}
+
+fn record_transition(context: &RequestContext, transition: Option<CircuitTransition>) {
+    if let Some(transition) = transition {
+        context.record_event("circuit_state_change", transition.attributes());
+    }
+}
+
+#[async_trait]
+impl Middleware for CircuitBreakerMiddleware {
+    fn name(&self) -> &str {
+        "circuit_breaker"
+    }
+
+    async fn handle(
+        &self,
+        context: &mut RequestContext,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse> {
+        let model = context.request.model.clone();
+        let mut permit = self.breaker.acquire(&context.provider, &model)?;
+        record_transition(context, permit.take_transition());
+
+        let result = next.run(context).await;
+        record_transition(context, permit.record(&result));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use crate::middleware::Pipeline;
+    use crate::providers::mock::{MockFailure, MockProvider};
+    use crate::types::{CompletionRequest, Message};
+
+    fn request(model: &str) -> CompletionRequest {
+        CompletionRequest::new(model, vec![Message::user("Hello")])
+    }
+
+    fn policy() -> CircuitBreakerPolicy {
+        CircuitBreakerPolicy::new()
+            .with_minimum_calls(4)
+            .with_open_duration(Duration::from_secs(10))
+            .with_half_open_probes(2)
+    }
+
+    fn failure() -> Result<()> {
+        Err(ConnectorError::ProviderUnavailable(ProviderError::new(
+            "openai", "down",
+        )))
+    }
+
+    fn call(breaker: &CircuitBreaker, model: &str, result: Result<()>) -> Option<CircuitState> {
+        let permit = breaker.acquire("openai", model).unwrap();
+        permit.record(&result).map(|transition| transition.to)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_opens_on_failure_rate() {
+        let breaker = CircuitBreaker::new(policy());
+
+        // Rates are not evaluated before the minimum number of calls
+        assert_eq!(call(&breaker, "gpt-4", failure()), None);
+        assert_eq!(call(&breaker, "gpt-4", failure()), None);
+        assert_eq!(call(&breaker, "gpt-4", Ok(())), None);
+        assert_eq!(call(&breaker, "gpt-4", Ok(())), Some(CircuitState::Open));
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::Open);
+
+        let error = breaker.acquire("openai", "gpt-4").unwrap_err();
+        assert_eq!(error.code(), "provider_unavailable");
+        assert!(error.to_string().contains("probing in 10000ms"));
+
+        // Other models have their own circuit
+        assert_eq!(
+            breaker.state("openai", "gpt-3.5-turbo"),
+            CircuitState::Closed
+        );
+        assert_eq!(call(&breaker, "gpt-3.5-turbo", Ok(())), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sliding_window() {
+        let breaker = CircuitBreaker::new(policy().with_window(Duration::from_secs(30)));
+
+        for _ in 0..3 {
+            call(&breaker, "gpt-4", failure());
+        }
+        tokio::time::advance(Duration::from_secs(30)).await;
+
+        // The failures have left the window
+        for _ in 0..3 {
+            assert_eq!(call(&breaker, "gpt-4", Ok(())), None);
+        }
+        assert_eq!(call(&breaker, "gpt-4", failure()), None);
+        let status = &breaker.statuses()[0];
+        assert_eq!(status.calls, 4);
+        assert_eq!(status.failure_rate, 0.25);
+        assert_eq!(status.state, CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_opens_on_slow_calls() {
+        let breaker = CircuitBreaker::new(
+            policy()
+                .with_slow_call_duration(Duration::from_secs(5))
+                .with_slow_call_rate_threshold(0.75),
+        );
+
+        for _ in 0..3 {
+            let permit = breaker.acquire("openai", "gpt-4").unwrap();
+            tokio::time::advance(Duration::from_secs(5)).await;
+            assert_eq!(permit.record(&Ok(())), None);
+        }
+        let transition = breaker
+            .acquire("openai", "gpt-4")
+            .unwrap()
+            .record(&Ok(()))
+            .unwrap();
+        assert_eq!(transition.to, CircuitState::Open);
+        assert_eq!(transition.slow_call_rate, 0.75);
+        assert_eq!(transition.failure_rate, 0.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_half_open_probes() {
+        let breaker = CircuitBreaker::new(policy());
+        for _ in 0..4 {
+            call(&breaker, "gpt-4", failure());
+        }
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::HalfOpen);
+
+        // Two probes at a time; a failed probe reopens the circuit
+        let mut first = breaker.acquire("openai", "gpt-4").unwrap();
+        assert!(first.is_probe());
+        assert_eq!(
+            first.take_transition().map(|t| (t.from, t.to)),
+            Some((CircuitState::Open, CircuitState::HalfOpen))
+        );
+        let second = breaker.acquire("openai", "gpt-4").unwrap();
+        assert!(breaker.acquire("openai", "gpt-4").is_err());
+        assert_eq!(first.record(&Ok(())), None);
+        assert_eq!(
+            second.record(&failure()).map(|t| t.to),
+            Some(CircuitState::Open)
+        );
+        assert!(breaker.acquire("openai", "gpt-4").is_err());
+
+        // Abandoned probes free their slot
+        tokio::time::advance(Duration::from_secs(10)).await;
+        drop(breaker.acquire("openai", "gpt-4").unwrap());
+        assert_eq!(call(&breaker, "gpt-4", Ok(())), None);
+        assert_eq!(call(&breaker, "gpt-4", Ok(())), Some(CircuitState::Closed));
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ignores_client_errors() {
+        let breaker = CircuitBreaker::new(policy());
+        let invalid = || -> Result<()> {
+            Err(ConnectorError::InvalidRequest(ProviderError::new(
+                "openai", "bad",
+            )))
+        };
+        for _ in 0..8 {
+            assert_eq!(call(&breaker, "gpt-4", invalid()), None);
+        }
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::Closed);
+
+        let breaker = CircuitBreaker::new(policy()).with_classifier(|_| true);
+        for _ in 0..4 {
+            call(&breaker, "gpt-4", invalid());
+        }
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_middleware_records_transitions() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let breaker = Arc::new(CircuitBreaker::new(
+            policy().with_minimum_calls(2).with_half_open_probes(1),
+        ));
+        let pipeline = Pipeline::new().with(CircuitBreakerMiddleware::new(breaker.clone()));
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::ServerError);
+
+        for _ in 0..3 {
+            let mut context = RequestContext::new("openai", request("gpt-4"));
+            let _ = pipeline.execute(&provider, &mut context).await;
+        }
+        assert_eq!(provider.call_count(), 2);
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::Open);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        let span_id = adapter
+            .lock()
+            .unwrap()
+            .start_provider_span("openai", "gpt-4", None);
+        let mut context = RequestContext::new("openai", request("gpt-4"))
+            .with_span(adapter.clone(), span_id.clone());
+        pipeline.execute(&provider, &mut context).await.unwrap();
+        assert_eq!(breaker.state("openai", "gpt-4"), CircuitState::Closed);
+
+        let adapter = adapter.lock().unwrap();
+        let events = adapter.span_events(&span_id).unwrap();
+        let states: Vec<_> = events
+            .iter()
+            .filter(|event| event.name == "circuit_state_change")
+            .map(|event| {
+                (
+                    event.attributes["from"].clone(),
+                    event.attributes["to"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (Value::from("open"), Value::from("half_open")),
+                (Value::from("half_open"), Value::from("closed")),
+            ]
+        );
+        assert_eq!(events[0].attributes["model"], Value::from("gpt-4"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Request/response logging.
+
+use super::pipeline::{Middleware, Next, RequestContext};
+use crate::error::Result;
+use crate::types::CompletionResponse;
+use async_trait::async_trait;
+use tracing::{debug, info, warn};
+
+/// Logs each completion through `tracing`
+///
+/// Requests are logged at debug level, completions at info level with
+/// latency and token usage, and failures at warn level with the error code.
+#[derive(Debug, Clone, Default)]
+pub struct LoggingMiddleware;
+
+impl LoggingMiddleware {
+    /// Create a logging middleware
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Middleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn handle(
+        &self,
+        context: &mut RequestContext,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse> {
+        debug!(
+            provider = %context.provider,
+            model = %context.request.model,
+            messages = context.request.messages.len(),
+            "Sending completion request"
+        );
+
+        let result = next.run(context).await;
+        let latency_ms = context.elapsed().as_millis() as u64;
+        match &result {
+            Ok(response) => info!(
+                provider = %context.provider,
+                model = %response.model,
+                latency_ms = latency_ms,
+                attempts = context.attempt,
+                prompt_tokens = response.usage.prompt_tokens,
+                completion_tokens = response.usage.completion_tokens,
+                "Completion succeeded"
+            ),
+            Err(e) => warn!(
+                provider = %context.provider,
+                model = %context.request.model,
+                latency_ms = latency_ms,
+                attempts = context.attempt,
+                code = e.code(),
+                error = %e,
+                "Completion failed"
+            ),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::pipeline::Pipeline;
+    use crate::providers::mock::{MockFailure, MockProvider};
+    use crate::types::{CompletionRequest, Message};
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tracing::Level;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Captured {
+        fn output(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture() -> (Captured, tracing::subscriber::DefaultGuard) {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        (captured, tracing::subscriber::set_default(subscriber))
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("mock-model", vec![Message::user("Hello")])
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_logs_success_with_latency() {
+        let (captured, _guard) = capture();
+        let provider = MockProvider::new("openai")
+            .with_latency(Duration::from_millis(250))
+            .respond_with_text("Hi");
+        let pipeline = Pipeline::new().with(LoggingMiddleware::new());
+
+        let mut context = RequestContext::new("openai", request());
+        let response = pipeline.execute(&provider, &mut context).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hi"));
+        assert_eq!(context.elapsed(), Duration::from_millis(250));
+
+        let output = captured.output();
+        assert!(output.contains("Sending completion request"));
+        assert!(output.contains("Completion succeeded"));
+        assert!(output.contains("latency_ms=250"));
+        assert!(output.contains("attempts=1"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_logs_failure_with_code() {
+        let (captured, _guard) = capture();
+        let provider = MockProvider::new("openai").fail_with(MockFailure::ServerError);
+        let pipeline = Pipeline::new().with(LoggingMiddleware::new());
+
+        let mut context = RequestContext::new("openai", request());
+        let error = pipeline.execute(&provider, &mut context).await.unwrap_err();
+
+        let output = captured.output();
+        assert!(output.contains("WARN"));
+        assert!(output.contains("Completion failed"));
+        assert!(output.contains(error.code()));
+    }
+}
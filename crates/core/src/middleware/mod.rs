@@ -0,0 +1,53 @@
+//! # Middleware
+//!
+//! Composable async middleware around provider completions.
+//!
+//! A [`Pipeline`] runs a chain of [`Middleware`] around a
+//! [`Provider`](crate::providers::Provider) call. Each middleware receives
+//! the per-request [`RequestContext`] and a [`Next`] handle for the rest of
+//! the chain, so it can:
+//!
+//! - rewrite the request before calling `next`
+//! - rewrite the response after `next` returns
+//! - intercept and replace errors returned by `next`
+//! - short-circuit by returning without calling `next`
+//! - call `next` more than once, e.g. to retry
+//!
+//! Middleware runs in the order it was added: the first middleware is the
+//! outermost, sees the request first and the response last.
+//!
+//! - [`pipeline`] - `Middleware`, `Next`, `RequestContext` and `Pipeline`
+//! - [`circuit_breaker`] - circuit breaking per provider and model
+//! - [`logging`] - request/response logging through `tracing`
+//! - [`rate_limit`] - client-side request and token rate limits
+//! - [`retry`] - retries with exponential backoff and jitter
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::middleware::{LoggingMiddleware, Pipeline, RetryMiddleware, RetryPolicy};
+//!
+//! let provider = Pipeline::new()
+//!     .with_telemetry(span_adapter)
+//!     .with(LoggingMiddleware::new())
+//!     .with(RetryMiddleware::new(RetryPolicy::new().with_max_attempts(4)))
+//!     .with(MyValidation::new())
+//!     .wrap(Arc::new(OpenAIProvider::from_config(&mut config)?));
+//!
+//! registry.register(Arc::new(provider));
+//! ```
+
+pub mod circuit_breaker;
+pub mod logging;
+pub mod pipeline;
+pub mod rate_limit;
+pub mod retry;
+
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerMiddleware, CircuitBreakerPolicy, CircuitState, CircuitStatus,
+    CircuitTransition,
+};
+pub use logging::LoggingMiddleware;
+pub use pipeline::{Middleware, Next, Pipeline, PipelineProvider, RequestContext};
+pub use rate_limit::{RateLimit, RateLimitMiddleware, RateLimitMode, RateLimiter};
+pub use retry::{Jitter, RetryMiddleware, RetryPolicy};
@@ -0,0 +1,639 @@
+//! Middleware chain execution.
+
+use crate::adapters::telemetry::{SharedSpanAdapter, SpanKind};
+use crate::error::Result;
+use crate::providers::http::ProviderTelemetry;
+use crate::providers::{HealthStatus, Provider, ProviderCapabilities, ProviderStream};
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Per-request state shared by the middleware handling a completion
+#[derive(Clone)]
+pub struct RequestContext {
+    /// Request sent to the provider; middleware may rewrite it
+    pub request: CompletionRequest,
+    /// Name of the provider handling the request
+    pub provider: String,
+    /// Span that middleware should record events on, if any
+    pub span_id: Option<String>,
+    /// Number of provider calls made so far
+    pub attempt: u32,
+    /// When the pipeline started handling the request
+    pub started_at: Instant,
+    /// Values set by middleware for later middleware or the caller
+    pub metadata: HashMap<String, Value>,
+    telemetry: Option<SharedSpanAdapter>,
+}
+
+impl RequestContext {
+    /// Create a context for a request to the named provider
+    pub fn new(provider: impl Into<String>, request: CompletionRequest) -> Self {
+        Self {
+            request,
+            provider: provider.into(),
+            span_id: None,
+            attempt: 0,
+            started_at: Instant::now(),
+            metadata: HashMap::new(),
+            telemetry: None,
+        }
+    }
+
+    /// Record middleware events on an active span of the given adapter
+    pub fn with_span(mut self, adapter: SharedSpanAdapter, span_id: impl Into<String>) -> Self {
+        self.telemetry = Some(adapter);
+        self.span_id = Some(span_id.into());
+        self
+    }
+
+    /// Record an event on the request's span, if it has one
+    ///
+    /// Failures are logged rather than returned: telemetry must not fail
+    /// the request.
+    pub fn record_event(&self, name: &str, attributes: HashMap<String, Value>) {
+        let (Some(adapter), Some(span_id)) = (&self.telemetry, &self.span_id) else {
+            return;
+        };
+        let Ok(mut adapter) = adapter.lock() else {
+            return;
+        };
+        if let Err(e) = adapter.record_event(span_id, name, attributes) {
+            warn!(span_id = %span_id, event = name, error = %e, "Failed to record span event");
+        }
+    }
+
+    /// Time since the pipeline started handling the request
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+impl fmt::Debug for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestContext")
+            .field("request", &self.request)
+            .field("provider", &self.provider)
+            .field("span_id", &self.span_id)
+            .field("attempt", &self.attempt)
+            .field("started_at", &self.started_at)
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Async middleware around a provider completion
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Middleware name, used to find and remove it in a [`Pipeline`]
+    fn name(&self) -> &str;
+
+    /// Handle a request
+    ///
+    /// Call `next.run(context)` to continue down the chain, possibly more
+    /// than once, or return without calling it to short-circuit.
+    async fn handle(
+        &self,
+        context: &mut RequestContext,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse>;
+}
+
+/// The rest of a middleware chain, ending at the provider
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn Middleware>],
+    provider: &'a dyn Provider,
+}
+
+impl<'a> Next<'a> {
+    /// Run the remaining middleware, then the provider
+    ///
+    /// The provider is called with `context.request` as it is by then, and
+    /// `context.attempt` is incremented before each call.
+    pub async fn run(self, context: &mut RequestContext) -> Result<CompletionResponse> {
+        match self.middleware.split_first() {
+            Some((current, rest)) => {
+                let next = Next {
+                    middleware: rest,
+                    provider: self.provider,
+                };
+                current.handle(context, next).await
+            }
+            None => {
+                context.attempt += 1;
+                self.provider.complete(&context.request).await
+            }
+        }
+    }
+
+    /// Provider at the end of the chain
+    pub fn provider(&self) -> &'a dyn Provider {
+        self.provider
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("middleware", &names(self.middleware))
+            .field("provider", &self.provider.name())
+            .finish()
+    }
+}
+
+/// Ordered chain of middleware
+///
+/// The first middleware added is the outermost.
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    middleware: Vec<Arc<dyn Middleware>>,
+    telemetry: Option<SharedSpanAdapter>,
+}
+
+impl Pipeline {
+    /// Create an empty pipeline
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report each request on a span that middleware can record events on
+    ///
+    /// The span covers the whole chain, including every provider call made
+    /// by retries or fallbacks, so it is marked [`SpanKind::Routed`] and
+    /// latency trackers ignore it. Contexts that already have a span keep it.
+    pub fn with_telemetry(mut self, adapter: SharedSpanAdapter) -> Self {
+        self.telemetry = Some(adapter);
+        self
+    }
+
+    /// Add a middleware inside those already added
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.push(Arc::new(middleware));
+        self
+    }
+
+    /// Add a shared middleware inside those already added
+    pub fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Remove the first middleware with the given name
+    pub fn remove(&mut self, name: &str) -> Option<Arc<dyn Middleware>> {
+        let index = self.middleware.iter().position(|m| m.name() == name)?;
+        Some(self.middleware.remove(index))
+    }
+
+    /// Whether a middleware with the given name is present
+    pub fn contains(&self, name: &str) -> bool {
+        self.middleware.iter().any(|m| m.name() == name)
+    }
+
+    /// Middleware names, outermost first
+    pub fn names(&self) -> Vec<&str> {
+        names(&self.middleware)
+    }
+
+    /// Number of middleware
+    pub fn len(&self) -> usize {
+        self.middleware.len()
+    }
+
+    /// Whether the pipeline has no middleware
+    pub fn is_empty(&self) -> bool {
+        self.middleware.is_empty()
+    }
+
+    /// Run a request through the chain and the provider
+    pub async fn execute(
+        &self,
+        provider: &dyn Provider,
+        context: &mut RequestContext,
+    ) -> Result<CompletionResponse> {
+        let next = Next {
+            middleware: &self.middleware,
+            provider,
+        };
+        let Some(adapter) = self
+            .telemetry
+            .as_ref()
+            .filter(|_| context.span_id.is_none())
+        else {
+            return next.run(context).await;
+        };
+
+        let telemetry = ProviderTelemetry::new(adapter.clone()).with_kind(SpanKind::Routed);
+        let request = serde_json::to_value(&context.request).unwrap_or_default();
+        let span_id = telemetry.start(&context.provider, &context.request.model, &request);
+        if span_id.is_some() {
+            context.telemetry = Some(adapter.clone());
+            context.span_id = span_id.clone();
+        }
+
+        let result = next.run(context).await;
+        // The span is finished below, so nothing more can be recorded on it
+        context.telemetry = None;
+        context.span_id = None;
+        match &result {
+            Ok(response) => {
+                let raw = serde_json::to_value(response).unwrap_or_default();
+                telemetry.succeed(span_id, &raw, &response.usage);
+            }
+            Err(_) => telemetry.fail(span_id),
+        }
+        result
+    }
+
+    /// Run a request with a fresh context
+    pub async fn complete(
+        &self,
+        provider: &dyn Provider,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse> {
+        let mut context = RequestContext::new(provider.name(), request.clone());
+        self.execute(provider, &mut context).await
+    }
+
+    /// Wrap a provider so every completion runs through this pipeline
+    pub fn wrap(self, provider: Arc<dyn Provider>) -> PipelineProvider {
+        PipelineProvider {
+            pipeline: self,
+            inner: provider,
+        }
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("middleware", &self.names())
+            .finish()
+    }
+}
+
+fn names(middleware: &[Arc<dyn Middleware>]) -> Vec<&str> {
+    middleware.iter().map(|m| m.name()).collect()
+}
+
+/// Provider whose completions run through a [`Pipeline`]
+///
+/// Streams, model listing and health checks go straight to the inner
+/// provider.
+#[derive(Clone)]
+pub struct PipelineProvider {
+    pipeline: Pipeline,
+    inner: Arc<dyn Provider>,
+}
+
+impl PipelineProvider {
+    /// Pipeline applied to completions
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    /// Wrapped provider
+    pub fn inner(&self) -> &Arc<dyn Provider> {
+        &self.inner
+    }
+}
+
+impl fmt::Debug for PipelineProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineProvider")
+            .field("pipeline", &self.pipeline)
+            .field("inner", &self.inner.name())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Provider for PipelineProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        self.pipeline.complete(self.inner.as_ref(), request).await
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        self.inner.stream(request).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.inner.list_models().await
+    }
+
+    async fn health_check(&self) -> Result<HealthStatus> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use crate::error::ConnectorError;
+    use crate::providers::mock::{MockFailure, MockProvider};
+    use crate::types::Message;
+    use std::sync::Mutex;
+
+    /// Records when it sees the request and the response
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Middleware for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn handle(
+            &self,
+            context: &mut RequestContext,
+            next: Next<'_>,
+        ) -> Result<CompletionResponse> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} request", self.name));
+            context
+                .metadata
+                .insert(self.name.to_string(), Value::from(context.attempt));
+            let response = next.run(context).await;
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} response", self.name));
+            response
+        }
+    }
+
+    /// Rewrites the model on the way in and the response id on the way out
+    struct Rewrite;
+
+    #[async_trait]
+    impl Middleware for Rewrite {
+        fn name(&self) -> &str {
+            "rewrite"
+        }
+
+        async fn handle(
+            &self,
+            context: &mut RequestContext,
+            next: Next<'_>,
+        ) -> Result<CompletionResponse> {
+            context.request.model = "rewritten-model".to_string();
+            let mut response = next.run(context).await?;
+            response.id = format!("{}-rewritten", response.id);
+            Ok(response)
+        }
+    }
+
+    /// Answers from a fixed response without calling the provider
+    struct Cached(CompletionResponse);
+
+    #[async_trait]
+    impl Middleware for Cached {
+        fn name(&self) -> &str {
+            "cache"
+        }
+
+        async fn handle(
+            &self,
+            _context: &mut RequestContext,
+            _next: Next<'_>,
+        ) -> Result<CompletionResponse> {
+            Ok(self.0.clone())
+        }
+    }
+
+    /// Retries retryable errors once, then replaces them with a canned reply
+    struct RetryOnceThenFallback;
+
+    #[async_trait]
+    impl Middleware for RetryOnceThenFallback {
+        fn name(&self) -> &str {
+            "retry"
+        }
+
+        async fn handle(
+            &self,
+            context: &mut RequestContext,
+            next: Next<'_>,
+        ) -> Result<CompletionResponse> {
+            match next.run(context).await {
+                Err(e) if e.is_retryable() => match next.run(context).await {
+                    Err(e) if e.is_retryable() => {
+                        let mut response = MockProvider::new("fallback")
+                            .complete(&context.request)
+                            .await?;
+                        response.id = "fallback".to_string();
+                        Ok(response)
+                    }
+                    result => result,
+                },
+                result => result,
+            }
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("mock-model", vec![Message::user("Hello")])
+    }
+
+    fn recorder(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Recorder {
+        Recorder {
+            name,
+            log: log.clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let pipeline = Pipeline::new()
+            .with(recorder("outer", &log))
+            .with(recorder("inner", &log));
+        assert_eq!(pipeline.names(), vec!["outer", "inner"]);
+
+        let provider = MockProvider::new("openai").respond_with_text("Hi");
+        let mut context = RequestContext::new("openai", request());
+        let response = pipeline.execute(&provider, &mut context).await.unwrap();
+
+        assert_eq!(response.text().as_deref(), Some("Hi"));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "outer request",
+                "inner request",
+                "inner response",
+                "outer response"
+            ]
+        );
+        assert_eq!(context.attempt, 1);
+        assert_eq!(context.metadata["outer"], Value::from(0));
+        assert_eq!(context.metadata["inner"], Value::from(0));
+    }
+
+    #[tokio::test]
+    async fn test_request_and_response_rewriting() {
+        let provider = Arc::new(MockProvider::new("openai").respond_with_text("Hi"));
+        let wrapped = Pipeline::new().with(Rewrite).wrap(provider.clone());
+
+        let response = wrapped.complete(&request()).await.unwrap();
+        assert!(response.id.ends_with("-rewritten"));
+        assert_eq!(provider.requests()[0].model, "rewritten-model");
+        assert_eq!(wrapped.name(), "openai");
+    }
+
+    #[tokio::test]
+    async fn test_short_circuit() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let cached = MockProvider::new("cache")
+            .complete(&request())
+            .await
+            .unwrap();
+        let pipeline = Pipeline::new()
+            .with(recorder("outer", &log))
+            .with(Cached(cached.clone()))
+            .with(recorder("inner", &log));
+
+        let provider = MockProvider::new("openai");
+        let mut context = RequestContext::new("openai", request());
+        let response = pipeline.execute(&provider, &mut context).await.unwrap();
+
+        assert_eq!(response, cached);
+        assert_eq!(provider.call_count(), 0);
+        assert_eq!(context.attempt, 0);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer request", "outer response"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_interception() {
+        let pipeline = Pipeline::new().with(RetryOnceThenFallback);
+
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::ServerError)
+            .respond_with_text("second try");
+        let mut context = RequestContext::new("openai", request());
+        let response = pipeline.execute(&provider, &mut context).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("second try"));
+        assert_eq!(context.attempt, 2);
+
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::RateLimited)
+            .fail_with(MockFailure::ServerError);
+        let response = pipeline.complete(&provider, &request()).await.unwrap();
+        assert_eq!(response.id, "fallback");
+
+        let provider = MockProvider::new("openai").fail_with(MockFailure::Status {
+            status: 401,
+            body: "{}".to_string(),
+        });
+        let error = pipeline.complete(&provider, &request()).await.unwrap_err();
+        assert!(matches!(error, ConnectorError::Authentication(_)));
+        assert_eq!(provider.call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_span() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+
+        struct SpanProbe(SharedSpanAdapter);
+
+        #[async_trait]
+        impl Middleware for SpanProbe {
+            fn name(&self) -> &str {
+                "span-probe"
+            }
+
+            async fn handle(
+                &self,
+                context: &mut RequestContext,
+                next: Next<'_>,
+            ) -> Result<CompletionResponse> {
+                context.record_event("probe", HashMap::new());
+                let span_id = context.span_id.clone().unwrap();
+                let events = self.0.lock().unwrap().span_events(&span_id).unwrap().len();
+                context
+                    .metadata
+                    .insert("events".to_string(), Value::from(events));
+                next.run(context).await
+            }
+        }
+
+        let pipeline = Pipeline::new()
+            .with_telemetry(adapter.clone())
+            .with(SpanProbe(adapter.clone()));
+        let provider = MockProvider::new("openai");
+        let mut context = RequestContext::new("openai", request());
+        pipeline.execute(&provider, &mut context).await.unwrap();
+
+        assert_eq!(context.metadata["events"], Value::from(1));
+        assert_eq!(context.span_id, None);
+        assert_eq!(adapter.lock().unwrap().active_span_count(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pipeline_span_is_not_tracked_as_latency() {
+        use crate::middleware::{RetryMiddleware, RetryPolicy};
+        use crate::routing::LatencyTracker;
+
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let tracker = Arc::new(LatencyTracker::new());
+        let observer = tracker.clone();
+        adapter
+            .lock()
+            .unwrap()
+            .add_listener(move |span| observer.observe_span(span));
+
+        let pipeline = Pipeline::new()
+            .with_telemetry(adapter.clone())
+            .with(RetryMiddleware::new(
+                RetryPolicy::new().with_max_attempts(3),
+            ));
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::ServerError)
+            .respond_with_text("Hi");
+        pipeline.complete(&provider, &request()).await.unwrap();
+
+        assert_eq!(provider.call_count(), 2);
+        // One span covering both attempts and the backoff is not a sample
+        assert_eq!(tracker.sample_count("openai", None), 0);
+    }
+
+    #[test]
+    fn test_pipeline_management() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut pipeline = Pipeline::new()
+            .with(recorder("a", &log))
+            .with(Rewrite)
+            .with(recorder("b", &log));
+        assert_eq!(pipeline.len(), 3);
+        assert!(pipeline.contains("rewrite"));
+
+        assert_eq!(pipeline.remove("rewrite").unwrap().name(), "rewrite");
+        assert!(pipeline.remove("rewrite").is_none());
+        assert_eq!(pipeline.names(), vec!["a", "b"]);
+        assert_eq!(
+            format!("{:?}", pipeline),
+            r#"Pipeline { middleware: ["a", "b"] }"#
+        );
+    }
+}
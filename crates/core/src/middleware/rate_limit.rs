@@ -0,0 +1,560 @@
+//! Client-side rate limiting.
+//!
+//! Limits are enforced with GCRA (the generic cell rate algorithm), which
+//! behaves like a token bucket refilled continuously: a limit of `N` per
+//! minute allows a burst of `N`, then one unit every `60s / N`. Requests
+//! and tokens are budgeted separately, per provider and optionally per
+//! model; a request must fit every budget that applies to it.
+//!
+//! Waiting requests reserve their slot when they are admitted to the queue,
+//! so they are served in arrival order. Time is measured with `tokio::time`,
+//! so tests can run against a paused clock.
+
+use super::pipeline::{Middleware, Next, RequestContext};
+use crate::adapters::config::{ConfigAdapter, RoutingPolicy};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+const PERIOD: Duration = Duration::from_secs(60);
+
+/// Per-minute request and token budgets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Maximum requests per minute
+    pub requests_per_minute: Option<u32>,
+    /// Maximum tokens (prompt and completion) per minute
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    /// Create an unlimited budget
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit requests per minute
+    pub fn with_requests_per_minute(mut self, limit: u32) -> Self {
+        self.requests_per_minute = Some(limit);
+        self
+    }
+
+    /// Limit tokens per minute
+    pub fn with_tokens_per_minute(mut self, limit: u32) -> Self {
+        self.tokens_per_minute = Some(limit);
+        self
+    }
+
+    /// Budgets from a routing policy's `rate_limit` and `token_rate_limit`
+    pub fn from_policy(policy: &RoutingPolicy) -> Self {
+        Self {
+            requests_per_minute: policy.rate_limit,
+            tokens_per_minute: policy.token_rate_limit,
+        }
+    }
+
+    /// Whether neither budget is limited
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+}
+
+/// What to do with a request that is over its budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// Fail immediately with `RateLimited`, with the wait as `retry_after`
+    FailFast,
+    /// Wait for capacity, failing if the wait would exceed `max_wait`
+    Queue {
+        /// Longest acceptable wait; unbounded if `None`
+        max_wait: Option<Duration>,
+    },
+}
+
+impl Default for RateLimitMode {
+    fn default() -> Self {
+        Self::Queue { max_wait: None }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Unit {
+    Requests,
+    Tokens,
+}
+
+/// Budget scope: a provider, or one model of a provider
+type Scope = (String, Option<String>);
+
+/// GCRA state for one budget
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    /// Time at which the budget is fully replenished
+    tat: Instant,
+    limit: u32,
+}
+
+impl Cell {
+    fn interval(&self) -> Duration {
+        PERIOD / self.limit
+    }
+
+    /// TAT after admitting `cost` units; costs above the limit count as the
+    /// whole budget so they can still be admitted once it is full
+    fn next_tat(&self, now: Instant, cost: u32) -> Instant {
+        self.tat.max(now) + self.interval() * cost.min(self.limit)
+    }
+
+    /// Wait until `cost` units fit the budget
+    fn wait(&self, now: Instant, cost: u32) -> Duration {
+        (self.next_tat(now, cost) - PERIOD).saturating_duration_since(now)
+    }
+}
+
+/// Enforces per-minute request and token budgets per provider and model
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    mode: RateLimitMode,
+    limits: HashMap<Scope, RateLimit>,
+    cells: Mutex<HashMap<(Scope, Unit), Cell>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter without budgets that queues requests
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a limiter from each provider's routing policy
+    pub fn from_config(config: &ConfigAdapter, providers: &[&str]) -> Result<Self> {
+        let mut limiter = Self::new();
+        for provider in providers {
+            let policy = config.get_routing_policy(provider)?;
+            limiter = limiter.with_limit(*provider, RateLimit::from_policy(&policy));
+        }
+        Ok(limiter)
+    }
+
+    /// Set what happens to requests over budget
+    pub fn with_mode(mut self, mode: RateLimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Budget shared by all models of a provider
+    pub fn with_limit(mut self, provider: impl Into<String>, limit: RateLimit) -> Self {
+        self.set_limit((provider.into(), None), limit);
+        self
+    }
+
+    /// Budget for one model, in addition to the provider budget
+    pub fn with_model_limit(
+        mut self,
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        limit: RateLimit,
+    ) -> Self {
+        self.set_limit((provider.into(), Some(model.into())), limit);
+        self
+    }
+
+    fn set_limit(&mut self, scope: Scope, limit: RateLimit) {
+        if limit.is_unlimited() {
+            self.limits.remove(&scope);
+        } else {
+            self.limits.insert(scope, limit);
+        }
+    }
+
+    /// Mode for requests over budget
+    pub fn mode(&self) -> RateLimitMode {
+        self.mode
+    }
+
+    /// Budget shared by all models of a provider
+    pub fn limit(&self, provider: &str) -> Option<RateLimit> {
+        self.limits.get(&(provider.to_string(), None)).copied()
+    }
+
+    /// Admit a request of `tokens` estimated tokens, waiting in queue mode
+    ///
+    /// A waiting request holds its reservation; if the future is dropped
+    /// the reserved capacity is not returned.
+    pub async fn acquire(&self, provider: &str, model: &str, tokens: u32) -> Result<()> {
+        let max_wait = match self.mode {
+            RateLimitMode::FailFast => Some(Duration::ZERO),
+            RateLimitMode::Queue { max_wait } => max_wait,
+        };
+        let wait = self.reserve(provider, model, tokens, max_wait)?;
+        if !wait.is_zero() {
+            debug!(
+                provider = provider,
+                model = model,
+                wait_ms = wait.as_millis() as u64,
+                "Waiting for rate limit capacity"
+            );
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Admit a request only if it fits every budget now
+    pub fn try_acquire(&self, provider: &str, model: &str, tokens: u32) -> Result<()> {
+        self.reserve(provider, model, tokens, Some(Duration::ZERO))
+            .map(|_| ())
+    }
+
+    /// Correct the token budgets once a request's actual usage is known
+    ///
+    /// Overestimates are returned to the budget; underestimates are charged.
+    pub fn record_usage(&self, provider: &str, model: &str, estimated: u32, actual: u32) {
+        if estimated == actual {
+            return;
+        }
+        let mut cells = self.lock();
+        for scope in self.scopes(provider, model) {
+            if let Some(cell) = cells.get_mut(&(scope, Unit::Tokens)) {
+                let interval = cell.interval();
+                if actual > estimated {
+                    cell.tat += interval * (actual - estimated);
+                } else {
+                    cell.tat = cell
+                        .tat
+                        .checked_sub(interval * (estimated - actual))
+                        .unwrap_or(cell.tat);
+                }
+            }
+        }
+    }
+
+    /// Reserve capacity if the wait is within `max_wait`, returning the wait
+    fn reserve(
+        &self,
+        provider: &str,
+        model: &str,
+        tokens: u32,
+        max_wait: Option<Duration>,
+    ) -> Result<Duration> {
+        let now = Instant::now();
+        let mut cells = self.lock();
+
+        let mut budgets = Vec::new();
+        for scope in self.scopes(provider, model) {
+            let limit = self.limits[&scope];
+            for (unit, limit, cost) in [
+                (Unit::Requests, limit.requests_per_minute, 1),
+                (Unit::Tokens, limit.tokens_per_minute, tokens),
+            ] {
+                let Some(limit) = limit.filter(|limit| *limit > 0) else {
+                    continue;
+                };
+                let key = (scope.clone(), unit);
+                let cell = match cells.get(&key) {
+                    Some(cell) if cell.limit == limit => *cell,
+                    _ => Cell { tat: now, limit },
+                };
+                budgets.push((key, cell, cost));
+            }
+        }
+
+        let wait = budgets
+            .iter()
+            .map(|(_, cell, cost)| cell.wait(now, *cost))
+            .max()
+            .unwrap_or_default();
+        if max_wait.is_some_and(|max_wait| wait > max_wait) {
+            let ((_, model), unit) = &budgets
+                .iter()
+                .max_by_key(|(_, cell, cost)| cell.wait(now, *cost))
+                .expect("a positive wait comes from a budget")
+                .0;
+            let scope = match model {
+                Some(model) => format!("{} ", model),
+                None => String::new(),
+            };
+            let unit = match unit {
+                Unit::Requests => "requests",
+                Unit::Tokens => "tokens",
+            };
+            return Err(ConnectorError::RateLimited {
+                error: ProviderError::new(
+                    provider,
+                    format!("client-side {}{} per minute limit reached", scope, unit),
+                ),
+                retry_after: Some(wait),
+            });
+        }
+
+        // Every budget admits the request at `now + wait`
+        let at = now + wait;
+        for (key, cell, cost) in budgets {
+            let tat = cell.next_tat(at, cost);
+            cells.insert(key, Cell { tat, ..cell });
+        }
+        Ok(wait)
+    }
+
+    /// Scopes with a budget that apply to a request
+    fn scopes(&self, provider: &str, model: &str) -> Vec<Scope> {
+        [
+            (provider.to_string(), None),
+            (provider.to_string(), Some(model.to_string())),
+        ]
+        .into_iter()
+        .filter(|scope| self.limits.contains_key(scope))
+        .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(Scope, Unit), Cell>> {
+        self.cells.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Rough token count of a request: about four characters per token for
+/// the messages, plus the requested completion tokens
+pub fn estimate_tokens(request: &CompletionRequest) -> u32 {
+    let chars: usize = request
+        .messages
+        .iter()
+        .map(|message| message.text().chars().count())
+        .sum();
+    let prompt = u32::try_from(chars.div_ceil(4)).unwrap_or(u32::MAX);
+    prompt.saturating_add(request.max_tokens.unwrap_or(0))
+}
+
+/// Admits each request through a shared [`RateLimiter`]
+///
+/// Tokens are estimated with [`estimate_tokens`] before the request and
+/// corrected with the reported usage after it.
+#[derive(Debug, Clone)]
+pub struct RateLimitMiddleware {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitMiddleware {
+    /// Create a middleware enforcing the limiter's budgets
+    pub fn new(limiter: Arc<RateLimiter>) -> Self {
+        Self { limiter }
+    }
+
+    /// Shared limiter
+    pub fn limiter(&self) -> &Arc<RateLimiter> {
+        &self.limiter
+    }
+}
+
+#[async_trait]
+impl Middleware for RateLimitMiddleware {
+    fn name(&self) -> &str {
+        "rate_limit"
+    }
+
+    async fn handle(
+        &self,
+        context: &mut RequestContext,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse> {
+        let provider = context.provider.clone();
+        let model = context.request.model.clone();
+        let estimated = estimate_tokens(&context.request);
+        self.limiter.acquire(&provider, &model, estimated).await?;
+
+        let response = next.run(context).await?;
+        self.limiter
+            .record_usage(&provider, &model, estimated, response.usage.total_tokens);
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::Pipeline;
+    use crate::providers::mock::MockProvider;
+    use crate::types::Message;
+
+    fn rpm(limit: u32) -> RateLimit {
+        RateLimit::new().with_requests_per_minute(limit)
+    }
+
+    fn tpm(limit: u32) -> RateLimit {
+        RateLimit::new().with_tokens_per_minute(limit)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_minute() {
+        let limiter = RateLimiter::new()
+            .with_limit("openai", rpm(60))
+            .with_mode(RateLimitMode::FailFast);
+
+        // Full burst, then one request per second
+        for _ in 0..60 {
+            limiter.try_acquire("openai", "gpt-4", 0).unwrap();
+        }
+        let error = limiter.acquire("openai", "gpt-4", 0).await.unwrap_err();
+        assert_eq!(error.code(), "rate_limited");
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(1)));
+        assert_eq!(error.provider(), Some("openai"));
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        limiter.try_acquire("openai", "gpt-4", 0).unwrap();
+        assert!(limiter.try_acquire("openai", "gpt-4", 0).is_err());
+
+        // Other providers are unlimited
+        for _ in 0..100 {
+            limiter.try_acquire("anthropic", "claude", 0).unwrap();
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_per_minute() {
+        let limiter = RateLimiter::new().with_limit("openai", tpm(1000));
+
+        limiter.try_acquire("openai", "gpt-4", 600).unwrap();
+        let error = limiter.try_acquire("openai", "gpt-4", 600).unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(12)));
+
+        // Queued until 200 more tokens have been replenished
+        let start = Instant::now();
+        limiter.acquire("openai", "gpt-4", 600).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(12));
+
+        // Requests larger than the budget wait until it is fully replenished
+        let start = Instant::now();
+        limiter.acquire("openai", "gpt-4", 5000).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_order_and_max_wait() {
+        let limiter = Arc::new(RateLimiter::new().with_limit("openai", rpm(2)).with_mode(
+            RateLimitMode::Queue {
+                max_wait: Some(Duration::from_secs(60)),
+            },
+        ));
+
+        let start = Instant::now();
+        let mut tasks = Vec::new();
+        for i in 0..4 {
+            let limiter = limiter.clone();
+            tasks.push(tokio::spawn(async move {
+                limiter.acquire("openai", "gpt-4", 0).await.unwrap();
+                (i, start.elapsed())
+            }));
+            tokio::task::yield_now().await;
+        }
+        let mut admitted = Vec::new();
+        for task in tasks {
+            admitted.push(task.await.unwrap());
+        }
+        assert_eq!(
+            admitted,
+            vec![
+                (0, Duration::ZERO),
+                (1, Duration::ZERO),
+                (2, Duration::from_secs(30)),
+                (3, Duration::from_secs(60)),
+            ]
+        );
+
+        // The queue now extends 60s; a fifth request would wait 90s
+        let limiter =
+            RateLimiter::new()
+                .with_limit("openai", rpm(2))
+                .with_mode(RateLimitMode::Queue {
+                    max_wait: Some(Duration::from_secs(60)),
+                });
+        for _ in 0..4 {
+            limiter.reserve("openai", "gpt-4", 0, None).unwrap();
+        }
+        let error = limiter.acquire("openai", "gpt-4", 0).await.unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(90)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_model_limits() {
+        let limiter = RateLimiter::new()
+            .with_limit("openai", rpm(3))
+            .with_model_limit("openai", "gpt-4", rpm(1))
+            .with_mode(RateLimitMode::FailFast);
+
+        limiter.try_acquire("openai", "gpt-4", 0).unwrap();
+        let error = limiter.try_acquire("openai", "gpt-4", 0).unwrap_err();
+        assert!(error.to_string().contains("gpt-4 requests per minute"));
+
+        // The provider budget is shared across models
+        limiter.try_acquire("openai", "gpt-3.5-turbo", 0).unwrap();
+        limiter.try_acquire("openai", "gpt-3.5-turbo", 0).unwrap();
+        let error = limiter
+            .try_acquire("openai", "gpt-3.5-turbo", 0)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("client-side requests per minute"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_record_usage() {
+        let limiter = RateLimiter::new().with_limit("openai", tpm(600));
+
+        // Estimated 600, used 100: 500 tokens are returned
+        limiter.try_acquire("openai", "gpt-4", 600).unwrap();
+        limiter.record_usage("openai", "gpt-4", 600, 100);
+        limiter.try_acquire("openai", "gpt-4", 500).unwrap();
+
+        // Estimated 0, used 60: charged 60 tokens (6s)
+        limiter.record_usage("openai", "gpt-4", 0, 60);
+        let error = limiter.try_acquire("openai", "gpt-4", 1).unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_millis(6100)));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let mut config = ConfigAdapter::new();
+        config.set_routing_policy(
+            "openai",
+            RoutingPolicy {
+                rate_limit: Some(500),
+                token_rate_limit: Some(30_000),
+                ..Default::default()
+            },
+        );
+
+        let limiter = RateLimiter::from_config(&config, &["openai", "anthropic"]).unwrap();
+        assert_eq!(
+            limiter.limit("openai"),
+            Some(rpm(500).with_tokens_per_minute(30_000))
+        );
+        assert_eq!(limiter.limit("anthropic"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_middleware() {
+        let limiter = Arc::new(
+            RateLimiter::new()
+                .with_limit("openai", rpm(2).with_tokens_per_minute(100))
+                .with_mode(RateLimitMode::FailFast),
+        );
+        let pipeline = Pipeline::new().with(RateLimitMiddleware::new(limiter.clone()));
+        let provider = MockProvider::new("openai");
+
+        let request = CompletionRequest::builder("mock-model")
+            .message(Message::user("one two three four"))
+            .max_tokens(40)
+            .build()
+            .unwrap();
+        assert_eq!(estimate_tokens(&request), 5 + 40);
+
+        // Each mock response uses 8 tokens, so most of the estimate is returned
+        pipeline.complete(&provider, &request).await.unwrap();
+        pipeline.complete(&provider, &request).await.unwrap();
+        let error = pipeline.complete(&provider, &request).await.unwrap_err();
+        assert_eq!(error.code(), "rate_limited");
+        assert_eq!(provider.call_count(), 2);
+    }
+}
@@ -0,0 +1,556 @@
+//! Retries with exponential backoff.
+//!
+//! Delays use `tokio::time`, so tests running with a paused clock
+//! (`#[tokio::test(start_paused = true)]`) see the exact backoff schedule
+//! without waiting for it.
+
+use super::pipeline::{Middleware, Next, RequestContext};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::types::CompletionResponse;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Randomization applied to backoff delays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    /// Exact exponential delays
+    None,
+    /// Uniform between zero and the exponential delay
+    #[default]
+    Full,
+    /// Uniform between the initial delay and three times the previous delay
+    Decorrelated,
+}
+
+/// When and how long to wait between attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry, before jitter
+    pub initial_delay: Duration,
+    /// Upper bound for backoff delays and provider-requested waits
+    pub max_delay: Duration,
+    /// Growth factor between consecutive delays; values below 1.0 (and NaN)
+    /// are treated as 1.0
+    pub multiplier: f64,
+    /// Randomization applied to delays
+    pub jitter: Jitter,
+    /// Time budget for all attempts and delays together
+    pub deadline: Option<Duration>,
+    /// Wait for the delay a rate-limited provider asks for instead of backing off
+    ///
+    /// The requested delay is still capped at `max_delay`.
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: Jitter::Full,
+            deadline: None,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create the default policy: 3 attempts, 1s initial delay, full jitter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the first
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the delay before the first retry
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the upper bound for backoff delays
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the growth factor (at least 1.0) between consecutive delays
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = clamp_multiplier(multiplier);
+        self
+    }
+
+    /// Set the jitter
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Give up once attempts and delays would exceed this total time
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Whether to wait for the provider's requested delay on rate limits
+    pub fn with_respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    /// Backoff before retry number `retry` (1 for the first retry)
+    ///
+    /// `previous` is the delay before the previous retry, used by
+    /// decorrelated jitter.
+    pub fn backoff<R: Rng + ?Sized>(
+        &self,
+        retry: u32,
+        previous: Duration,
+        rng: &mut R,
+    ) -> Duration {
+        let max = self.max_delay.as_secs_f64();
+        let initial = self.initial_delay.as_secs_f64().min(max);
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let exponential = initial * clamp_multiplier(self.multiplier).powi(exponent);
+        // 0 * inf is NaN
+        let exponential = if exponential.is_nan() {
+            initial
+        } else {
+            exponential.clamp(0.0, max)
+        };
+
+        let seconds = match self.jitter {
+            Jitter::None => exponential,
+            Jitter::Full => rng.gen_range(0.0..=exponential),
+            Jitter::Decorrelated => {
+                let high = (previous.as_secs_f64().max(initial) * 3.0).min(max);
+                if high > initial {
+                    rng.gen_range(initial..=high)
+                } else {
+                    high
+                }
+            }
+        };
+        Duration::try_from_secs_f64(seconds).unwrap_or(self.max_delay)
+    }
+}
+
+fn clamp_multiplier(multiplier: f64) -> f64 {
+    if multiplier.is_nan() {
+        1.0
+    } else {
+        multiplier.max(1.0)
+    }
+}
+
+type Classifier = Arc<dyn Fn(&ConnectorError) -> bool + Send + Sync>;
+
+/// Retries failed completions according to a [`RetryPolicy`]
+///
+/// By default errors are retried when [`ConnectorError::is_retryable`].
+/// Each retry is recorded as a `retry_attempt` event on the request's span,
+/// with the retry number, the error code as `reason`, the HTTP status and
+/// the delay.
+pub struct RetryMiddleware {
+    policy: RetryPolicy,
+    classifier: Option<Classifier>,
+    rng: Mutex<StdRng>,
+}
+
+impl RetryMiddleware {
+    /// Create a retry middleware with the given policy
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            classifier: None,
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Seed the jitter, for reproducible delays
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Decide which errors to retry instead of using `is_retryable`
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&ConnectorError) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Retry policy
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    fn is_retryable(&self, error: &ConnectorError) -> bool {
+        match &self.classifier {
+            Some(classifier) => classifier(error),
+            None => error.is_retryable(),
+        }
+    }
+
+    /// Delay before retry number `retry`
+    fn delay(&self, retry: u32, previous: Duration, error: &ConnectorError) -> Duration {
+        if self.policy.respect_retry_after {
+            if let Some(retry_after) = error.retry_after() {
+                return retry_after.min(self.policy.max_delay);
+            }
+        }
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        self.policy.backoff(retry, previous, &mut *rng)
+    }
+
+    fn deadline_error(&self, context: &RequestContext) -> ConnectorError {
+        let deadline = self.policy.deadline.unwrap_or_default();
+        ConnectorError::Timeout(ProviderError::new(
+            &context.provider,
+            format!("retry deadline of {}ms exceeded", deadline.as_millis()),
+        ))
+    }
+}
+
+impl Default for RetryMiddleware {
+    fn default() -> Self {
+        Self::new(RetryPolicy::default())
+    }
+}
+
+impl fmt::Debug for RetryMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryMiddleware")
+            .field("policy", &self.policy)
+            .field("classifier", &self.classifier.is_some())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryMiddleware {
+    fn name(&self) -> &str {
+        "retry"
+    }
+
+    async fn handle(
+        &self,
+        context: &mut RequestContext,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse> {
+        let started = Instant::now();
+        let deadline = self.policy.deadline.map(|deadline| started + deadline);
+        let mut previous = self.policy.initial_delay;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let result = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, next.run(context))
+                    .await
+                    .unwrap_or_else(|_| Err(self.deadline_error(context))),
+                None => next.run(context).await,
+            };
+            let error = match result {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            if attempt >= self.policy.max_attempts || !self.is_retryable(&error) {
+                return Err(error);
+            }
+            let delay = self.delay(attempt, previous, &error);
+            previous = delay;
+            if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+                debug!(
+                    provider = %context.provider,
+                    attempt = attempt,
+                    delay_ms = delay.as_millis() as u64,
+                    "Retry deadline reached, giving up"
+                );
+                return Err(error);
+            }
+
+            warn!(
+                provider = %context.provider,
+                model = %context.request.model,
+                attempt = attempt,
+                delay_ms = delay.as_millis() as u64,
+                code = error.code(),
+                error = %error,
+                "Retrying failed completion"
+            );
+            let mut attributes = HashMap::new();
+            attributes.insert("retry".to_string(), Value::from(attempt));
+            attributes.insert("reason".to_string(), Value::from(error.code()));
+            attributes.insert(
+                "delay_ms".to_string(),
+                Value::from(delay.as_millis() as u64),
+            );
+            if let Some(status) = error.status() {
+                attributes.insert("status".to_string(), Value::from(status));
+            }
+            context.record_event("retry_attempt", attributes);
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use crate::middleware::Pipeline;
+    use crate::providers::mock::{MockFailure, MockProvider, MockReply, MockStep};
+    use crate::types::{CompletionRequest, Message};
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("mock-model", vec![Message::user("Hello")])
+    }
+
+    fn exact(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new()
+            .with_max_attempts(max_attempts)
+            .with_initial_delay(Duration::from_millis(100))
+            .with_jitter(Jitter::None)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_exponential_backoff() {
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(exact(4)));
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::Timeout)
+            .fail_with(MockFailure::RateLimited)
+            .respond_with_text("Hi");
+
+        let start = Instant::now();
+        let response = pipeline.complete(&provider, &request()).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hi"));
+        assert_eq!(provider.call_count(), 4);
+        assert_eq!(start.elapsed(), Duration::from_millis(100 + 200 + 400));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up() {
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(exact(3)));
+
+        // Attempts exhausted
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::ServerError);
+        let error = pipeline.complete(&provider, &request()).await.unwrap_err();
+        assert_eq!(error.code(), "provider_unavailable");
+        assert_eq!(provider.call_count(), 3);
+
+        // Not retryable
+        let provider = MockProvider::new("openai").fail_with(MockFailure::Status {
+            status: 400,
+            body: r#"{"message": "bad request"}"#.to_string(),
+        });
+        let error = pipeline.complete(&provider, &request()).await.unwrap_err();
+        assert_eq!(error.code(), "invalid_request");
+        assert_eq!(provider.call_count(), 1);
+
+        // Custom classifier
+        let pipeline = Pipeline::new().with(
+            RetryMiddleware::new(exact(3))
+                .with_classifier(|e| !matches!(e, ConnectorError::RateLimited { .. })),
+        );
+        let provider = MockProvider::new("openai").fail_with(MockFailure::RateLimited);
+        assert!(pipeline.complete(&provider, &request()).await.is_err());
+        assert_eq!(provider.call_count(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after() {
+        let rate_limited = MockFailure::Status {
+            status: 429,
+            body: r#"{"message": "Rate limit reached. Please try again in 5s."}"#.to_string(),
+        };
+
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(exact(2)));
+        let provider = MockProvider::new("openai")
+            .fail_with(rate_limited.clone())
+            .respond_with_text("Hi");
+        let start = Instant::now();
+        pipeline.complete(&provider, &request()).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+
+        // Capped at max_delay
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(
+            exact(2).with_max_delay(Duration::from_secs(2)),
+        ));
+        let provider = MockProvider::new("openai")
+            .fail_with(rate_limited.clone())
+            .respond_with_text("Hi");
+        let start = Instant::now();
+        pipeline.complete(&provider, &request()).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(
+            exact(2).with_respect_retry_after(false),
+        ));
+        let provider = MockProvider::new("openai")
+            .fail_with(rate_limited)
+            .respond_with_text("Hi");
+        let start = Instant::now();
+        pipeline.complete(&provider, &request()).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline() {
+        let policy = exact(10).with_deadline(Duration::from_millis(1000));
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(policy));
+
+        // Attempts take 200ms and start at 0ms, 300ms and 700ms; the next
+        // 400ms backoff would end past the deadline.
+        let provider = MockProvider::new("openai")
+            .with_latency(Duration::from_millis(200))
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::ServerError);
+        let start = Instant::now();
+        let error = pipeline.complete(&provider, &request()).await.unwrap_err();
+        assert_eq!(error.code(), "provider_unavailable");
+        assert_eq!(provider.call_count(), 3);
+        assert_eq!(
+            start.elapsed(),
+            Duration::from_millis(200 + 100 + 200 + 200 + 200)
+        );
+
+        // An attempt still running at the deadline is cancelled
+        let provider = MockProvider::new("openai").then(
+            MockStep::new(MockReply::Failure(MockFailure::ServerError))
+                .with_latency(Duration::from_secs(5)),
+        );
+        let start = Instant::now();
+        let error = pipeline.complete(&provider, &request()).await.unwrap_err();
+        assert_eq!(error.code(), "timeout");
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_records_retry_events() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let span_id = adapter
+            .lock()
+            .unwrap()
+            .start_provider_span("openai", "mock-model", None);
+
+        let pipeline = Pipeline::new().with(RetryMiddleware::new(exact(3)));
+        let provider = MockProvider::new("openai")
+            .fail_with(MockFailure::RateLimited)
+            .fail_with(MockFailure::ServerError)
+            .respond_with_text("Hi");
+        let mut context =
+            RequestContext::new("openai", request()).with_span(adapter.clone(), span_id.clone());
+        pipeline.execute(&provider, &mut context).await.unwrap();
+        assert_eq!(context.attempt, 3);
+
+        let adapter = adapter.lock().unwrap();
+        let events = adapter.span_events(&span_id).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| event.name == "retry_attempt"));
+        assert_eq!(events[0].attributes["retry"], Value::from(1));
+        assert_eq!(events[0].attributes["reason"], Value::from("rate_limited"));
+        assert_eq!(events[0].attributes["status"], Value::from(429));
+        assert_eq!(events[0].attributes["delay_ms"], Value::from(100));
+        assert_eq!(events[1].attributes["retry"], Value::from(2));
+        assert_eq!(
+            events[1].attributes["reason"],
+            Value::from("provider_unavailable")
+        );
+        assert_eq!(events[1].attributes["delay_ms"], Value::from(200));
+    }
+
+    #[test]
+    fn test_jitter() {
+        let policy = RetryPolicy::new()
+            .with_initial_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(1));
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let policy = policy.with_jitter(Jitter::Full);
+        for retry in 1..10 {
+            let cap = Duration::from_millis(100 * 2u64.pow(retry - 1)).min(policy.max_delay);
+            let delay = policy.backoff(retry, Duration::ZERO, &mut rng);
+            assert!(delay <= cap, "retry {}: {:?} > {:?}", retry, delay, cap);
+        }
+
+        let policy = policy.with_jitter(Jitter::Decorrelated);
+        let mut previous = policy.initial_delay;
+        for retry in 1..10 {
+            let delay = policy.backoff(retry, previous, &mut rng);
+            assert!(delay >= policy.initial_delay);
+            assert!(delay <= (previous * 3).min(policy.max_delay));
+            previous = delay;
+        }
+
+        // Same seed, same delays
+        let delays = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (1..5)
+                .map(|retry| policy.backoff(retry, Duration::from_millis(300), &mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(delays(42), delays(42));
+        assert_ne!(delays(42), delays(43));
+
+        // Huge exponents saturate at the maximum
+        let policy = policy.with_jitter(Jitter::None);
+        assert_eq!(
+            policy.backoff(u32::MAX, Duration::ZERO, &mut rng),
+            policy.max_delay
+        );
+    }
+
+    #[test]
+    fn test_invalid_multiplier() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for multiplier in [-2.0, 0.5, f64::NAN] {
+            let policy = exact(5).with_multiplier(multiplier);
+            assert_eq!(policy.multiplier, 1.0);
+            for retry in 1..5 {
+                let delay = policy.backoff(retry, Duration::ZERO, &mut rng);
+                assert_eq!(delay, Duration::from_millis(100));
+            }
+        }
+
+        // Set directly on the field, with every jitter mode
+        for jitter in [Jitter::None, Jitter::Full, Jitter::Decorrelated] {
+            let mut policy = exact(5).with_jitter(jitter);
+            policy.multiplier = -2.0;
+            for retry in 1..5 {
+                let delay = policy.backoff(retry, Duration::from_millis(100), &mut rng);
+                assert!(delay <= policy.max_delay);
+            }
+        }
+
+        // A zero initial delay times an infinite growth is not NaN
+        let mut policy = exact(5).with_initial_delay(Duration::ZERO);
+        policy.multiplier = f64::INFINITY;
+        assert_eq!(policy.backoff(3, Duration::ZERO, &mut rng), Duration::ZERO);
+    }
+}
@@ -0,0 +1,741 @@
+//! # Anthropic Provider
+//!
+//! Native client for the Anthropic Messages API.
+//!
+//! Handles the differences from the OpenAI format:
+//!
+//! - System messages are hoisted into the top-level `system` field
+//! - Roles must alternate between `user` and `assistant`, starting with `user`
+//! - `max_tokens` is mandatory
+//! - Requests carry an `anthropic-version` header
+//! - Tool calls and results are `tool_use`/`tool_result` content blocks
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::anthropic::AnthropicProvider;
+//!
+//! let mut config = ConfigAdapter::new();
+//! let provider = AnthropicProvider::from_adapter(&mut config)?;
+//! let response = provider.complete(&request).await?;
+//! ```
+
+use super::errors;
+use super::http::{self, ProviderTelemetry};
+use super::openai::ModelList;
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, Result};
+use crate::streaming;
+use crate::types::{
+    Choice, CompletionRequest, CompletionResponse, ContentPart, FinishReason, Message,
+    MessageContent, ProviderMetadata, Role, ToolCall, Usage,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+/// Default Anthropic API endpoint
+pub const DEFAULT_ENDPOINT: &str = "https://api.anthropic.com/v1";
+
+/// Default `anthropic-version` header value
+pub const DEFAULT_API_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` used when the request does not set one
+pub const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+/// Anthropic Messages API provider
+#[derive(Clone)]
+pub struct AnthropicProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    api_version: String,
+    default_max_tokens: u32,
+    telemetry: ProviderTelemetry,
+}
+
+impl AnthropicProvider {
+    /// Create a provider using the default endpoint and API version
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            api_key: api_key.into(),
+            api_version: DEFAULT_API_VERSION.to_string(),
+            default_max_tokens: DEFAULT_MAX_TOKENS,
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// Recognized settings: `api_version`, `default_max_tokens`.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let api_key = config.api_key.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("API key not configured for {}", config.provider))
+        })?;
+
+        let mut provider = Self::new(api_key);
+        if let Some(endpoint) = &config.endpoint {
+            provider.endpoint = endpoint.clone();
+        }
+        if let Some(version) = config.settings.get("api_version").and_then(Value::as_str) {
+            provider.api_version = version.to_string();
+        }
+        if let Some(max_tokens) = config
+            .settings
+            .get("default_max_tokens")
+            .and_then(Value::as_u64)
+        {
+            provider.default_max_tokens = u32::try_from(max_tokens).map_err(|_| {
+                ConnectorError::Config(format!("default_max_tokens out of range: {}", max_tokens))
+            })?;
+        }
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "anthropic" entry of a config adapter
+    ///
+    /// The API key is read with `get_credential("anthropic", "api_key")` if
+    /// the configuration does not carry one.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut config = adapter.get_provider_config("anthropic")?.clone();
+        if config.api_key.is_none() {
+            config.api_key = Some(adapter.get_credential("anthropic", "api_key")?);
+        }
+        Self::from_config(&config)
+    }
+
+    /// Override the API endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Override the `anthropic-version` header
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+
+    /// Set the `max_tokens` used when the request does not set one
+    pub fn with_default_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.default_max_tokens = max_tokens;
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    /// Build the Messages API request body
+    pub(crate) fn build_request(&self, request: &CompletionRequest) -> Result<MessagesRequest> {
+        messages_request(request, self.default_max_tokens)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        request
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", &self.api_version)
+    }
+}
+
+#[async_trait]
+impl Provider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: true,
+            vision: true,
+            json_mode: false,
+            max_tokens: Some(8192),
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let mut body = self.build_request(request)?;
+        body.stream = false;
+        let body = serde_json::to_value(body)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = http::join_url(&self.endpoint, "messages");
+        debug!(url = %url, model = %request.model, "Sending Anthropic message");
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.authorize(self.client.post(&url)).json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::anthropic(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let message: MessagesResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+                ConnectorError::Internal(format!("Unexpected Anthropic response: {}", e))
+            })?;
+            Ok((raw, message.into_unified(self.name())))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let mut body = self.build_request(request)?;
+        body.stream = true;
+        let body = serde_json::to_value(body)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let url = http::join_url(&self.endpoint, "messages");
+        debug!(url = %url, model = %request.model, "Streaming Anthropic message");
+        http::send_stream(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.authorize(self.client.post(&url)).json(&body),
+            &body,
+            errors::anthropic,
+            |response| streaming::anthropic::decode(response.bytes_stream()).boxed(),
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.endpoint, "models");
+        let response = http::send(self.name(), self.authorize(self.client.get(&url))).await?;
+        if !response.is_success() {
+            return Err(errors::anthropic(self.name(), &response));
+        }
+        let models: ModelList = response.json(self.name())?;
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+}
+
+/// Build a Messages API request body
+///
+/// Shared with Bedrock, which accepts the same body for Anthropic models.
+pub(crate) fn messages_request(
+    request: &CompletionRequest,
+    default_max_tokens: u32,
+) -> Result<MessagesRequest> {
+    let (system, messages) = convert_messages(&request.messages)?;
+
+    Ok(MessagesRequest {
+        model: request.model.clone(),
+        messages,
+        system,
+        max_tokens: request.max_tokens.unwrap_or(default_max_tokens),
+        temperature: request.temperature,
+        top_p: request.top_p,
+        top_k: request.top_k,
+        stop_sequences: request.stop.clone(),
+        stream: request.stream,
+        metadata: request
+            .user
+            .clone()
+            .map(|user_id| RequestMetadata { user_id }),
+        tools: request
+            .tools
+            .iter()
+            .map(|tool| AnthropicTool {
+                name: tool.function.name.clone(),
+                description: tool.function.description.clone(),
+                input_schema: tool
+                    .function
+                    .parameters
+                    .clone()
+                    .unwrap_or_else(|| serde_json::json!({"type": "object"})),
+            })
+            .collect(),
+    })
+}
+
+/// Hoist system messages and convert the rest into alternating turns
+fn convert_messages(messages: &[Message]) -> Result<(Option<String>, Vec<AnthropicMessage>)> {
+    let mut system: Vec<String> = Vec::new();
+    let mut turns: Vec<AnthropicMessage> = Vec::new();
+
+    for message in messages {
+        let (role, blocks) = match message.role {
+            Role::System => {
+                system.push(message.text());
+                continue;
+            }
+            Role::Tool | Role::Function => {
+                let tool_use_id = message.tool_call_id.clone().ok_or_else(|| {
+                    ConnectorError::Schema("Tool result message requires tool_call_id".to_string())
+                })?;
+                (
+                    Role::User,
+                    vec![ContentBlock::ToolResult {
+                        tool_use_id,
+                        content: message.text(),
+                    }],
+                )
+            }
+            Role::User => (Role::User, content_blocks(&message.content)),
+            Role::Assistant => {
+                let mut blocks = content_blocks(&message.content);
+                for call in &message.tool_calls {
+                    let input = serde_json::from_str(&call.function.arguments).map_err(|e| {
+                        ConnectorError::Schema(format!(
+                            "Tool call {} has invalid JSON arguments: {}",
+                            call.id, e
+                        ))
+                    })?;
+                    blocks.push(ContentBlock::ToolUse {
+                        id: call.id.clone(),
+                        name: call.function.name.clone(),
+                        input,
+                    });
+                }
+                (Role::Assistant, blocks)
+            }
+        };
+
+        // Merge consecutive turns from the same role to keep roles alternating
+        match turns.last_mut() {
+            Some(last) if last.role == role => last.content.extend(blocks),
+            _ => turns.push(AnthropicMessage {
+                role,
+                content: blocks,
+            }),
+        }
+    }
+
+    match turns.first() {
+        None => {
+            return Err(ConnectorError::Schema(
+                "Anthropic requests require at least one non-system message".to_string(),
+            ))
+        }
+        Some(first) if first.role != Role::User => {
+            return Err(ConnectorError::Schema(
+                "Anthropic conversations must start with a user message".to_string(),
+            ))
+        }
+        _ => {}
+    }
+
+    let system = (!system.is_empty()).then(|| system.join("\n\n"));
+    Ok((system, turns))
+}
+
+/// Convert unified content into Anthropic content blocks
+fn content_blocks(content: &MessageContent) -> Vec<ContentBlock> {
+    match content {
+        MessageContent::Text(text) if text.is_empty() => Vec::new(),
+        MessageContent::Text(text) => vec![ContentBlock::Text { text: text.clone() }],
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => ContentBlock::Text { text: text.clone() },
+                ContentPart::ImageUrl { image_url, .. } => match http::parse_data_url(image_url) {
+                    Some((media_type, data)) => ContentBlock::Image {
+                        source: ImageSource::Base64 { media_type, data },
+                    },
+                    None => ContentBlock::Image {
+                        source: ImageSource::Url {
+                            url: image_url.clone(),
+                        },
+                    },
+                },
+                ContentPart::ImageBase64 { image_base64, .. } => {
+                    let (media_type, data) = http::parse_data_url(image_base64)
+                        .unwrap_or_else(|| ("image/jpeg".to_string(), image_base64.clone()));
+                    ContentBlock::Image {
+                        source: ImageSource::Base64 { media_type, data },
+                    }
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Map an Anthropic `stop_reason`
+pub(crate) fn map_stop_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "end_turn" | "stop_sequence" | "pause_turn" => Some(FinishReason::Stop),
+        "max_tokens" => Some(FinishReason::Length),
+        "tool_use" => Some(FinishReason::ToolCalls),
+        "refusal" => Some(FinishReason::ContentFilter),
+        _ => None,
+    }
+}
+
+/// Messages API request body
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct MessagesRequest {
+    pub model: String,
+    pub messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<RequestMetadata>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<AnthropicTool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct RequestMetadata {
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AnthropicTool {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub input_schema: Value,
+}
+
+/// A single conversation turn
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AnthropicMessage {
+    pub role: Role,
+    pub content: Vec<ContentBlock>,
+}
+
+/// Anthropic content block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: ImageSource,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+    },
+    /// Block types this client does not interpret (e.g. `thinking`)
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
+}
+
+/// Messages API response body
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct MessagesResponse {
+    pub id: String,
+    pub model: String,
+    #[serde(default)]
+    pub content: Vec<ContentBlock>,
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    #[serde(default)]
+    pub usage: AnthropicUsage,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub(crate) struct AnthropicUsage {
+    #[serde(default)]
+    pub input_tokens: u32,
+    #[serde(default)]
+    pub output_tokens: u32,
+}
+
+impl MessagesResponse {
+    pub(crate) fn into_unified(self, provider: &str) -> CompletionResponse {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in self.content {
+            match block {
+                ContentBlock::Text { text: t } => text.push_str(&t),
+                ContentBlock::ToolUse { id, name, input } => {
+                    tool_calls.push(ToolCall::function(id, name, input.to_string()))
+                }
+                _ => {}
+            }
+        }
+
+        CompletionResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: self.model.clone(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(text).with_tool_calls(tool_calls),
+                finish_reason: self.stop_reason.as_deref().and_then(map_stop_reason),
+            }],
+            usage: Usage::new(self.usage.input_tokens, self.usage.output_tokens),
+            metadata: Some(ProviderMetadata {
+                provider: provider.to_string(),
+                model: self.model,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_system_hoisting_and_role_merging() {
+        let provider = AnthropicProvider::new("key");
+        let request = CompletionRequest::builder("claude-3-haiku-20240307")
+            .message(Message::system("Be brief."))
+            .message(Message::system("Answer in French."))
+            .message(Message::user("Hello"))
+            .message(Message::user("Are you there?"))
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(provider.build_request(&request).unwrap()).unwrap();
+        assert_eq!(body["system"], "Be brief.\n\nAnswer in French.");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["messages"][0]["content"][1]["text"], "Are you there?");
+    }
+
+    #[test]
+    fn test_tool_round_trip_blocks() {
+        let provider = AnthropicProvider::new("key");
+        let request = CompletionRequest::builder("claude-3-opus-20240229")
+            .message(Message::user("Weather in Paris?"))
+            .message(
+                Message::assistant("").with_tool_calls(vec![ToolCall::function(
+                    "toolu_1",
+                    "get_weather",
+                    "{\"city\":\"Paris\"}",
+                )]),
+            )
+            .message(Message::tool("toolu_1", "18C"))
+            .max_tokens(200)
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(provider.build_request(&request).unwrap()).unwrap();
+        assert_eq!(body["max_tokens"], 200);
+        assert_eq!(body["messages"][1]["content"][0]["type"], "tool_use");
+        assert_eq!(body["messages"][1]["content"][0]["input"]["city"], "Paris");
+        assert_eq!(body["messages"][2]["role"], "user");
+        assert_eq!(body["messages"][2]["content"][0]["tool_use_id"], "toolu_1");
+    }
+
+    #[test]
+    fn test_conversation_must_start_with_user() {
+        let provider = AnthropicProvider::new("key");
+        let request = CompletionRequest::new(
+            "claude-3-haiku-20240307",
+            vec![Message::system("sys"), Message::assistant("Hi")],
+        );
+        assert!(provider.build_request(&request).is_err());
+
+        let request =
+            CompletionRequest::new("claude-3-haiku-20240307", vec![Message::system("sys")]);
+        assert!(provider.build_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_stop_reason_mapping() {
+        assert_eq!(map_stop_reason("end_turn"), Some(FinishReason::Stop));
+        assert_eq!(map_stop_reason("stop_sequence"), Some(FinishReason::Stop));
+        assert_eq!(map_stop_reason("max_tokens"), Some(FinishReason::Length));
+        assert_eq!(map_stop_reason("tool_use"), Some(FinishReason::ToolCalls));
+        assert_eq!(map_stop_reason("something_new"), None);
+    }
+
+    #[test]
+    fn test_image_blocks() {
+        let blocks = content_blocks(&MessageContent::Parts(vec![
+            ContentPart::ImageUrl {
+                image_url: "data:image/png;base64,iVBOR".to_string(),
+                detail: None,
+            },
+            ContentPart::ImageUrl {
+                image_url: "https://example.com/a.jpg".to_string(),
+                detail: None,
+            },
+        ]));
+        assert_eq!(
+            blocks[0],
+            ContentBlock::Image {
+                source: ImageSource::Base64 {
+                    media_type: "image/png".to_string(),
+                    data: "iVBOR".to_string()
+                }
+            }
+        );
+        assert!(matches!(
+            &blocks[1],
+            ContentBlock::Image {
+                source: ImageSource::Url { .. }
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_complete_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(header("x-api-key", "sk-ant-test"))
+            .and(header("anthropic-version", DEFAULT_API_VERSION))
+            .and(body_partial_json(json!({"system": "Be brief.", "max_tokens": 1024})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-haiku-20240307",
+                "content": [
+                    {"type": "text", "text": "Checking."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+                ],
+                "stop_reason": "tool_use",
+                "stop_sequence": null,
+                "usage": {"input_tokens": 20, "output_tokens": 8}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-ant-test").with_endpoint(server.uri());
+        let request = CompletionRequest::new(
+            "claude-3-haiku-20240307",
+            vec![Message::system("Be brief."), Message::user("Weather?")],
+        );
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.id, "msg_01");
+        assert_eq!(response.text().as_deref(), Some("Checking."));
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(
+            response.choices[0].message.tool_calls[0],
+            ToolCall::function("toolu_1", "get_weather", "{\"city\":\"Paris\"}")
+        );
+        assert_eq!(response.usage, Usage::new(20, 8));
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock_server() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_02\",\"model\":\"claude-3-haiku-20240307\",\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi there\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":3}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-ant-test").with_endpoint(server.uri());
+        let request = CompletionRequest::new("claude-3-haiku-20240307", vec![Message::user("Hi")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.id, "msg_02");
+        assert_eq!(response.text().as_deref(), Some("Hi there"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(12, 3));
+    }
+
+    #[test]
+    fn test_from_adapter_uses_default_endpoint() {
+        std::env::set_var("ANTHROPIC_API_KEY", "sk-ant-env");
+        let mut adapter = ConfigAdapter::new();
+        let provider = AnthropicProvider::from_adapter(&mut adapter);
+        // Removed before asserting so a failure cannot leak it to other tests
+        std::env::remove_var("ANTHROPIC_API_KEY");
+        let provider = provider.unwrap();
+        assert_eq!(provider.endpoint, DEFAULT_ENDPOINT);
+        assert_eq!(provider.api_key, "sk-ant-env");
+    }
+
+    #[test]
+    fn test_from_config_default_max_tokens() {
+        let mut config = ProviderConfig {
+            provider: "anthropic".to_string(),
+            endpoint: None,
+            api_key: Some("sk-ant-test".to_string()),
+            models: vec![],
+            settings: Default::default(),
+        };
+        config
+            .settings
+            .insert("default_max_tokens".to_string(), json!(8192));
+        let provider = AnthropicProvider::from_config(&config).unwrap();
+        assert_eq!(provider.default_max_tokens, 8192);
+
+        config.settings.insert(
+            "default_max_tokens".to_string(),
+            json!(u64::from(u32::MAX) + 1),
+        );
+        let error = AnthropicProvider::from_config(&config).err().unwrap();
+        assert_eq!(error.code(), "config");
+    }
+}
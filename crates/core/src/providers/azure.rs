@@ -0,0 +1,509 @@
+//! # Azure OpenAI Provider
+//!
+//! Native client for Azure OpenAI chat completions.
+//!
+//! Azure routes requests by deployment rather than by model:
+//!
+//! ```text
+//! {resource}/openai/deployments/{deployment}/chat/completions?api-version=...
+//! ```
+//!
+//! Model names in unified requests are translated to deployment names through
+//! a configurable map. Authentication uses either the `api-key` header or an
+//! Entra ID (Azure AD) bearer token.
+//!
+//! ## Configuration
+//!
+//! `ProviderConfig.settings` keys:
+//!
+//! - `resource` - resource name (`my-resource`) or base URL; ignored if
+//!   `endpoint` is set
+//! - `deployments` - object mapping model names to deployment names
+//! - `deployment` - deployment used for models without a mapping
+//! - `api_version` - API version (default `2024-02-15-preview`)
+//! - `auth` - `"api_key"` (default) or `"bearer"`
+
+use super::errors;
+use super::http::{self, ProviderTelemetry};
+use super::openai::{self, ChatRequest, ChatResponse, ModelList};
+use super::{HealthStatus, Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, Result};
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Default Azure OpenAI API version
+pub const DEFAULT_API_VERSION: &str = "2024-02-15-preview";
+
+/// Azure OpenAI authentication
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AzureAuth {
+    /// `api-key` header
+    ApiKey(String),
+    /// `Authorization: Bearer` token (Entra ID / Azure AD)
+    Bearer(String),
+}
+
+/// Azure OpenAI provider
+#[derive(Clone)]
+pub struct AzureOpenAIProvider {
+    client: reqwest::Client,
+    base_url: String,
+    auth: AzureAuth,
+    api_version: String,
+    deployments: HashMap<String, String>,
+    default_deployment: Option<String>,
+    telemetry: ProviderTelemetry,
+}
+
+impl AzureOpenAIProvider {
+    /// Create a provider for an Azure resource
+    ///
+    /// `resource` is either a resource name (`my-resource`) or a full base URL.
+    pub fn new(resource: &str, auth: AzureAuth) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: resource_url(resource),
+            auth,
+            api_version: DEFAULT_API_VERSION.to_string(),
+            deployments: HashMap::new(),
+            default_deployment: None,
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// See the module documentation for the recognized settings.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let settings = &config.settings;
+        let resource = config
+            .endpoint
+            .clone()
+            .or_else(|| {
+                settings
+                    .get("resource")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .ok_or_else(|| {
+                ConnectorError::Config(format!(
+                    "Azure resource or endpoint not configured for {}",
+                    config.provider
+                ))
+            })?;
+
+        let key = config.api_key.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("API key not configured for {}", config.provider))
+        })?;
+        let auth = match settings.get("auth").and_then(Value::as_str) {
+            None | Some("api_key") => AzureAuth::ApiKey(key),
+            Some("bearer") => AzureAuth::Bearer(key),
+            Some(other) => {
+                return Err(ConnectorError::Config(format!(
+                    "Unknown Azure auth mode: {}",
+                    other
+                )))
+            }
+        };
+
+        let mut provider = Self::new(&resource, auth);
+        if let Some(version) = settings.get("api_version").and_then(Value::as_str) {
+            provider.api_version = version.to_string();
+        }
+        if let Some(deployments) = settings.get("deployments") {
+            provider.deployments = serde_json::from_value(deployments.clone())
+                .map_err(|e| ConnectorError::Config(format!("Invalid Azure deployments: {}", e)))?;
+        }
+        provider.default_deployment = settings
+            .get("deployment")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "azure" entry of a config adapter
+    ///
+    /// The key is read from the credential store if the configuration does
+    /// not carry one.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut config = adapter.get_provider_config("azure")?.clone();
+        if config.api_key.is_none() {
+            config.api_key = Some(adapter.get_credential("azure", "api_key")?);
+        }
+        Self::from_config(&config)
+    }
+
+    /// Map a model name to a deployment name
+    pub fn with_deployment(
+        mut self,
+        model: impl Into<String>,
+        deployment: impl Into<String>,
+    ) -> Self {
+        self.deployments.insert(model.into(), deployment.into());
+        self
+    }
+
+    /// Deployment used for models without an explicit mapping
+    pub fn with_default_deployment(mut self, deployment: impl Into<String>) -> Self {
+        self.default_deployment = Some(deployment.into());
+        self
+    }
+
+    /// Override the API version
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    /// Deployment serving the given model
+    ///
+    /// Falls back to the default deployment, then to the model name itself.
+    pub fn deployment_for<'a>(&'a self, model: &'a str) -> &'a str {
+        self.deployments
+            .get(model)
+            .or(self.default_deployment.as_ref())
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+
+    /// Chat-completions URL for a model
+    pub fn chat_completions_url(&self, model: &str) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.base_url,
+            self.deployment_for(model),
+            self.api_version
+        )
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth {
+            AzureAuth::ApiKey(key) => request.header("api-key", key),
+            AzureAuth::Bearer(token) => request.bearer_auth(token),
+        }
+    }
+
+    /// Query the resource's model list
+    async fn fetch_models(&self) -> Result<Vec<String>> {
+        let url = format!(
+            "{}/openai/models?api-version={}",
+            self.base_url, self.api_version
+        );
+        let response = http::send(self.name(), self.authorize(self.client.get(&url))).await?;
+        if !response.is_success() {
+            return Err(errors::azure(self.name(), &response));
+        }
+        let models: ModelList = response.json(self.name())?;
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+}
+
+/// Base URL for a resource name or URL
+fn resource_url(resource: &str) -> String {
+    if resource.starts_with("http://") || resource.starts_with("https://") {
+        resource.trim_end_matches('/').to_string()
+    } else {
+        format!("https://{}.openai.azure.com", resource)
+    }
+}
+
+#[async_trait]
+impl Provider for AzureOpenAIProvider {
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: true,
+            vision: true,
+            json_mode: true,
+            max_tokens: None,
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.stream = false;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = self.chat_completions_url(&request.model);
+        debug!(url = %url, model = %request.model, "Sending Azure OpenAI chat completion");
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.authorize(self.client.post(&url)).json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::azure(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let chat: ChatResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+                ConnectorError::Internal(format!("Unexpected Azure OpenAI response: {}", e))
+            })?;
+            Ok((raw, chat.into_unified(self.name())))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.stream = true;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let url = self.chat_completions_url(&request.model);
+        debug!(url = %url, model = %request.model, "Streaming Azure OpenAI chat completion");
+        openai::stream_chat(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.authorize(self.client.post(&url)).json(&body),
+            &body,
+            errors::azure,
+        )
+        .await
+    }
+
+    /// Models with a configured deployment, or the resource's model list
+    async fn list_models(&self) -> Result<Vec<String>> {
+        if !self.deployments.is_empty() {
+            let mut models: Vec<String> = self.deployments.keys().cloned().collect();
+            models.sort();
+            return Ok(models);
+        }
+        self.fetch_models().await
+    }
+
+    /// Reports `Healthy` if the resource's model list can be fetched, even
+    /// when deployments are configured
+    async fn health_check(&self) -> Result<HealthStatus> {
+        match self.fetch_models().await {
+            Ok(_) => Ok(HealthStatus::Healthy),
+            Err(e) => {
+                debug!(provider = self.name(), error = %e, "Health check failed");
+                Ok(HealthStatus::Unhealthy)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FinishReason, Message};
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn azure_config(endpoint: Option<String>) -> ProviderConfig {
+        let mut settings = HashMap::new();
+        settings.insert("resource".to_string(), json!("my-resource"));
+        settings.insert(
+            "deployments".to_string(),
+            json!({"gpt-4": "prod-gpt4", "gpt-35-turbo": "chat35"}),
+        );
+        settings.insert("api_version".to_string(), json!("2024-06-01"));
+        ProviderConfig {
+            provider: "azure".to_string(),
+            endpoint,
+            api_key: Some("azure-key".to_string()),
+            models: vec![],
+            settings,
+        }
+    }
+
+    #[test]
+    fn test_deployment_url_resolution() {
+        let provider = AzureOpenAIProvider::from_config(&azure_config(None)).unwrap();
+        assert_eq!(
+            provider.chat_completions_url("gpt-4"),
+            "https://my-resource.openai.azure.com/openai/deployments/prod-gpt4/chat/completions?api-version=2024-06-01"
+        );
+        // Unmapped models fall back to the model name
+        assert_eq!(provider.deployment_for("gpt-4o"), "gpt-4o");
+
+        let provider = provider.with_default_deployment("catch-all");
+        assert_eq!(provider.deployment_for("gpt-4o"), "catch-all");
+        assert_eq!(provider.deployment_for("gpt-35-turbo"), "chat35");
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let mut config = azure_config(None);
+        config.settings.remove("resource");
+        assert!(AzureOpenAIProvider::from_config(&config).is_err());
+
+        let mut config = azure_config(None);
+        config
+            .settings
+            .insert("auth".to_string(), json!("certificate"));
+        assert!(AzureOpenAIProvider::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/prod-gpt4/chat/completions"))
+            .and(query_param("api-version", "2024-06-01"))
+            .and(header("api-key", "azure-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-az",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": "gpt-4",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hello from Azure"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 4, "total_tokens": 9}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AzureOpenAIProvider::from_config(&azure_config(Some(server.uri()))).unwrap();
+        let request = CompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hello from Azure"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.metadata.unwrap().provider, "azure");
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_bearer_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt-4o/chat/completions"))
+            .and(header("authorization", "Bearer aad-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-az",
+                "created": 1700000000,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "ok"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider =
+            AzureOpenAIProvider::new(&server.uri(), AzureAuth::Bearer("aad-token".to_string()));
+        let request = CompletionRequest::new("gpt-4o", vec![Message::user("Hi")]);
+        assert_eq!(
+            provider.complete(&request).await.unwrap().text().as_deref(),
+            Some("ok")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_with_prompt_filter_results() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"choices\":[],\"created\":0,\"id\":\"\",\"model\":\"\",\"object\":\"\",\"prompt_filter_results\":[{\"prompt_index\":0,\"content_filter_results\":{\"hate\":{\"filtered\":false,\"severity\":\"safe\"}}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"\",\"role\":\"assistant\"},\"finish_reason\":null,\"index\":0}],\"created\":1727000000,\"id\":\"chatcmpl-az\",\"model\":\"gpt-4-0613\",\"object\":\"chat.completion.chunk\"}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello from Azure\"},\"finish_reason\":\"stop\",\"index\":0}],\"created\":1727000000,\"id\":\"chatcmpl-az\",\"model\":\"gpt-4-0613\",\"object\":\"chat.completion.chunk\"}\n\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/prod-gpt4/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AzureOpenAIProvider::from_config(&azure_config(Some(server.uri()))).unwrap();
+        let request = CompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = crate::streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.id, "chatcmpl-az");
+        assert_eq!(response.model, "gpt-4-0613");
+        assert_eq!(response.text().as_deref(), Some("Hello from Azure"));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_from_deployments() {
+        let provider = AzureOpenAIProvider::from_config(&azure_config(None)).unwrap();
+        assert_eq!(
+            provider.list_models().await.unwrap(),
+            vec!["gpt-35-turbo".to_string(), "gpt-4".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_check_queries_resource() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/openai/models"))
+            .and(query_param("api-version", "2024-06-01"))
+            .and(header("api-key", "azure-key"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"object": "list", "data": [{"id": "gpt-4"}]})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Deployments are configured, but the probe still reaches the resource
+        let provider = AzureOpenAIProvider::from_config(&azure_config(Some(server.uri()))).unwrap();
+        assert_eq!(
+            provider.health_check().await.unwrap(),
+            HealthStatus::Healthy
+        );
+
+        let unreachable = MockServer::start().await;
+        let provider =
+            AzureOpenAIProvider::from_config(&azure_config(Some(unreachable.uri()))).unwrap();
+        assert_eq!(
+            provider.health_check().await.unwrap(),
+            HealthStatus::Unhealthy
+        );
+    }
+}
@@ -0,0 +1,1209 @@
+//! # AWS Bedrock Provider
+//!
+//! Native client for the Bedrock runtime, signing requests with AWS SigV4
+//! directly instead of depending on the AWS SDK.
+//!
+//! Two APIs are supported:
+//!
+//! - **Converse** (default): a model-agnostic chat API with tool use
+//! - **InvokeModel**: the raw model-family specific bodies for Anthropic
+//!   Claude, Meta Llama and Amazon Titan models
+//!
+//! Both are also available as streams (`ConverseStream` and
+//! `InvokeModelWithResponseStream`), decoded from the binary AWS event-stream
+//! framing.
+//!
+//! Throttling and validation exceptions are mapped to
+//! `ConnectorError::RateLimited` and `ConnectorError::InvalidRequest`, whether
+//! returned as an HTTP error or raised mid-stream.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::bedrock::BedrockProvider;
+//!
+//! // Reads BEDROCK_ACCESS_KEY_ID / BEDROCK_SECRET_ACCESS_KEY
+//! // (and optionally BEDROCK_SESSION_TOKEN) through the config adapter
+//! let mut config = ConfigAdapter::new();
+//! let provider = BedrockProvider::from_adapter(&mut config)?;
+//! let response = provider.complete(&request).await?;
+//! ```
+
+pub mod sigv4;
+mod stream;
+
+pub use sigv4::AwsCredentials;
+
+use super::anthropic::{self, MessagesResponse};
+use super::errors;
+use super::http::{self, HttpResponse, ProviderTelemetry};
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::types::{
+    Choice, CompletionRequest, CompletionResponse, ContentPart, FinishReason, Message,
+    MessageContent, ProviderMetadata, Role, ToolCall, Usage,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+/// Region used when none is configured
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// `anthropic_version` sent in InvokeModel bodies for Claude models
+pub const ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+/// `max_tokens` used when the request does not set one
+pub const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+/// SigV4 service name for both the runtime and control plane
+const SERVICE: &str = "bedrock";
+
+/// `Accept` header for streaming operations
+const EVENT_STREAM: &str = "application/vnd.amazon.eventstream";
+
+/// Cross-region inference profile prefixes (e.g. `us.anthropic.claude-...`)
+const REGION_PREFIXES: &[&str] = &["us", "eu", "apac", "us-gov", "global"];
+
+/// Bedrock runtime API used for completions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BedrockApi {
+    /// Model-agnostic Converse API
+    #[default]
+    Converse,
+    /// Model-family specific InvokeModel API
+    InvokeModel,
+}
+
+/// Model family, which determines the InvokeModel body format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    /// Anthropic Claude (Messages API body)
+    Anthropic,
+    /// Meta Llama (raw prompt)
+    Llama,
+    /// Amazon Titan Text
+    Titan,
+}
+
+impl ModelFamily {
+    /// Detect the family from a Bedrock model ID
+    ///
+    /// Cross-region inference profile prefixes such as `us.` are ignored.
+    pub fn from_model_id(model_id: &str) -> Option<Self> {
+        let mut segments = model_id.split('.');
+        let mut vendor = segments.next()?;
+        if REGION_PREFIXES.contains(&vendor) {
+            vendor = segments.next()?;
+        }
+        let model = segments.next().unwrap_or_default();
+
+        match vendor {
+            "anthropic" => Some(Self::Anthropic),
+            "meta" => Some(Self::Llama),
+            "amazon" if model.starts_with("titan-text") => Some(Self::Titan),
+            _ => None,
+        }
+    }
+}
+
+/// AWS Bedrock provider
+#[derive(Clone)]
+pub struct BedrockProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    control_endpoint: String,
+    region: String,
+    credentials: AwsCredentials,
+    api: BedrockApi,
+    default_max_tokens: u32,
+    telemetry: ProviderTelemetry,
+}
+
+impl BedrockProvider {
+    /// Create a provider for a region using the Converse API
+    pub fn new(region: impl Into<String>, credentials: AwsCredentials) -> Self {
+        let region = region.into();
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: format!("https://bedrock-runtime.{}.amazonaws.com", region),
+            control_endpoint: format!("https://bedrock.{}.amazonaws.com", region),
+            region,
+            credentials,
+            api: BedrockApi::default(),
+            default_max_tokens: DEFAULT_MAX_TOKENS,
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// Recognized settings: `region`, `api` (`"converse"` or `"invoke"`),
+    /// `default_max_tokens`. The configured endpoint, if any, replaces the
+    /// regional runtime endpoint.
+    pub fn from_config(config: &ProviderConfig, credentials: AwsCredentials) -> Result<Self> {
+        let region = config
+            .settings
+            .get("region")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_REGION);
+
+        let mut provider = Self::new(region, credentials);
+        if let Some(endpoint) = &config.endpoint {
+            provider.endpoint = endpoint.clone();
+        }
+        if let Some(api) = config.settings.get("api").and_then(Value::as_str) {
+            provider.api = match api {
+                "converse" => BedrockApi::Converse,
+                "invoke" => BedrockApi::InvokeModel,
+                other => {
+                    return Err(ConnectorError::Config(format!(
+                        "Unknown Bedrock api setting: {} (expected \"converse\" or \"invoke\")",
+                        other
+                    )))
+                }
+            };
+        }
+        if let Some(max_tokens) = config
+            .settings
+            .get("default_max_tokens")
+            .and_then(Value::as_u64)
+        {
+            provider.default_max_tokens = u32::try_from(max_tokens).map_err(|_| {
+                ConnectorError::Config(format!("default_max_tokens out of range: {}", max_tokens))
+            })?;
+        }
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "bedrock" entry of a config adapter
+    ///
+    /// Credentials are read with `get_credential("bedrock", ...)` for
+    /// `access_key_id`, `secret_access_key` and the optional `session_token`.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut credentials = AwsCredentials::new(
+            adapter.get_credential("bedrock", "access_key_id")?,
+            adapter.get_credential("bedrock", "secret_access_key")?,
+        );
+        if let Ok(token) = adapter.get_credential("bedrock", "session_token") {
+            credentials = credentials.with_session_token(token);
+        }
+
+        let config = adapter.get_provider_config("bedrock")?.clone();
+        Self::from_config(&config, credentials)
+    }
+
+    /// Override the runtime endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Override the control-plane endpoint used by `list_models`
+    pub fn with_control_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.control_endpoint = endpoint.into();
+        self
+    }
+
+    /// Select the runtime API
+    pub fn with_api(mut self, api: BedrockApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Set the `max_tokens` used when the request does not set one
+    pub fn with_default_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.default_max_tokens = max_tokens;
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    /// Runtime URL for a model operation (`converse` or `invoke`)
+    fn model_url(&self, model: &str, operation: &str) -> String {
+        http::join_url(
+            &self.endpoint,
+            &format!("model/{}/{}", sigv4::uri_encode(model), operation),
+        )
+    }
+
+    /// Build the request body for the configured API
+    fn build_body(&self, request: &CompletionRequest) -> Result<Value> {
+        match self.api {
+            BedrockApi::Converse => to_value(converse_request(request)?),
+            BedrockApi::InvokeModel => invoke_body(request, self.default_max_tokens),
+        }
+    }
+
+    /// Sign and send a request
+    async fn send_signed(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Vec<u8>,
+    ) -> Result<HttpResponse> {
+        let request = self.signed_request(method, url, body, "application/json")?;
+        http::send(self.name(), request).await
+    }
+
+    /// Build a SigV4-signed request
+    fn signed_request(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Vec<u8>,
+        accept: &str,
+    ) -> Result<reqwest::RequestBuilder> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| ConnectorError::Config(format!("Invalid Bedrock URL {}: {}", url, e)))?;
+
+        let mut headers = vec![("accept".to_string(), accept.to_string())];
+        if !body.is_empty() {
+            headers.push(("content-type".to_string(), "application/json".to_string()));
+        }
+        let signed = sigv4::sign(
+            &sigv4::SignableRequest {
+                method: method.as_str(),
+                url: &url,
+                headers: &headers,
+                body: &body,
+            },
+            &self.credentials,
+            &self.region,
+            SERVICE,
+            chrono::Utc::now(),
+        );
+
+        let mut builder = self.client.request(method, url);
+        for (name, value) in headers.iter().chain(signed.iter()) {
+            builder = builder.header(name, value);
+        }
+        Ok(builder.body(body))
+    }
+}
+
+#[async_trait]
+impl Provider for BedrockProvider {
+    fn name(&self) -> &str {
+        "bedrock"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: self.api == BedrockApi::Converse,
+            vision: true,
+            json_mode: false,
+            max_tokens: None,
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let body = self.build_body(request)?;
+        let payload = serde_json::to_vec(&body)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let operation = match self.api {
+            BedrockApi::Converse => "converse",
+            BedrockApi::InvokeModel => "invoke",
+        };
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = self.model_url(&request.model, operation);
+        debug!(url = %url, model = %request.model, "Sending Bedrock request");
+
+        let result = async {
+            let response = self
+                .send_signed(reqwest::Method::POST, &url, payload)
+                .await?;
+            if !response.is_success() {
+                return Err(errors::bedrock(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let unified = match self.api {
+                BedrockApi::Converse => {
+                    from_value::<ConverseResponse>(raw.clone())?.into_unified(&request.model)
+                }
+                BedrockApi::InvokeModel => parse_invoke_response(&request.model, raw.clone())?,
+            };
+            Ok((raw, unified))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let body = self.build_body(request)?;
+        let payload = serde_json::to_vec(&body)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let (operation, parser) = match self.api {
+            BedrockApi::Converse => (
+                "converse-stream",
+                stream::BedrockStreamParser::converse(&request.model),
+            ),
+            BedrockApi::InvokeModel => (
+                "invoke-with-response-stream",
+                stream::BedrockStreamParser::invoke(&request.model, model_family(&request.model)?),
+            ),
+        };
+        let url = self.model_url(&request.model, operation);
+        debug!(url = %url, model = %request.model, "Streaming Bedrock request");
+
+        let builder = self.signed_request(reqwest::Method::POST, &url, payload, EVENT_STREAM)?;
+        http::send_stream(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            builder,
+            &body,
+            errors::bedrock,
+            move |response| stream::decode(response.bytes_stream(), parser).boxed(),
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.control_endpoint, "foundation-models");
+        let response = self
+            .send_signed(reqwest::Method::GET, &url, Vec::new())
+            .await?;
+        if !response.is_success() {
+            return Err(errors::bedrock(self.name(), &response));
+        }
+        let models: FoundationModels = response.json(self.name())?;
+        Ok(models
+            .model_summaries
+            .into_iter()
+            .map(|model| model.model_id)
+            .collect())
+    }
+}
+
+/// Map a Converse `stopReason`
+fn map_stop_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "guardrail_intervened" | "content_filtered" => Some(FinishReason::ContentFilter),
+        other => anthropic::map_stop_reason(other),
+    }
+}
+
+fn to_value<T: Serialize>(body: T) -> Result<Value> {
+    serde_json::to_value(body)
+        .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(raw: Value) -> Result<T> {
+    serde_json::from_value(raw)
+        .map_err(|e| ConnectorError::Internal(format!("Unexpected Bedrock response: {}", e)))
+}
+
+fn unified_response(
+    model: &str,
+    message: Message,
+    finish_reason: Option<FinishReason>,
+    usage: Usage,
+) -> CompletionResponse {
+    CompletionResponse {
+        id: format!("bedrock-{}", uuid::Uuid::new_v4()),
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp(),
+        model: model.to_string(),
+        choices: vec![Choice {
+            index: 0,
+            message,
+            finish_reason,
+        }],
+        usage,
+        metadata: Some(ProviderMetadata {
+            provider: "bedrock".to_string(),
+            model: model.to_string(),
+            ..Default::default()
+        }),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Converse API
+// ----------------------------------------------------------------------------
+
+/// Build a Converse request body
+fn converse_request(request: &CompletionRequest) -> Result<ConverseRequest> {
+    let mut system = Vec::new();
+    let mut messages: Vec<ConverseMessage> = Vec::new();
+
+    for message in &request.messages {
+        let (role, content) = match message.role {
+            Role::System => {
+                system.push(SystemBlock {
+                    text: message.text(),
+                });
+                continue;
+            }
+            Role::Tool | Role::Function => {
+                let tool_use_id = message.tool_call_id.clone().ok_or_else(|| {
+                    ConnectorError::Schema("Tool result message requires tool_call_id".to_string())
+                })?;
+                (
+                    Role::User,
+                    vec![ConverseBlock::ToolResult(ConverseToolResult {
+                        tool_use_id,
+                        content: vec![SystemBlock {
+                            text: message.text(),
+                        }],
+                    })],
+                )
+            }
+            Role::User => (Role::User, converse_blocks(&message.content)),
+            Role::Assistant => {
+                let mut blocks = converse_blocks(&message.content);
+                for call in &message.tool_calls {
+                    let input = serde_json::from_str(&call.function.arguments).map_err(|e| {
+                        ConnectorError::Schema(format!(
+                            "Tool call {} has invalid JSON arguments: {}",
+                            call.id, e
+                        ))
+                    })?;
+                    blocks.push(ConverseBlock::ToolUse(ConverseToolUse {
+                        tool_use_id: call.id.clone(),
+                        name: call.function.name.clone(),
+                        input,
+                    }));
+                }
+                (Role::Assistant, blocks)
+            }
+        };
+
+        // Converse requires alternating roles, like the Messages API
+        match messages.last_mut() {
+            Some(last) if last.role == role => last.content.extend(content),
+            _ => messages.push(ConverseMessage { role, content }),
+        }
+    }
+
+    if messages.is_empty() {
+        return Err(ConnectorError::Schema(
+            "Bedrock requests require at least one non-system message".to_string(),
+        ));
+    }
+
+    let tool_config = (!request.tools.is_empty()).then(|| ToolConfig {
+        tools: request
+            .tools
+            .iter()
+            .map(|tool| ConverseTool {
+                tool_spec: ToolSpec {
+                    name: tool.function.name.clone(),
+                    description: tool.function.description.clone(),
+                    input_schema: json!({
+                        "json": tool
+                            .function
+                            .parameters
+                            .clone()
+                            .unwrap_or_else(|| json!({"type": "object"}))
+                    }),
+                },
+            })
+            .collect(),
+    });
+
+    Ok(ConverseRequest {
+        messages,
+        system,
+        inference_config: InferenceConfig {
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            top_p: request.top_p,
+            stop_sequences: request.stop.clone(),
+        },
+        tool_config,
+        additional_model_request_fields: request.top_k.map(|top_k| json!({ "top_k": top_k })),
+    })
+}
+
+/// Convert unified content into Converse content blocks
+fn converse_blocks(content: &MessageContent) -> Vec<ConverseBlock> {
+    match content {
+        MessageContent::Text(text) if text.is_empty() => Vec::new(),
+        MessageContent::Text(text) => vec![ConverseBlock::Text(text.clone())],
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .filter_map(|part| {
+                let url = match part {
+                    ContentPart::Text { text } => return Some(ConverseBlock::Text(text.clone())),
+                    ContentPart::ImageUrl { image_url, .. } => image_url,
+                    ContentPart::ImageBase64 { image_base64, .. } => image_base64,
+                };
+                let inline = http::parse_data_url(url).or_else(|| {
+                    matches!(part, ContentPart::ImageBase64 { .. })
+                        .then(|| ("image/jpeg".to_string(), url.clone()))
+                });
+                match inline {
+                    Some((media_type, data)) => Some(ConverseBlock::Image(ConverseImage {
+                        format: media_type.trim_start_matches("image/").to_string(),
+                        source: ImageBytes { bytes: data },
+                    })),
+                    None => {
+                        warn!("Bedrock Converse only accepts inline images; skipping image URL");
+                        None
+                    }
+                }
+            })
+            .collect(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseRequest {
+    messages: Vec<ConverseMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    system: Vec<SystemBlock>,
+    inference_config: InferenceConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_config: Option<ToolConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    additional_model_request_fields: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConverseMessage {
+    role: Role,
+    content: Vec<ConverseBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SystemBlock {
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ConverseBlock {
+    Text(String),
+    Image(ConverseImage),
+    ToolUse(ConverseToolUse),
+    ToolResult(ConverseToolResult),
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConverseImage {
+    format: String,
+    source: ImageBytes,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ImageBytes {
+    bytes: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseToolUse {
+    tool_use_id: String,
+    name: String,
+    input: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseToolResult {
+    tool_use_id: String,
+    content: Vec<SystemBlock>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InferenceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ToolConfig {
+    tools: Vec<ConverseTool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseTool {
+    tool_spec: ToolSpec,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolSpec {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    input_schema: Value,
+}
+
+/// Converse response body
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseResponse {
+    output: ConverseOutput,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: ConverseUsage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ConverseOutput {
+    message: ConverseResponseMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ConverseResponseMessage {
+    #[serde(default)]
+    content: Vec<ConverseResponseBlock>,
+}
+
+/// Response content block; block types other than text and tool use are
+/// ignored
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseResponseBlock {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    tool_use: Option<ConverseToolUse>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+impl ConverseResponse {
+    fn into_unified(self, model: &str) -> CompletionResponse {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for block in self.output.message.content {
+            if let Some(t) = block.text {
+                text.push_str(&t);
+            }
+            if let Some(call) = block.tool_use {
+                tool_calls.push(ToolCall::function(
+                    call.tool_use_id,
+                    call.name,
+                    call.input.to_string(),
+                ));
+            }
+        }
+
+        unified_response(
+            model,
+            Message::assistant(text).with_tool_calls(tool_calls),
+            self.stop_reason.as_deref().and_then(map_stop_reason),
+            Usage::new(self.usage.input_tokens, self.usage.output_tokens),
+        )
+    }
+}
+
+// ----------------------------------------------------------------------------
+// InvokeModel API
+// ----------------------------------------------------------------------------
+
+fn model_family(model: &str) -> Result<ModelFamily> {
+    ModelFamily::from_model_id(model).ok_or_else(|| {
+        ConnectorError::InvalidRequest(ProviderError::new(
+            "bedrock",
+            format!(
+                "InvokeModel does not support model {}; use the Converse API",
+                model
+            ),
+        ))
+    })
+}
+
+/// Build the model-family specific InvokeModel body
+fn invoke_body(request: &CompletionRequest, default_max_tokens: u32) -> Result<Value> {
+    let max_tokens = request.max_tokens.unwrap_or(default_max_tokens);
+
+    match model_family(&request.model)? {
+        ModelFamily::Anthropic => {
+            let mut body = to_value(anthropic::messages_request(request, default_max_tokens)?)?;
+            if let Some(fields) = body.as_object_mut() {
+                // The model is addressed by the URL and streaming by the operation
+                fields.remove("model");
+                fields.remove("stream");
+                fields.insert("anthropic_version".to_string(), json!(ANTHROPIC_VERSION));
+            }
+            Ok(body)
+        }
+        ModelFamily::Llama => to_value(LlamaRequest {
+            prompt: llama3_prompt(&request.messages),
+            max_gen_len: max_tokens,
+            temperature: request.temperature,
+            top_p: request.top_p,
+        }),
+        ModelFamily::Titan => to_value(TitanRequest {
+            input_text: titan_prompt(&request.messages),
+            text_generation_config: TitanConfig {
+                max_token_count: max_tokens,
+                temperature: request.temperature,
+                top_p: request.top_p,
+                stop_sequences: request.stop.clone(),
+            },
+        }),
+    }
+}
+
+/// Decode an InvokeModel response for the model's family
+fn parse_invoke_response(model: &str, raw: Value) -> Result<CompletionResponse> {
+    match model_family(model)? {
+        ModelFamily::Anthropic => {
+            let mut response = from_value::<MessagesResponse>(raw)?.into_unified("bedrock");
+            response.model = model.to_string();
+            Ok(response)
+        }
+        ModelFamily::Llama => {
+            let response: LlamaResponse = from_value(raw)?;
+            Ok(unified_response(
+                model,
+                Message::assistant(response.generation),
+                response
+                    .stop_reason
+                    .as_deref()
+                    .and_then(llama_finish_reason),
+                Usage::new(response.prompt_token_count, response.generation_token_count),
+            ))
+        }
+        ModelFamily::Titan => {
+            let response: TitanResponse = from_value(raw)?;
+            let result = response.results.into_iter().next().ok_or_else(|| {
+                ConnectorError::Internal("Titan response contained no results".to_string())
+            })?;
+            Ok(unified_response(
+                model,
+                Message::assistant(result.output_text),
+                result
+                    .completion_reason
+                    .as_deref()
+                    .and_then(titan_finish_reason),
+                Usage::new(response.input_text_token_count, result.token_count),
+            ))
+        }
+    }
+}
+
+/// Map a Llama `stop_reason`
+fn llama_finish_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "stop" => Some(FinishReason::Stop),
+        "length" => Some(FinishReason::Length),
+        _ => None,
+    }
+}
+
+/// Map a Titan `completionReason`
+fn titan_finish_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "FINISH" | "STOP_CRITERIA_MET" => Some(FinishReason::Stop),
+        "LENGTH" => Some(FinishReason::Length),
+        "CONTENT_FILTERED" => Some(FinishReason::ContentFilter),
+        _ => None,
+    }
+}
+
+/// Render a conversation in the Llama 3 instruct prompt format
+fn llama3_prompt(messages: &[Message]) -> String {
+    let mut prompt = String::from("<|begin_of_text|>");
+    for message in messages {
+        let role = match message.role {
+            Role::System => "system",
+            Role::Assistant => "assistant",
+            Role::Tool | Role::Function => "ipython",
+            Role::User => "user",
+        };
+        prompt.push_str(&format!(
+            "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+            role,
+            message.text()
+        ));
+    }
+    prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+    prompt
+}
+
+/// Render a conversation as a Titan `User:`/`Bot:` transcript
+fn titan_prompt(messages: &[Message]) -> String {
+    let mut lines = Vec::new();
+    for message in messages {
+        match message.role {
+            Role::System => lines.push(message.text()),
+            Role::Assistant => lines.push(format!("Bot: {}", message.text())),
+            _ => lines.push(format!("User: {}", message.text())),
+        }
+    }
+    lines.push("Bot:".to_string());
+    lines.join("\n")
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LlamaRequest {
+    prompt: String,
+    max_gen_len: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LlamaResponse {
+    generation: String,
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    generation_token_count: u32,
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TitanRequest {
+    input_text: String,
+    text_generation_config: TitanConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TitanConfig {
+    max_token_count: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TitanResponse {
+    #[serde(default)]
+    input_text_token_count: u32,
+    #[serde(default)]
+    results: Vec<TitanResult>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TitanResult {
+    #[serde(default)]
+    token_count: u32,
+    output_text: String,
+    #[serde(default)]
+    completion_reason: Option<String>,
+}
+
+/// `ListFoundationModels` response body
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundationModels {
+    #[serde(default)]
+    model_summaries: Vec<FoundationModel>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundationModel {
+    model_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const HAIKU: &str = "anthropic.claude-3-haiku-20240307-v1:0";
+
+    fn credentials() -> AwsCredentials {
+        AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY")
+    }
+
+    #[test]
+    fn test_model_family_detection() {
+        assert_eq!(
+            ModelFamily::from_model_id(HAIKU),
+            Some(ModelFamily::Anthropic)
+        );
+        assert_eq!(
+            ModelFamily::from_model_id("us.anthropic.claude-3-5-sonnet-20241022-v2:0"),
+            Some(ModelFamily::Anthropic)
+        );
+        assert_eq!(
+            ModelFamily::from_model_id("meta.llama3-8b-instruct-v1:0"),
+            Some(ModelFamily::Llama)
+        );
+        assert_eq!(
+            ModelFamily::from_model_id("amazon.titan-text-express-v1"),
+            Some(ModelFamily::Titan)
+        );
+        assert_eq!(ModelFamily::from_model_id("amazon.nova-pro-v1:0"), None);
+    }
+
+    #[test]
+    fn test_converse_request_body() {
+        let request = CompletionRequest::builder(HAIKU)
+            .message(Message::system("Be brief."))
+            .message(Message::user("Weather in Paris?"))
+            .message(
+                Message::assistant("").with_tool_calls(vec![ToolCall::function(
+                    "tooluse_1",
+                    "get_weather",
+                    "{\"city\":\"Paris\"}",
+                )]),
+            )
+            .message(Message::tool("tooluse_1", "18C"))
+            .max_tokens(100)
+            .top_k(40)
+            .build()
+            .unwrap();
+
+        let body = to_value(converse_request(&request).unwrap()).unwrap();
+        assert_eq!(body["system"][0]["text"], "Be brief.");
+        assert_eq!(
+            body["messages"][0]["content"][0]["text"],
+            "Weather in Paris?"
+        );
+        assert_eq!(
+            body["messages"][1]["content"][0]["toolUse"]["toolUseId"],
+            "tooluse_1"
+        );
+        assert_eq!(
+            body["messages"][2]["content"][0]["toolResult"]["content"][0]["text"],
+            "18C"
+        );
+        assert_eq!(body["inferenceConfig"]["maxTokens"], 100);
+        assert_eq!(body["additionalModelRequestFields"]["top_k"], 40);
+    }
+
+    #[test]
+    fn test_invoke_bodies_per_family() {
+        let messages = vec![Message::system("Be brief."), Message::user("Hi")];
+
+        let request = CompletionRequest::new(HAIKU, messages.clone());
+        let body = invoke_body(&request, DEFAULT_MAX_TOKENS).unwrap();
+        assert_eq!(body["anthropic_version"], ANTHROPIC_VERSION);
+        assert_eq!(body["system"], "Be brief.");
+        assert!(body.get("model").is_none());
+
+        let request = CompletionRequest::new("meta.llama3-8b-instruct-v1:0", messages.clone());
+        let body = invoke_body(&request, 256).unwrap();
+        assert_eq!(body["max_gen_len"], 256);
+        assert_eq!(
+            body["prompt"],
+            "<|begin_of_text|><|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+
+        let request = CompletionRequest::new("amazon.titan-text-express-v1", messages);
+        let body = invoke_body(&request, 256).unwrap();
+        assert_eq!(body["inputText"], "Be brief.\nUser: Hi\nBot:");
+        assert_eq!(body["textGenerationConfig"]["maxTokenCount"], 256);
+    }
+
+    #[tokio::test]
+    async fn test_converse_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/model/anthropic.claude-3-haiku-20240307-v1%3A0/converse",
+            ))
+            .and(header_exists("authorization"))
+            .and(header_exists("x-amz-date"))
+            .and(header("content-type", "application/json"))
+            .and(body_partial_json(
+                json!({"system": [{"text": "Be brief."}]}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "output": {"message": {"role": "assistant", "content": [{"text": "Bonjour"}]}},
+                "stopReason": "end_turn",
+                "usage": {"inputTokens": 12, "outputTokens": 3, "totalTokens": 15},
+                "metrics": {"latencyMs": 210}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = BedrockProvider::new("us-east-1", credentials()).with_endpoint(server.uri());
+        let request = CompletionRequest::new(
+            HAIKU,
+            vec![Message::system("Be brief."), Message::user("Hello")],
+        );
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Bonjour"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(12, 3));
+        assert_eq!(response.model, HAIKU);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_titan_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/model/amazon.titan-text-express-v1/invoke"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "inputTextTokenCount": 5,
+                "results": [{"tokenCount": 2, "outputText": " Hello!", "completionReason": "FINISH"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = BedrockProvider::new("us-east-1", credentials())
+            .with_endpoint(server.uri())
+            .with_api(BedrockApi::InvokeModel);
+        let request =
+            CompletionRequest::new("amazon.titan-text-express-v1", vec![Message::user("Hi")]);
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some(" Hello!"));
+        assert_eq!(response.usage, Usage::new(5, 2));
+    }
+
+    #[tokio::test]
+    async fn test_converse_stream_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/model/anthropic.claude-3-haiku-20240307-v1%3A0/converse-stream",
+            ))
+            .and(header("accept", EVENT_STREAM))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", EVENT_STREAM)
+                    .set_body_bytes(include_bytes!("testdata/converse.bin").to_vec()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = BedrockProvider::new("us-east-1", credentials()).with_endpoint(server.uri());
+        let request = CompletionRequest::new(HAIKU, vec![Message::user("Weather in Paris?")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = crate::streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Checking"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(response.usage, Usage::new(31, 17));
+        assert_eq!(response.model, HAIKU);
+    }
+
+    #[tokio::test]
+    async fn test_exception_mapping() {
+        let server = MockServer::start().await;
+        Mock::given(path("/model/throttled/converse"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header(
+                        "x-amzn-errortype",
+                        "ThrottlingException:http://internal.amazon.com/coral/com.amazon.bedrock/",
+                    )
+                    .set_body_json(json!({"message": "Too many requests, please wait."})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/model/invalid/converse"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "__type": "com.amazon.coral.validate#ValidationException",
+                "message": "The provided model identifier is invalid."
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = BedrockProvider::new("us-east-1", credentials()).with_endpoint(server.uri());
+
+        let request = CompletionRequest::new("throttled", vec![Message::user("Hi")]);
+        let err = provider.complete(&request).await.unwrap_err();
+        assert!(
+            matches!(err, ConnectorError::RateLimited { ref error, .. } if error.message.contains("Too many"))
+        );
+        assert_eq!(err.status(), Some(429));
+
+        let request = CompletionRequest::new("invalid", vec![Message::user("Hi")]);
+        let err = provider.complete(&request).await.unwrap_err();
+        assert!(
+            matches!(err, ConnectorError::InvalidRequest(ref e) if e.message.contains("identifier"))
+        );
+        assert!(err
+            .provider_error()
+            .and_then(|e| e.body.as_deref())
+            .unwrap()
+            .contains("ValidationException"));
+    }
+
+    #[test]
+    fn test_from_config_settings() {
+        let mut config = ProviderConfig {
+            provider: "bedrock".to_string(),
+            endpoint: None,
+            api_key: None,
+            models: vec![],
+            settings: Default::default(),
+        };
+        config
+            .settings
+            .insert("region".to_string(), json!("eu-west-3"));
+        config.settings.insert("api".to_string(), json!("invoke"));
+
+        let provider = BedrockProvider::from_config(&config, credentials()).unwrap();
+        assert_eq!(
+            provider.endpoint,
+            "https://bedrock-runtime.eu-west-3.amazonaws.com"
+        );
+        assert_eq!(provider.api, BedrockApi::InvokeModel);
+
+        config.settings.insert("api".to_string(), json!("stream"));
+        assert!(BedrockProvider::from_config(&config, credentials()).is_err());
+
+        config.settings.insert("api".to_string(), json!("converse"));
+        config
+            .settings
+            .insert("default_max_tokens".to_string(), json!(u64::MAX));
+        assert!(BedrockProvider::from_config(&config, credentials()).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+//! Bedrock streaming responses.
+//!
+//! Both streaming operations use the AWS event-stream framing. `ConverseStream`
+//! sends one modeled event per frame (`messageStart`, `contentBlockDelta`,
+//! ...), while `InvokeModelWithResponseStream` wraps the model's own stream
+//! format in base64 `chunk` events.
+
+use super::{llama_finish_reason, map_stop_reason, titan_finish_reason};
+use super::{ConverseUsage, ModelFamily};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::providers::errors::bedrock_exception;
+use crate::streaming::anthropic::{AnthropicEvent, AnthropicStreamParser};
+use crate::streaming::delta::until_error;
+use crate::streaming::eventstream::{self, EventPayload, Message};
+use crate::streaming::{ChunkDelta, StreamEvent};
+use crate::types::{Role, Usage};
+use futures::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Stream body format
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Converse,
+    Invoke(ModelFamily),
+}
+
+/// Converts event-stream messages into unified stream events
+#[derive(Debug, Clone)]
+pub(super) struct BedrockStreamParser {
+    model: String,
+    format: Format,
+    started: bool,
+    /// Tool-call indices of Converse `toolUse` blocks by content block index
+    tool_blocks: HashMap<u32, u32>,
+    anthropic: AnthropicStreamParser,
+}
+
+impl BedrockStreamParser {
+    /// Parser for a `ConverseStream` response
+    pub(super) fn converse(model: &str) -> Self {
+        Self::new(model, Format::Converse)
+    }
+
+    /// Parser for an `InvokeModelWithResponseStream` response
+    pub(super) fn invoke(model: &str, family: ModelFamily) -> Self {
+        Self::new(model, Format::Invoke(family))
+    }
+
+    fn new(model: &str, format: Format) -> Self {
+        Self {
+            model: model.to_string(),
+            format,
+            started: false,
+            tool_blocks: HashMap::new(),
+            anthropic: AnthropicStreamParser::new(),
+        }
+    }
+
+    /// Parse one event-stream message
+    ///
+    /// Exceptions are returned as `Err`.
+    pub(super) fn parse(&mut self, message: Message) -> Result<Vec<StreamEvent>> {
+        let (event_type, payload) = match message.into_payload()? {
+            EventPayload::Event {
+                event_type,
+                payload,
+            } => (event_type, payload),
+            EventPayload::Exception {
+                exception_type,
+                message,
+            } => {
+                let error =
+                    ProviderError::new("bedrock", format!("{}: {}", exception_type, message));
+                return Err(bedrock_exception(&exception_type, error));
+            }
+            EventPayload::Error { code, message } => {
+                return Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                    "bedrock",
+                    format!("{}: {}", code, message),
+                )))
+            }
+        };
+
+        match self.format {
+            Format::Converse => self.parse_converse(&event_type, &payload),
+            Format::Invoke(_) if event_type != "chunk" => Ok(Vec::new()),
+            Format::Invoke(ModelFamily::Anthropic) => {
+                let mut events = self
+                    .anthropic
+                    .parse(from_slice::<AnthropicEvent>(&payload)?)?;
+                // Bedrock reports the upstream model name; use the requested ID
+                // as non-streaming InvokeModel does
+                for event in &mut events {
+                    if let StreamEvent::Start { model, .. } = event {
+                        *model = self.model.clone();
+                    }
+                }
+                Ok(events)
+            }
+            Format::Invoke(ModelFamily::Llama) => {
+                let chunk: LlamaChunk = from_slice(&payload)?;
+                let mut events = self.start();
+                events.extend(text_event(chunk.generation));
+                events.extend(
+                    chunk
+                        .stop_reason
+                        .as_deref()
+                        .and_then(llama_finish_reason)
+                        .map(finish_event),
+                );
+                events.extend(chunk.metrics.map(InvocationMetrics::usage_event));
+                Ok(events)
+            }
+            Format::Invoke(ModelFamily::Titan) => {
+                let chunk: TitanChunk = from_slice(&payload)?;
+                let mut events = self.start();
+                events.extend(text_event(chunk.output_text));
+                events.extend(
+                    chunk
+                        .completion_reason
+                        .as_deref()
+                        .and_then(titan_finish_reason)
+                        .map(finish_event),
+                );
+                events.extend(chunk.metrics.map(InvocationMetrics::usage_event));
+                Ok(events)
+            }
+        }
+    }
+
+    fn parse_converse(&mut self, event_type: &str, payload: &[u8]) -> Result<Vec<StreamEvent>> {
+        let mut events = self.start();
+        match event_type {
+            "messageStart" => {
+                self.tool_blocks.clear();
+            }
+            "contentBlockStart" => {
+                let event: ContentBlockStart = from_slice(payload)?;
+                if let Some(tool) = event.start.tool_use {
+                    let index = self.tool_blocks.len() as u32;
+                    self.tool_blocks.insert(event.content_block_index, index);
+                    events.push(StreamEvent::delta(ChunkDelta::ToolCall {
+                        index,
+                        id: Some(tool.tool_use_id),
+                        name: Some(tool.name),
+                        arguments: String::new(),
+                    }));
+                }
+            }
+            "contentBlockDelta" => {
+                let event: ContentBlockDelta = from_slice(payload)?;
+                events.extend(event.delta.text.and_then(text_event));
+                if let Some(tool) = event.delta.tool_use {
+                    let index = self
+                        .tool_blocks
+                        .get(&event.content_block_index)
+                        .copied()
+                        .ok_or_else(|| {
+                            ConnectorError::Internal(format!(
+                                "bedrock toolUse delta for unknown block {}",
+                                event.content_block_index
+                            ))
+                        })?;
+                    events.push(StreamEvent::delta(ChunkDelta::ToolCall {
+                        index,
+                        id: None,
+                        name: None,
+                        arguments: tool.input,
+                    }));
+                }
+            }
+            "messageStop" => {
+                let event: MessageStop = from_slice(payload)?;
+                events.extend(
+                    event
+                        .stop_reason
+                        .as_deref()
+                        .and_then(map_stop_reason)
+                        .map(finish_event),
+                );
+            }
+            "metadata" => {
+                let event: Metadata = from_slice(payload)?;
+                if let Some(usage) = event.usage {
+                    events.push(StreamEvent::delta(ChunkDelta::Usage {
+                        usage: Usage::new(usage.input_tokens, usage.output_tokens),
+                    }));
+                }
+            }
+            // contentBlockStop and future event types carry nothing we need
+            _ => {}
+        }
+        Ok(events)
+    }
+
+    /// `Start` and `Role` events, before the first message only
+    fn start(&mut self) -> Vec<StreamEvent> {
+        if self.started {
+            return Vec::new();
+        }
+        self.started = true;
+        vec![
+            StreamEvent::Start {
+                id: format!("bedrock-{}", uuid::Uuid::new_v4()),
+                model: self.model.clone(),
+            },
+            StreamEvent::delta(ChunkDelta::Role {
+                role: Role::Assistant,
+            }),
+        ]
+    }
+}
+
+fn from_slice<T: DeserializeOwned>(payload: &[u8]) -> Result<T> {
+    serde_json::from_slice(payload)
+        .map_err(|e| ConnectorError::Internal(format!("Invalid Bedrock stream event: {}", e)))
+}
+
+fn text_event(text: String) -> Option<StreamEvent> {
+    (!text.is_empty()).then(|| StreamEvent::delta(ChunkDelta::Text { text }))
+}
+
+fn finish_event(reason: crate::types::FinishReason) -> StreamEvent {
+    StreamEvent::delta(ChunkDelta::Finish { reason })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentBlockStart {
+    content_block_index: u32,
+    start: BlockStart,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockStart {
+    #[serde(default)]
+    tool_use: Option<ToolUseStart>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolUseStart {
+    tool_use_id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentBlockDelta {
+    content_block_index: u32,
+    delta: BlockDelta,
+}
+
+/// Delta of a content block; reasoning and other block types are ignored
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockDelta {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    tool_use: Option<ToolUseDelta>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ToolUseDelta {
+    #[serde(default)]
+    input: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageStop {
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Metadata {
+    #[serde(default)]
+    usage: Option<ConverseUsage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LlamaChunk {
+    #[serde(default)]
+    generation: String,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(rename = "amazon-bedrock-invocationMetrics", default)]
+    metrics: Option<InvocationMetrics>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TitanChunk {
+    #[serde(default)]
+    output_text: String,
+    #[serde(default)]
+    completion_reason: Option<String>,
+    #[serde(rename = "amazon-bedrock-invocationMetrics", default)]
+    metrics: Option<InvocationMetrics>,
+}
+
+/// Token counts Bedrock appends to the last chunk of an InvokeModel stream
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvocationMetrics {
+    #[serde(default)]
+    input_token_count: u32,
+    #[serde(default)]
+    output_token_count: u32,
+}
+
+impl InvocationMetrics {
+    fn usage_event(self) -> StreamEvent {
+        StreamEvent::delta(ChunkDelta::Usage {
+            usage: Usage::new(self.input_token_count, self.output_token_count),
+        })
+    }
+}
+
+/// Decode an event-stream body with the given parser
+///
+/// Ends at the first error, including modeled exceptions.
+pub(super) fn decode<S, B, E>(
+    bytes: S,
+    mut parser: BedrockStreamParser,
+) -> impl Stream<Item = Result<StreamEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let events = eventstream::decode(bytes).flat_map(move |message| {
+        let events: Vec<Result<StreamEvent>> = match message.and_then(|m| parser.parse(m)) {
+            Ok(events) => events.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(events)
+    });
+    until_error(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::aggregate;
+    use crate::types::{FinishReason, ToolCall};
+
+    async fn decode_fixture(
+        fixture: &'static [u8],
+        parser: BedrockStreamParser,
+    ) -> Vec<Result<StreamEvent>> {
+        // Split frames across reads to exercise reassembly
+        let chunks: Vec<std::result::Result<&[u8], String>> = fixture.chunks(37).map(Ok).collect();
+        decode(stream::iter(chunks), parser).collect().await
+    }
+
+    #[tokio::test]
+    async fn test_converse_stream_with_tool_use() {
+        let parser = BedrockStreamParser::converse("anthropic.claude-3-haiku-20240307-v1:0");
+        let events = decode_fixture(include_bytes!("testdata/converse.bin"), parser).await;
+        let response = aggregate(stream::iter(events)).await.unwrap();
+
+        assert!(response.id.starts_with("bedrock-"));
+        assert_eq!(response.model, "anthropic.claude-3-haiku-20240307-v1:0");
+        assert_eq!(response.text().as_deref(), Some("Checking"));
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            vec![ToolCall::function(
+                "tooluse_1",
+                "get_weather",
+                "{\"city\":\"Paris\"}"
+            )]
+        );
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(response.usage, Usage::new(31, 17));
+    }
+
+    #[tokio::test]
+    async fn test_converse_stream_exception() {
+        let parser = BedrockStreamParser::converse("meta.llama3-8b-instruct-v1:0");
+        let events =
+            decode_fixture(include_bytes!("testdata/converse_throttled.bin"), parser).await;
+
+        assert!(matches!(
+            events.last(),
+            Some(Err(ConnectorError::RateLimited { error, .. }))
+                if error.message.contains("Too many tokens")
+        ));
+        let text: String = events
+            .iter()
+            .filter_map(|event| event.as_ref().ok()?.as_text())
+            .collect();
+        assert_eq!(text, "Hel");
+    }
+
+    #[tokio::test]
+    async fn test_invoke_stream_model_families() {
+        let model = "us.anthropic.claude-3-haiku-20240307-v1:0";
+        let parser = BedrockStreamParser::invoke(model, ModelFamily::Anthropic);
+        let events = decode_fixture(include_bytes!("testdata/invoke_anthropic.bin"), parser).await;
+        let response = aggregate(stream::iter(events)).await.unwrap();
+        assert_eq!(response.id, "msg_bdrk_01");
+        assert_eq!(response.model, model);
+        assert_eq!(response.text().as_deref(), Some("Bonjour"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(9, 4));
+
+        let parser =
+            BedrockStreamParser::invoke("meta.llama3-8b-instruct-v1:0", ModelFamily::Llama);
+        let events = decode_fixture(include_bytes!("testdata/invoke_llama.bin"), parser).await;
+        let response = aggregate(stream::iter(events)).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hello there"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(12, 2));
+
+        let parser =
+            BedrockStreamParser::invoke("amazon.titan-text-express-v1", ModelFamily::Titan);
+        let events = decode_fixture(include_bytes!("testdata/invoke_titan.bin"), parser).await;
+        let response = aggregate(stream::iter(events)).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hi there"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(4, 2));
+    }
+}
@@ -0,0 +1,665 @@
+//! # Cohere Provider
+//!
+//! Native client for the Cohere Chat v2 API.
+//!
+//! Handles the differences from the OpenAI format:
+//!
+//! - Sampling parameters are named `p`, `k` and `stop_sequences`
+//! - Grounding documents from [`CompletionRequest::documents`] are sent as
+//!   `documents`; the model answers with citations pointing back into them
+//! - Citations are returned in the response metadata under `citations` and
+//!   can be read back with [`citations`]
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::cohere::{self, CohereProvider};
+//!
+//! let mut config = ConfigAdapter::new();
+//! let provider = CohereProvider::from_adapter(&mut config)?;
+//! let request = CompletionRequest::builder("command-r-plus-08-2024")
+//!     .message(Message::user("When was the company founded?"))
+//!     .document(Document::text("Founded in 2019 in Toronto.").with_id("about"))
+//!     .build()?;
+//! let response = provider.complete(&request).await?;
+//! for citation in cohere::citations(&response) { /* ... */ }
+//! ```
+
+use super::errors;
+use super::http::{self, ProviderTelemetry};
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, Result};
+use crate::streaming;
+use crate::types::{
+    Choice, CompletionRequest, CompletionResponse, ContentPart, Document, FinishReason, Message,
+    MessageContent, ProviderMetadata, Role, ToolCall, ToolDefinition, Usage,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Default Cohere API endpoint
+pub const DEFAULT_ENDPOINT: &str = "https://api.cohere.com";
+
+/// A span of generated text grounded in one or more documents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Citation {
+    /// Start offset in the generated text
+    pub start: usize,
+    /// End offset in the generated text
+    pub end: usize,
+    /// Cited text
+    pub text: String,
+    /// IDs of the documents supporting the span
+    pub document_ids: Vec<String>,
+}
+
+/// Citations attached to a Cohere response
+///
+/// Returns an empty list for responses from other providers.
+pub fn citations(response: &CompletionResponse) -> Vec<Citation> {
+    response
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.extra.get("citations"))
+        .and_then(|citations| serde_json::from_value(citations.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Cohere Chat v2 provider
+#[derive(Clone)]
+pub struct CohereProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    telemetry: ProviderTelemetry,
+}
+
+impl CohereProvider {
+    /// Create a provider using the default endpoint
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            api_key: api_key.into(),
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let api_key = config.api_key.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("API key not configured for {}", config.provider))
+        })?;
+
+        let mut provider = Self::new(api_key);
+        if let Some(endpoint) = &config.endpoint {
+            provider.endpoint = endpoint.clone();
+        }
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "cohere" entry of a config adapter
+    ///
+    /// The API key is read with `get_credential("cohere", "api_key")` if
+    /// the configuration does not carry one.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut config = adapter.get_provider_config("cohere")?.clone();
+        if config.api_key.is_none() {
+            config.api_key = Some(adapter.get_credential("cohere", "api_key")?);
+        }
+        Self::from_config(&config)
+    }
+
+    /// Override the API endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    /// Build the Chat v2 request body
+    pub(crate) fn build_request(&self, request: &CompletionRequest) -> CohereChatRequest {
+        CohereChatRequest {
+            model: request.model.clone(),
+            messages: request
+                .messages
+                .iter()
+                .map(CohereMessage::from_unified)
+                .collect(),
+            documents: request.documents.clone(),
+            tools: request.tools.clone(),
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            p: request.top_p,
+            k: request.top_k,
+            stop_sequences: request.stop.clone(),
+            stream: request.stream,
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for CohereProvider {
+    fn name(&self) -> &str {
+        "cohere"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: true,
+            vision: false,
+            json_mode: true,
+            max_tokens: Some(4096),
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let mut chat = self.build_request(request);
+        chat.stream = false;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = http::join_url(&self.endpoint, "v2/chat");
+        debug!(url = %url, model = %request.model, "Sending Cohere chat");
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.client
+                    .post(&url)
+                    .bearer_auth(&self.api_key)
+                    .json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::generic(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let chat: CohereChatResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+                ConnectorError::Internal(format!("Unexpected Cohere response: {}", e))
+            })?;
+            Ok((raw, chat.into_unified(&request.model)))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let mut chat = self.build_request(request);
+        chat.stream = true;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let url = http::join_url(&self.endpoint, "v2/chat");
+        debug!(url = %url, model = %request.model, "Streaming Cohere chat");
+        let model = request.model.clone();
+        http::send_stream(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.client
+                .post(&url)
+                .bearer_auth(&self.api_key)
+                .json(&body),
+            &body,
+            errors::generic,
+            move |response| streaming::cohere::decode(response.bytes_stream(), &model).boxed(),
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.endpoint, "v1/models");
+        let response = http::send(
+            self.name(),
+            self.client
+                .get(&url)
+                .query(&[("endpoint", "chat")])
+                .bearer_auth(&self.api_key),
+        )
+        .await?;
+        if !response.is_success() {
+            return Err(errors::generic(self.name(), &response));
+        }
+        let models: CohereModelList = response.json(self.name())?;
+        Ok(models.models.into_iter().map(|model| model.name).collect())
+    }
+}
+
+/// Map a Cohere `finish_reason`
+pub(crate) fn map_finish_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "COMPLETE" | "STOP_SEQUENCE" => Some(FinishReason::Stop),
+        "MAX_TOKENS" => Some(FinishReason::Length),
+        "TOOL_CALL" => Some(FinishReason::ToolCalls),
+        "ERROR_TOXIC" => Some(FinishReason::ContentFilter),
+        _ => None,
+    }
+}
+
+/// Chat v2 request body
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CohereChatRequest {
+    pub model: String,
+    pub messages: Vec<CohereMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub documents: Vec<Document>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub k: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+/// Chat v2 message
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CohereMessage {
+    pub role: Role,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<CohereContent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl CohereMessage {
+    fn from_unified(message: &Message) -> Self {
+        // Cohere has no legacy function role; treat it as a tool result
+        let role = match message.role {
+            Role::Function => Role::Tool,
+            role => role,
+        };
+        let content = match &message.content {
+            MessageContent::Text(text) if text.is_empty() && !message.tool_calls.is_empty() => None,
+            MessageContent::Text(text) => Some(CohereContent::Text(text.clone())),
+            MessageContent::Parts(parts) => Some(CohereContent::Parts(
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        ContentPart::Text { text } => {
+                            CohereContentPart::Text { text: text.clone() }
+                        }
+                        ContentPart::ImageUrl { image_url, .. } => CohereContentPart::ImageUrl {
+                            image_url: CohereImageUrl {
+                                url: image_url.clone(),
+                            },
+                        },
+                        ContentPart::ImageBase64 { image_base64, .. } => {
+                            let url = if image_base64.starts_with("data:") {
+                                image_base64.clone()
+                            } else {
+                                format!("data:image/jpeg;base64,{}", image_base64)
+                            };
+                            CohereContentPart::ImageUrl {
+                                image_url: CohereImageUrl { url },
+                            }
+                        }
+                    })
+                    .collect(),
+            )),
+        };
+
+        Self {
+            role,
+            content,
+            tool_calls: message.tool_calls.clone(),
+            tool_call_id: message.tool_call_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub(crate) enum CohereContent {
+    Text(String),
+    Parts(Vec<CohereContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum CohereContentPart {
+    Text { text: String },
+    ImageUrl { image_url: CohereImageUrl },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CohereImageUrl {
+    pub url: String,
+}
+
+/// Chat v2 response body
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CohereChatResponse {
+    pub id: String,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+    pub message: CohereResponseMessage,
+    #[serde(default)]
+    pub usage: Option<CohereUsage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CohereResponseMessage {
+    #[serde(default)]
+    pub content: Vec<CohereResponseContent>,
+    #[serde(default)]
+    pub tool_plan: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default)]
+    pub citations: Vec<CohereCitation>,
+}
+
+/// Response content block; only text blocks are interpreted
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CohereResponseContent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CohereCitation {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    #[serde(default)]
+    pub sources: Vec<CohereSource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CohereSource {
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CohereUsage {
+    #[serde(default)]
+    pub tokens: Option<CohereTokens>,
+    #[serde(default)]
+    pub billed_units: Option<CohereTokens>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub(crate) struct CohereTokens {
+    #[serde(default)]
+    pub input_tokens: f64,
+    #[serde(default)]
+    pub output_tokens: f64,
+}
+
+impl CohereChatResponse {
+    fn into_unified(self, model: &str) -> CompletionResponse {
+        let text: String = self
+            .message
+            .content
+            .iter()
+            .filter(|block| block.kind == "text")
+            .filter_map(|block| block.text.as_deref())
+            .collect();
+
+        let citations: Vec<Citation> = self
+            .message
+            .citations
+            .into_iter()
+            .map(|citation| Citation {
+                start: citation.start,
+                end: citation.end,
+                text: citation.text,
+                document_ids: citation
+                    .sources
+                    .into_iter()
+                    .filter_map(|source| source.id)
+                    .collect(),
+            })
+            .collect();
+
+        let mut extra = HashMap::new();
+        if !citations.is_empty() {
+            extra.insert(
+                "citations".to_string(),
+                serde_json::to_value(&citations).unwrap_or_default(),
+            );
+        }
+        if let Some(plan) = self.message.tool_plan {
+            extra.insert("tool_plan".to_string(), Value::String(plan));
+        }
+
+        // Prefer raw token counts; billed units exclude system overhead
+        let tokens = self
+            .usage
+            .and_then(|usage| usage.tokens.or(usage.billed_units))
+            .unwrap_or_default();
+
+        CompletionResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: model.to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(text).with_tool_calls(self.message.tool_calls),
+                finish_reason: self.finish_reason.as_deref().and_then(map_finish_reason),
+            }],
+            usage: Usage::new(tokens.input_tokens as u32, tokens.output_tokens as u32),
+            metadata: Some(ProviderMetadata {
+                provider: "cohere".to_string(),
+                model: model.to_string(),
+                raw_response: None,
+                extra,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CohereModelList {
+    #[serde(default)]
+    models: Vec<CohereModel>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CohereModel {
+    name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_request_body() {
+        let provider = CohereProvider::new("key");
+        let request = CompletionRequest::builder("command-r-plus-08-2024")
+            .message(Message::system("Answer from the documents."))
+            .message(Message::user("Where is HQ?"))
+            .message(
+                Message::assistant("").with_tool_calls(vec![ToolCall::function(
+                    "call_1",
+                    "search",
+                    "{\"q\":\"hq\"}",
+                )]),
+            )
+            .message(Message::tool("call_1", "Toronto"))
+            .document(Document::text("HQ is in Toronto.").with_id("doc-hq"))
+            .top_p(0.9)
+            .top_k(50)
+            .stop("END")
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(provider.build_request(&request)).unwrap();
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert!(body["messages"][2].get("content").is_none());
+        assert_eq!(body["messages"][2]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(body["messages"][3]["tool_call_id"], "call_1");
+        assert_eq!(
+            body["documents"],
+            json!([{"id": "doc-hq", "data": {"text": "HQ is in Toronto."}}])
+        );
+        assert!((body["p"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(body["k"], 50);
+        assert_eq!(body["stop_sequences"], json!(["END"]));
+    }
+
+    #[test]
+    fn test_finish_reason_mapping() {
+        assert_eq!(map_finish_reason("COMPLETE"), Some(FinishReason::Stop));
+        assert_eq!(map_finish_reason("MAX_TOKENS"), Some(FinishReason::Length));
+        assert_eq!(
+            map_finish_reason("TOOL_CALL"),
+            Some(FinishReason::ToolCalls)
+        );
+        assert_eq!(map_finish_reason("ERROR"), None);
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_citations() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v2/chat"))
+            .and(header("authorization", "Bearer co-key"))
+            .and(body_partial_json(json!({"documents": [{"id": "doc-hq"}]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "c14c80c3",
+                "finish_reason": "COMPLETE",
+                "message": {
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "HQ is in Toronto."}],
+                    "citations": [{
+                        "start": 9,
+                        "end": 16,
+                        "text": "Toronto",
+                        "sources": [{"type": "document", "id": "doc-hq", "document": {"text": "HQ is in Toronto."}}]
+                    }]
+                },
+                "usage": {
+                    "billed_units": {"input_tokens": 12, "output_tokens": 5},
+                    "tokens": {"input_tokens": 210, "output_tokens": 5}
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = CohereProvider::new("co-key").with_endpoint(server.uri());
+        let request = CompletionRequest::builder("command-r-plus-08-2024")
+            .message(Message::user("Where is HQ?"))
+            .document(Document::text("HQ is in Toronto.").with_id("doc-hq"))
+            .build()
+            .unwrap();
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("HQ is in Toronto."));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(210, 5));
+        assert_eq!(
+            citations(&response),
+            vec![Citation {
+                start: 9,
+                end: 16,
+                text: "Toronto".to_string(),
+                document_ids: vec!["doc-hq".to_string()],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock_server() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "event: message-start\n",
+            "data: {\"id\":\"c14c80c3\",\"type\":\"message-start\",\"delta\":{\"message\":{\"role\":\"assistant\"}}}\n\n",
+            "event: content-delta\n",
+            "data: {\"type\":\"content-delta\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"text\":\"Hi there\"}}}}\n\n",
+            "event: message-end\n",
+            "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"COMPLETE\",\"usage\":{\"tokens\":{\"input_tokens\":12,\"output_tokens\":3}}}}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v2/chat"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = CohereProvider::new("co-key").with_endpoint(server.uri());
+        let request = CompletionRequest::new("command-r-08-2024", vec![Message::user("Hi")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.id, "c14c80c3");
+        assert_eq!(response.model, "command-r-08-2024");
+        assert_eq!(response.text().as_deref(), Some("Hi there"));
+        assert_eq!(response.usage, Usage::new(12, 3));
+    }
+
+    #[tokio::test]
+    async fn test_list_models() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(query_param("endpoint", "chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "models": [{"name": "command-r-08-2024"}, {"name": "command-r-plus-08-2024"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = CohereProvider::new("co-key").with_endpoint(server.uri());
+        assert_eq!(
+            provider.list_models().await.unwrap(),
+            vec!["command-r-08-2024", "command-r-plus-08-2024"]
+        );
+    }
+}
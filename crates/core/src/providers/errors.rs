@@ -0,0 +1,916 @@
+//! Provider error mapping.
+//!
+//! Each mapper turns a non-2xx response into a [`ConnectorError`], using the
+//! provider's documented error `type`/`code`/`status` fields before falling
+//! back to the HTTP status. Rate-limit errors carry the delay from
+//! `retry-after-ms`, `retry-after`, exhausted `x-ratelimit-*` buckets, a
+//! Google `RetryInfo` detail or a "try again in ..." message, in that order.
+//!
+//! The raw status and body are always kept on the [`ProviderError`].
+
+use super::http::HttpResponse;
+use crate::error::{ConnectorError, ProviderError};
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Maps a non-2xx response from the named provider to an error
+pub(crate) type ErrorMapper = fn(&str, &HttpResponse) -> ConnectorError;
+
+/// Status-based mapping for providers without a structured error format
+///
+/// The message is taken from the common `message`, `error.message`, `error`
+/// or `detail` body fields.
+pub(crate) fn generic(provider: &str, response: &HttpResponse) -> ConnectorError {
+    let body = json_body(response);
+    let message = body
+        .get("message")
+        .or_else(|| body.pointer("/error/message"))
+        .or_else(|| body.get("error"))
+        .or_else(|| body.get("detail"))
+        .and_then(Value::as_str);
+    classify_status(response, details(provider, response, message))
+}
+
+/// OpenAI `{"error": {"message", "type", "param", "code"}}` errors
+///
+/// Also used for Mistral and OpenAI-compatible servers.
+pub(crate) fn openai(provider: &str, response: &HttpResponse) -> ConnectorError {
+    let body = json_body(response);
+    let error = body.get("error").unwrap_or(&Value::Null);
+    let kind = str_field(error, "type");
+    let code = str_field(error, "code");
+    let details = details(
+        provider,
+        response,
+        error.get("message").and_then(Value::as_str),
+    );
+
+    match (kind, code) {
+        (_, "context_length_exceeded") => ConnectorError::ContextLengthExceeded(details),
+        ("authentication_error" | "permission_error", _)
+        | (_, "invalid_api_key" | "permission_denied") => ConnectorError::Authentication(details),
+        (_, "model_not_found") => ConnectorError::ModelNotFound(details),
+        (_, "content_filter" | "content_policy_violation") => {
+            ConnectorError::ContentFiltered(details)
+        }
+        (_, "insufficient_quota") => ConnectorError::QuotaExceeded(details),
+        ("rate_limit_error" | "requests" | "tokens", _) | (_, "rate_limit_exceeded") => {
+            rate_limited(response, details)
+        }
+        ("server_error" | "api_error", _) => ConnectorError::ProviderUnavailable(details),
+        ("invalid_request_error", _) if is_context_length(&details.message) => {
+            ConnectorError::ContextLengthExceeded(details)
+        }
+        ("invalid_request_error", _) if response.status < 500 => {
+            ConnectorError::InvalidRequest(details)
+        }
+        _ => classify_status(response, details),
+    }
+}
+
+/// Azure OpenAI errors: the OpenAI format plus Azure-specific codes
+///
+/// Content filtering is reported as `content_filter` or with an
+/// `innererror.code` of `ResponsibleAIPolicyViolation`.
+pub(crate) fn azure(provider: &str, response: &HttpResponse) -> ConnectorError {
+    let body = json_body(response);
+    let error = body.get("error").unwrap_or(&Value::Null);
+    let message = error.get("message").and_then(Value::as_str);
+    let inner_code = error
+        .pointer("/innererror/code")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    match str_field(error, "code") {
+        "DeploymentNotFound" => ConnectorError::ModelNotFound(details(provider, response, message)),
+        _ if inner_code == "ResponsibleAIPolicyViolation" => {
+            ConnectorError::ContentFiltered(details(provider, response, message))
+        }
+        "401" | "403" => ConnectorError::Authentication(details(provider, response, message)),
+        "429" => rate_limited(response, details(provider, response, message)),
+        _ => openai(provider, response),
+    }
+}
+
+/// Anthropic `{"type": "error", "error": {"type", "message"}}` errors
+pub(crate) fn anthropic(provider: &str, response: &HttpResponse) -> ConnectorError {
+    let body = json_body(response);
+    let error = body.get("error").unwrap_or(&Value::Null);
+    let details = details(
+        provider,
+        response,
+        error.get("message").and_then(Value::as_str),
+    );
+    match str_field(error, "type") {
+        "" => classify_status(response, details),
+        "rate_limit_error" => rate_limited(response, details),
+        kind => anthropic_error(kind, details),
+    }
+}
+
+/// Map an Anthropic error `type`, from a response or an `error` stream event
+pub(crate) fn anthropic_error(kind: &str, error: ProviderError) -> ConnectorError {
+    match kind {
+        "invalid_request_error" if is_context_length(&error.message) => {
+            ConnectorError::ContextLengthExceeded(error)
+        }
+        "invalid_request_error" | "request_too_large" => ConnectorError::InvalidRequest(error),
+        "authentication_error" | "permission_error" => ConnectorError::Authentication(error),
+        "not_found_error" => ConnectorError::ModelNotFound(error),
+        "rate_limit_error" => ConnectorError::RateLimited {
+            error,
+            retry_after: None,
+        },
+        "api_error" | "overloaded_error" => ConnectorError::ProviderUnavailable(error),
+        "timeout_error" => ConnectorError::Timeout(error),
+        _ if error.status.is_some() => ConnectorError::from_status(error),
+        _ => ConnectorError::StreamInterrupted(error),
+    }
+}
+
+/// Google API `{"error": {"code", "message", "status", "details"}}` errors
+///
+/// Streaming endpoints wrap the error object in a one-element array.
+pub(crate) fn google(provider: &str, response: &HttpResponse) -> ConnectorError {
+    let body = match json_body(response) {
+        Value::Array(items) => items.into_iter().next().unwrap_or_default(),
+        body => body,
+    };
+    let error = body.get("error").unwrap_or(&Value::Null);
+    let details = details(
+        provider,
+        response,
+        error.get("message").and_then(Value::as_str),
+    );
+
+    let error_details = error
+        .get("details")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let reason = error_details
+        .iter()
+        .find_map(|detail| detail.get("reason").and_then(Value::as_str))
+        .unwrap_or_default();
+    if reason == "API_KEY_INVALID" {
+        return ConnectorError::Authentication(details);
+    }
+
+    match google_status(str_field(error, "status"), details) {
+        ConnectorError::RateLimited { error, retry_after } => {
+            let retry_info = error_details.iter().find_map(|detail| {
+                detail
+                    .get("retryDelay")
+                    .and_then(Value::as_str)
+                    .and_then(parse_duration)
+            });
+            ConnectorError::RateLimited {
+                retry_after: retry_after
+                    .or_else(|| retry_after_header(&response.headers))
+                    .or(retry_info)
+                    .or_else(|| retry_after_message(&error.message)),
+                error,
+            }
+        }
+        mapped => mapped,
+    }
+}
+
+/// Map a Google RPC `status`, from a response or a stream `error` element
+pub(crate) fn google_status(status: &str, error: ProviderError) -> ConnectorError {
+    match status {
+        "INVALID_ARGUMENT" if is_context_length(&error.message) => {
+            ConnectorError::ContextLengthExceeded(error)
+        }
+        "INVALID_ARGUMENT" | "FAILED_PRECONDITION" | "OUT_OF_RANGE" => {
+            ConnectorError::InvalidRequest(error)
+        }
+        "UNAUTHENTICATED" | "PERMISSION_DENIED" => ConnectorError::Authentication(error),
+        "NOT_FOUND" => ConnectorError::ModelNotFound(error),
+        "RESOURCE_EXHAUSTED" => ConnectorError::RateLimited {
+            error,
+            retry_after: None,
+        },
+        "INTERNAL" | "UNAVAILABLE" => ConnectorError::ProviderUnavailable(error),
+        "DEADLINE_EXCEEDED" => ConnectorError::Timeout(error),
+        _ if error.status.is_some() => ConnectorError::from_status(error),
+        _ => ConnectorError::StreamInterrupted(error),
+    }
+}
+
+/// Bedrock errors
+///
+/// The exception name comes from the `x-amzn-errortype` header or the
+/// body's `__type` field; the message from the body's `message` field.
+pub(crate) fn bedrock(provider: &str, response: &HttpResponse) -> ConnectorError {
+    let body = json_body(response);
+    let kind = response
+        .header("x-amzn-errortype")
+        .or_else(|| body.get("__type").and_then(Value::as_str))
+        .map(|kind| {
+            // "ThrottlingException:http://..." or "com.amazon...#ThrottlingException"
+            let kind = kind.split(':').next().unwrap_or(kind);
+            kind.rsplit('#').next().unwrap_or(kind)
+        })
+        .unwrap_or_default();
+    let message = body
+        .get("message")
+        .or_else(|| body.get("Message"))
+        .and_then(Value::as_str);
+
+    match bedrock_exception(kind, details(provider, response, message)) {
+        ConnectorError::RateLimited { error, .. } => rate_limited(response, error),
+        mapped => mapped,
+    }
+}
+
+/// Map a Bedrock exception name, from a response or a stream exception
+///
+/// Names are matched case-insensitively: HTTP errors use `ThrottlingException`
+/// while stream exceptions use `throttlingException`. Unknown exceptions are
+/// classified by status code, or as an interrupted stream when raised
+/// mid-stream.
+pub(crate) fn bedrock_exception(kind: &str, error: ProviderError) -> ConnectorError {
+    match kind.to_ascii_lowercase().as_str() {
+        "throttlingexception" | "servicequotaexceededexception" => ConnectorError::RateLimited {
+            error,
+            retry_after: None,
+        },
+        "validationexception" if is_context_length(&error.message) => {
+            ConnectorError::ContextLengthExceeded(error)
+        }
+        "validationexception" => ConnectorError::InvalidRequest(error),
+        "accessdeniedexception" | "unrecognizedclientexception" => {
+            ConnectorError::Authentication(error)
+        }
+        "resourcenotfoundexception" => ConnectorError::ModelNotFound(error),
+        "modeltimeoutexception" => ConnectorError::Timeout(error),
+        "internalserverexception"
+        | "serviceunavailableexception"
+        | "modelnotreadyexception"
+        | "modelerrorexception" => ConnectorError::ProviderUnavailable(error),
+        "modelstreamerrorexception" => ConnectorError::StreamInterrupted(error),
+        _ if error.status == Some(429) => ConnectorError::RateLimited {
+            error,
+            retry_after: None,
+        },
+        _ if error.status.is_some() => ConnectorError::from_status(error),
+        _ => ConnectorError::StreamInterrupted(error),
+    }
+}
+
+fn json_body(response: &HttpResponse) -> Value {
+    serde_json::from_slice(&response.body).unwrap_or_default()
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> &'a str {
+    value.get(field).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Error details keeping the response status and raw body
+fn details(provider: &str, response: &HttpResponse, message: Option<&str>) -> ProviderError {
+    ProviderError::new(provider, message.unwrap_or("API error"))
+        .with_status(response.status)
+        .with_body(response.text())
+}
+
+fn classify_status(response: &HttpResponse, error: ProviderError) -> ConnectorError {
+    match ConnectorError::from_status(error) {
+        ConnectorError::RateLimited { error, .. } => rate_limited(response, error),
+        ConnectorError::InvalidRequest(error) if is_context_length(&error.message) => {
+            ConnectorError::ContextLengthExceeded(error)
+        }
+        mapped => mapped,
+    }
+}
+
+fn rate_limited(response: &HttpResponse, error: ProviderError) -> ConnectorError {
+    ConnectorError::RateLimited {
+        retry_after: retry_after_header(&response.headers)
+            .or_else(|| retry_after_message(&error.message)),
+        error,
+    }
+}
+
+/// Whether an invalid-request message reports a context-window overflow
+fn is_context_length(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "context length",
+        "context window",
+        "maximum context",
+        "prompt is too long",
+        "input is too long",
+        "input token count",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Longest delay accepted from a server
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Delay requested by rate-limit headers
+///
+/// `x-ratelimit-reset-*` is only used for a bucket whose matching
+/// `x-ratelimit-remaining-*` is exhausted.
+pub(crate) fn retry_after_header(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(ms) = header("retry-after-ms").and_then(|ms| ms.trim().parse::<f64>().ok()) {
+        return delay_from_secs(ms / 1000.0);
+    }
+    if let Some(value) = header("retry-after").map(str::trim) {
+        if let Ok(seconds) = value.parse::<f64>() {
+            return delay_from_secs(seconds);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+            return Some(delay.to_std().unwrap_or_default().min(MAX_RETRY_AFTER));
+        }
+    }
+    ["requests", "tokens"]
+        .iter()
+        .filter(|bucket| header(&format!("x-ratelimit-remaining-{}", bucket)) == Some("0"))
+        .filter_map(|bucket| header(&format!("x-ratelimit-reset-{}", bucket)))
+        .filter_map(parse_duration)
+        .max()
+}
+
+/// Delay from a "try again in 20s" or "retry after 3 seconds" message
+fn retry_after_message(message: &str) -> Option<Duration> {
+    let message = message.to_ascii_lowercase();
+    let rest = ["try again in ", "retry after "]
+        .iter()
+        .find_map(|marker| message.find(marker).map(|at| &message[at + marker.len()..]))?;
+    let number_len = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    let value: f64 = rest[..number_len].parse().ok()?;
+    let unit = rest[number_len..].trim_start();
+    let seconds = if unit.starts_with("ms") || unit.starts_with("milli") {
+        value / 1000.0
+    } else if unit.starts_with('m') {
+        value * 60.0
+    } else {
+        value
+    };
+    delay_from_secs(seconds)
+}
+
+/// Parse a Go-style duration such as `1s`, `6m0s`, `20ms` or `1.5s`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+        let (scale, unit_len) = if rest.starts_with("ms") {
+            (0.001, 2)
+        } else if rest.starts_with('h') {
+            (3600.0, 1)
+        } else if rest.starts_with('m') {
+            (60.0, 1)
+        } else if rest.starts_with('s') {
+            (1.0, 1)
+        } else {
+            return None;
+        };
+        seconds += number * scale;
+        rest = &rest[unit_len..];
+    }
+    delay_from_secs(seconds)
+}
+
+/// Delay of `seconds`, capped at [`MAX_RETRY_AFTER`]
+///
+/// `NaN`, infinite and out-of-range values, which a misbehaving server may
+/// send, are ignored; negative values read as no delay.
+fn delay_from_secs(seconds: f64) -> Option<Duration> {
+    if seconds.is_nan() {
+        return None;
+    }
+    Duration::try_from_secs_f64(seconds.max(0.0))
+        .ok()
+        .map(|delay| delay.min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    /// Status, headers, body, expected code and expected retry delay in ms
+    type Case<'a> = (u16, &'a [(&'a str, &'a str)], &'a str, &'a str, Option<u64>);
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> HttpResponse {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        HttpResponse {
+            status,
+            headers: map,
+            body: body.to_string().into(),
+        }
+    }
+
+    /// Map each case and check the error code, retry delay, status and body
+    fn check(mapper: ErrorMapper, cases: &[Case]) {
+        for &(status, headers, body, code, retry_ms) in cases {
+            let error = mapper("test", &response(status, headers, body));
+            assert_eq!(error.code(), code, "{}: {}", body, error);
+            assert_eq!(
+                error.retry_after(),
+                retry_ms.map(Duration::from_millis),
+                "{}",
+                body
+            );
+            assert_eq!(error.status(), Some(status));
+            assert_eq!(error.provider(), Some("test"));
+            assert_eq!(error.provider_error().unwrap().body.as_deref(), Some(body));
+        }
+    }
+
+    #[test]
+    fn test_openai_errors() {
+        let exhausted_tokens = [
+            ("x-ratelimit-remaining-requests", "59"),
+            ("x-ratelimit-remaining-tokens", "0"),
+            ("x-ratelimit-reset-requests", "1s"),
+            ("x-ratelimit-reset-tokens", "6m0s"),
+        ];
+        check(
+            openai,
+            &[
+                (
+                    401,
+                    &[],
+                    r#"{"error": {"message": "Incorrect API key provided: sk-abc.",
+                        "type": "invalid_request_error", "param": null,
+                        "code": "invalid_api_key"}}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    429,
+                    &exhausted_tokens,
+                    r#"{"error": {"message": "Rate limit reached for gpt-4 on tokens per min.",
+                        "type": "tokens", "param": null, "code": "rate_limit_exceeded"}}"#,
+                    "rate_limited",
+                    Some(360_000),
+                ),
+                (
+                    429,
+                    &[],
+                    r#"{"error": {"message": "Rate limit reached. Please try again in 20ms.",
+                        "type": "requests", "param": null, "code": "rate_limit_exceeded"}}"#,
+                    "rate_limited",
+                    Some(20),
+                ),
+                (
+                    429,
+                    &[],
+                    r#"{"error": {"message": "You exceeded your current quota.",
+                        "type": "insufficient_quota", "param": null,
+                        "code": "insufficient_quota"}}"#,
+                    "quota_exceeded",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"message": "This model's maximum context length is 8192 tokens.",
+                        "type": "invalid_request_error", "param": "messages",
+                        "code": "context_length_exceeded"}}"#,
+                    "context_length_exceeded",
+                    None,
+                ),
+                (
+                    404,
+                    &[],
+                    r#"{"error": {"message": "The model `gpt-5` does not exist",
+                        "type": "invalid_request_error", "param": null,
+                        "code": "model_not_found"}}"#,
+                    "model_not_found",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"message": "'messages' is a required property",
+                        "type": "invalid_request_error", "param": null, "code": null}}"#,
+                    "invalid_request",
+                    None,
+                ),
+                (
+                    500,
+                    &[],
+                    r#"{"error": {"message": "The server had an error processing your request.",
+                        "type": "server_error", "param": null, "code": null}}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+                (
+                    503,
+                    &[("retry-after", "2")],
+                    "<html>upstream connect error</html>",
+                    "provider_unavailable",
+                    None,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_azure_errors() {
+        check(
+            azure,
+            &[
+                (
+                    404,
+                    &[],
+                    r#"{"error": {"code": "DeploymentNotFound",
+                        "message": "The API deployment for this resource does not exist."}}"#,
+                    "model_not_found",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"message": "The response was filtered by the content policy.",
+                        "type": null, "param": "prompt", "status": 400, "code": "content_filter",
+                        "innererror": {"code": "ResponsibleAIPolicyViolation",
+                            "content_filter_result": {"hate": {"filtered": true}}}}}"#,
+                    "content_filtered",
+                    None,
+                ),
+                (
+                    429,
+                    &[("retry-after", "20")],
+                    r#"{"error": {"code": "429", "message":
+                        "Requests exceeded the call rate limit. Please retry after 20 seconds."}}"#,
+                    "rate_limited",
+                    Some(20_000),
+                ),
+                (
+                    429,
+                    &[("retry-after-ms", "1500"), ("retry-after", "2")],
+                    r#"{"error": {"code": "429", "message": "Rate limit is exceeded."}}"#,
+                    "rate_limited",
+                    Some(1500),
+                ),
+                (
+                    401,
+                    &[],
+                    r#"{"error": {"code": "401", "message":
+                        "Access denied due to invalid subscription key or wrong API endpoint."}}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"message": "This model's maximum context length is 4096 tokens.",
+                        "type": "invalid_request_error", "code": "context_length_exceeded"}}"#,
+                    "context_length_exceeded",
+                    None,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_anthropic_errors() {
+        check(
+            anthropic,
+            &[
+                (
+                    400,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "invalid_request_error",
+                        "message": "max_tokens: Field required"}}"#,
+                    "invalid_request",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "invalid_request_error",
+                        "message": "prompt is too long: 215000 tokens > 200000 maximum"}}"#,
+                    "context_length_exceeded",
+                    None,
+                ),
+                (
+                    401,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "authentication_error",
+                        "message": "invalid x-api-key"}}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    403,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "permission_error",
+                        "message": "Your API key does not have permission to use the resource."}}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    404,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "not_found_error",
+                        "message": "model: claude-4"}}"#,
+                    "model_not_found",
+                    None,
+                ),
+                (
+                    413,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "request_too_large",
+                        "message": "Request exceeds the maximum allowed number of bytes."}}"#,
+                    "invalid_request",
+                    None,
+                ),
+                (
+                    429,
+                    &[("retry-after", "7")],
+                    r#"{"type": "error", "error": {"type": "rate_limit_error",
+                        "message": "Number of request tokens has exceeded your rate limit."}}"#,
+                    "rate_limited",
+                    Some(7000),
+                ),
+                (
+                    500,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "api_error",
+                        "message": "An unexpected error has occurred internal to Anthropic."}}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+                (
+                    529,
+                    &[],
+                    r#"{"type": "error", "error": {"type": "overloaded_error",
+                        "message": "Overloaded"}}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_google_errors() {
+        check(
+            google,
+            &[
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"code": 400, "status": "INVALID_ARGUMENT",
+                        "message": "API key not valid. Please pass a valid API key.",
+                        "details": [{"@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                            "reason": "API_KEY_INVALID", "domain": "googleapis.com"}]}}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"code": 400, "status": "INVALID_ARGUMENT",
+                        "message":
+                        "The input token count (1200000) exceeds the limit (1048576)."}}"#,
+                    "context_length_exceeded",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"error": {"code": 400, "status": "INVALID_ARGUMENT",
+                        "message": "Invalid JSON payload received."}}"#,
+                    "invalid_request",
+                    None,
+                ),
+                (
+                    403,
+                    &[],
+                    r#"{"error": {"code": 403, "status": "PERMISSION_DENIED",
+                        "message": "Permission denied on resource project."}}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    404,
+                    &[],
+                    r#"{"error": {"code": 404, "status": "NOT_FOUND",
+                        "message": "models/gemini-9 is not found for API version v1"}}"#,
+                    "model_not_found",
+                    None,
+                ),
+                (
+                    429,
+                    &[],
+                    r#"[{"error": {"code": 429, "status": "RESOURCE_EXHAUSTED",
+                        "message": "Resource has been exhausted (e.g. check quota).",
+                        "details": [{"@type": "type.googleapis.com/google.rpc.RetryInfo",
+                            "retryDelay": "39s"}]}}]"#,
+                    "rate_limited",
+                    Some(39_000),
+                ),
+                (
+                    500,
+                    &[],
+                    r#"{"error": {"code": 500, "status": "INTERNAL",
+                        "message": "An internal error has occurred."}}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+                (
+                    503,
+                    &[],
+                    r#"{"error": {"code": 503, "status": "UNAVAILABLE",
+                        "message": "The model is overloaded. Please try again later."}}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+                (
+                    504,
+                    &[],
+                    r#"{"error": {"code": 504, "status": "DEADLINE_EXCEEDED",
+                        "message": "Deadline exceeded."}}"#,
+                    "timeout",
+                    None,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_bedrock_errors() {
+        check(
+            bedrock,
+            &[
+                (
+                    429,
+                    &[(
+                        "x-amzn-errortype",
+                        "ThrottlingException:http://internal.amazon.com/coral/com.amazon.bedrock/",
+                    )],
+                    r#"{"message": "Too many requests, please wait before trying again."}"#,
+                    "rate_limited",
+                    None,
+                ),
+                (
+                    400,
+                    &[("x-amzn-errortype", "ValidationException")],
+                    r#"{"message": "Input is too long for requested model."}"#,
+                    "context_length_exceeded",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"__type": "com.amazon.coral.validate#ValidationException",
+                        "message": "Malformed input request"}"#,
+                    "invalid_request",
+                    None,
+                ),
+                (
+                    403,
+                    &[("x-amzn-errortype", "AccessDeniedException")],
+                    r#"{"message": "You don't have access to the model with the specified ID."}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    403,
+                    &[("x-amzn-errortype", "UnrecognizedClientException")],
+                    r#"{"Message": "The security token included in the request is invalid."}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    404,
+                    &[("x-amzn-errortype", "ResourceNotFoundException")],
+                    r#"{"message": "Could not resolve the foundation model."}"#,
+                    "model_not_found",
+                    None,
+                ),
+                (
+                    408,
+                    &[("x-amzn-errortype", "ModelTimeoutException")],
+                    r#"{"message": "Model has timed out in processing the request."}"#,
+                    "timeout",
+                    None,
+                ),
+                (
+                    503,
+                    &[("x-amzn-errortype", "ServiceUnavailableException")],
+                    r#"{"message": "Service unavailable."}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+                (
+                    424,
+                    &[("x-amzn-errortype", "ModelErrorException")],
+                    r#"{"message": "The model returned an error."}"#,
+                    "provider_unavailable",
+                    None,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_generic_errors() {
+        check(
+            generic,
+            &[
+                (
+                    401,
+                    &[],
+                    r#"{"message": "invalid api token"}"#,
+                    "authentication",
+                    None,
+                ),
+                (
+                    400,
+                    &[],
+                    r#"{"detail": "This model's maximum context length is 4096 tokens."}"#,
+                    "context_length_exceeded",
+                    None,
+                ),
+                (
+                    429,
+                    &[("retry-after", "Thu, 01 Jan 1970 00:00:00 GMT")],
+                    r#"{"message": "slow down"}"#,
+                    "rate_limited",
+                    Some(0),
+                ),
+                (502, &[], "Bad Gateway", "provider_unavailable", None),
+            ],
+        );
+        let body = r#"{"message": "invalid request: model not set"}"#;
+        let error = generic("cohere", &response(400, &[], body));
+        assert_eq!(
+            error.to_string(),
+            "Invalid request: cohere: invalid request: model not set (400)"
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let secs = |seconds| Some(Duration::from_secs(seconds));
+        assert_eq!(parse_duration("1s"), secs(1));
+        assert_eq!(parse_duration("6m0s"), secs(360));
+        assert_eq!(parse_duration("1h2m3s"), secs(3723));
+        assert_eq!(parse_duration("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("3d"), None);
+    }
+
+    #[test]
+    fn test_retry_after_bounds() {
+        let delay = |name: &str, value: &str| {
+            let response = response(429, &[(name, value)], "");
+            retry_after_header(&response.headers)
+        };
+        assert_eq!(delay("retry-after", "inf"), None);
+        assert_eq!(delay("retry-after", "NaN"), None);
+        assert_eq!(delay("retry-after", "1e30"), None);
+        assert_eq!(delay("retry-after", "-5"), Some(Duration::ZERO));
+        assert_eq!(delay("retry-after", "604800"), Some(MAX_RETRY_AFTER));
+        assert_eq!(delay("retry-after-ms", "inf"), None);
+        assert_eq!(
+            delay("retry-after", "Fri, 31 Dec 9999 23:59:59 GMT"),
+            Some(MAX_RETRY_AFTER)
+        );
+
+        assert_eq!(
+            retry_after_message("Please try again in 99999999999999999999999s."),
+            None
+        );
+        assert_eq!(
+            retry_after_message("Please try again in 9999999s."),
+            Some(MAX_RETRY_AFTER)
+        );
+        assert_eq!(
+            retry_after_message(&format!("Retry after 1{}s", "0".repeat(400))),
+            None
+        );
+        assert_eq!(parse_duration("1e30s"), None);
+        assert_eq!(parse_duration("9999999h"), Some(MAX_RETRY_AFTER));
+    }
+}
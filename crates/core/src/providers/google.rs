@@ -0,0 +1,945 @@
+//! # Google Gemini Provider
+//!
+//! Native client for the Gemini `generateContent` API
+//! (`generativelanguage.googleapis.com/v1`).
+//!
+//! Handles the differences from the OpenAI format:
+//!
+//! - Messages become `contents` with `parts`; the assistant role is `model`
+//! - System messages are sent as `systemInstruction`
+//! - Sampling parameters live in `generationConfig`
+//! - `safetySettings` control content blocking; blocked prompts and
+//!   candidates surface as `ConnectorError::ContentFiltered`
+//! - Streaming uses `streamGenerateContent`, which returns one JSON array
+//!   decoded element by element; a safety block mid-stream ends the stream
+//!   with `FinishReason::ContentFilter`
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::google::GoogleProvider;
+//!
+//! let mut config = ConfigAdapter::new();
+//! let provider = GoogleProvider::from_adapter(&mut config)?;
+//! let response = provider.complete(&request).await?;
+//! ```
+
+use super::errors;
+use super::http::{self, ProviderTelemetry};
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::streaming;
+use crate::types::{
+    Choice, CompletionRequest, CompletionResponse, ContentPart, FinishReason, Message,
+    MessageContent, ProviderMetadata, Role, ToolCall, Usage,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+/// Default Gemini API endpoint
+pub const DEFAULT_ENDPOINT: &str = "https://generativelanguage.googleapis.com/v1";
+
+/// Harm category for safety settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmCategory {
+    /// Harassment
+    HarmCategoryHarassment,
+    /// Hate speech
+    HarmCategoryHateSpeech,
+    /// Sexually explicit content
+    HarmCategorySexuallyExplicit,
+    /// Dangerous content
+    HarmCategoryDangerousContent,
+    /// Civic integrity
+    HarmCategoryCivicIntegrity,
+}
+
+/// Blocking threshold for a harm category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmBlockThreshold {
+    /// Never block
+    BlockNone,
+    /// Block only high-probability harm
+    BlockOnlyHigh,
+    /// Block medium and high probability harm
+    BlockMediumAndAbove,
+    /// Block low, medium and high probability harm
+    BlockLowAndAbove,
+}
+
+/// Safety setting sent with each request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafetySetting {
+    /// Harm category
+    pub category: HarmCategory,
+    /// Blocking threshold
+    pub threshold: HarmBlockThreshold,
+}
+
+/// Default safety settings (block only high-probability harm)
+pub fn default_safety_settings() -> Vec<SafetySetting> {
+    [
+        HarmCategory::HarmCategoryHarassment,
+        HarmCategory::HarmCategoryHateSpeech,
+        HarmCategory::HarmCategorySexuallyExplicit,
+        HarmCategory::HarmCategoryDangerousContent,
+    ]
+    .into_iter()
+    .map(|category| SafetySetting {
+        category,
+        threshold: HarmBlockThreshold::BlockOnlyHigh,
+    })
+    .collect()
+}
+
+/// Google Gemini provider
+#[derive(Clone)]
+pub struct GoogleProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    safety_settings: Vec<SafetySetting>,
+    telemetry: ProviderTelemetry,
+}
+
+impl GoogleProvider {
+    /// Create a provider using the default endpoint and safety settings
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            api_key: api_key.into(),
+            safety_settings: default_safety_settings(),
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// Recognized settings: `safety_settings` (array of
+    /// `{"category": ..., "threshold": ...}`).
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let api_key = config.api_key.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("API key not configured for {}", config.provider))
+        })?;
+
+        let mut provider = Self::new(api_key);
+        if let Some(endpoint) = &config.endpoint {
+            provider.endpoint = endpoint.clone();
+        }
+        if let Some(settings) = config.settings.get("safety_settings") {
+            provider.safety_settings = serde_json::from_value(settings.clone())
+                .map_err(|e| ConnectorError::Config(format!("Invalid safety_settings: {}", e)))?;
+        }
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "google" entry of a config adapter
+    ///
+    /// The API key is read from the credential store if the configuration
+    /// does not carry one.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut config = adapter.get_provider_config("google")?.clone();
+        if config.api_key.is_none() {
+            config.api_key = Some(adapter.get_credential("google", "api_key")?);
+        }
+        Self::from_config(&config)
+    }
+
+    /// Override the API endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Override the safety settings
+    pub fn with_safety_settings(mut self, safety_settings: Vec<SafetySetting>) -> Self {
+        self.safety_settings = safety_settings;
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    /// Build the `generateContent` request body
+    pub(crate) fn build_request(&self, request: &CompletionRequest) -> GenerateContentRequest {
+        let (system_instruction, contents) = convert_messages(&request.messages);
+
+        let generation_config = GenerationConfig {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            top_k: request.top_k,
+            max_output_tokens: request.max_tokens,
+            stop_sequences: request.stop.clone(),
+        };
+
+        let tools = if request.tools.is_empty() {
+            Vec::new()
+        } else {
+            vec![GoogleTool {
+                function_declarations: request
+                    .tools
+                    .iter()
+                    .map(|tool| FunctionDeclaration {
+                        name: tool.function.name.clone(),
+                        description: tool.function.description.clone(),
+                        parameters: tool.function.parameters.clone(),
+                    })
+                    .collect(),
+            }]
+        };
+
+        GenerateContentRequest {
+            contents,
+            system_instruction,
+            generation_config: (!generation_config.is_empty()).then_some(generation_config),
+            safety_settings: self.safety_settings.clone(),
+            tools,
+        }
+    }
+
+    /// URL for a model method such as `generateContent`
+    pub(crate) fn model_url(&self, model: &str, method: &str) -> String {
+        let model = model.strip_prefix("models/").unwrap_or(model);
+        http::join_url(&self.endpoint, &format!("models/{}:{}", model, method))
+    }
+}
+
+#[async_trait]
+impl Provider for GoogleProvider {
+    fn name(&self) -> &str {
+        "google"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: true,
+            vision: true,
+            json_mode: true,
+            max_tokens: Some(8192),
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let body = serde_json::to_value(self.build_request(request))
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = self.model_url(&request.model, "generateContent");
+        debug!(url = %url, model = %request.model, "Sending Gemini generateContent");
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.client
+                    .post(&url)
+                    .header("x-goog-api-key", &self.api_key)
+                    .json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::google(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let generated: GenerateContentResponse =
+                serde_json::from_value(raw.clone()).map_err(|e| {
+                    ConnectorError::Internal(format!("Unexpected Gemini response: {}", e))
+                })?;
+            let response = generated.into_unified(self.name(), &request.model)?;
+            Ok((raw, response))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let body = serde_json::to_value(self.build_request(request))
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+        let url = self.model_url(&request.model, "streamGenerateContent");
+        debug!(url = %url, model = %request.model, "Streaming Gemini streamGenerateContent");
+
+        let model = request.model.clone();
+        http::send_stream(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.client
+                .post(&url)
+                .header("x-goog-api-key", &self.api_key)
+                .json(&body),
+            &body,
+            errors::google,
+            move |response| streaming::gemini::decode(response.bytes_stream(), &model).boxed(),
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.endpoint, "models");
+        let response = http::send(
+            self.name(),
+            self.client
+                .get(&url)
+                .header("x-goog-api-key", &self.api_key),
+        )
+        .await?;
+        if !response.is_success() {
+            return Err(errors::google(self.name(), &response));
+        }
+        let models: ModelList = response.json(self.name())?;
+        Ok(models
+            .models
+            .into_iter()
+            .map(|model| {
+                model
+                    .name
+                    .strip_prefix("models/")
+                    .map(str::to_string)
+                    .unwrap_or(model.name)
+            })
+            .collect())
+    }
+}
+
+/// Split out system instructions and convert the rest into `contents`
+fn convert_messages(messages: &[Message]) -> (Option<GoogleContent>, Vec<GoogleContent>) {
+    let mut system_parts: Vec<Part> = Vec::new();
+    let mut contents: Vec<GoogleContent> = Vec::new();
+    // Gemini function responses are keyed by function name, not call ID
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+
+    for message in messages {
+        let (role, parts) = match message.role {
+            Role::System => {
+                system_parts.extend(content_parts(&message.content));
+                continue;
+            }
+            Role::User => ("user", content_parts(&message.content)),
+            Role::Assistant => {
+                let mut parts = content_parts(&message.content);
+                for call in &message.tool_calls {
+                    call_names.insert(call.id.as_str(), call.function.name.as_str());
+                    let args = serde_json::from_str(&call.function.arguments)
+                        .unwrap_or_else(|_| Value::Object(Default::default()));
+                    parts.push(Part::FunctionCall {
+                        function_call: FunctionCall {
+                            name: call.function.name.clone(),
+                            args,
+                        },
+                    });
+                }
+                ("model", parts)
+            }
+            Role::Tool | Role::Function => {
+                let name = message
+                    .name
+                    .as_deref()
+                    .or_else(|| {
+                        message
+                            .tool_call_id
+                            .as_deref()
+                            .and_then(|id| call_names.get(id).copied())
+                    })
+                    .unwrap_or_default()
+                    .to_string();
+                let text = message.text();
+                let response = serde_json::from_str::<Value>(&text)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| serde_json::json!({ "content": text }));
+                (
+                    "user",
+                    vec![Part::FunctionResponse {
+                        function_response: FunctionResponse { name, response },
+                    }],
+                )
+            }
+        };
+
+        match contents.last_mut() {
+            Some(last) if last.role.as_deref() == Some(role) => last.parts.extend(parts),
+            _ => contents.push(GoogleContent {
+                role: Some(role.to_string()),
+                parts,
+            }),
+        }
+    }
+
+    let system = (!system_parts.is_empty()).then_some(GoogleContent {
+        role: None,
+        parts: system_parts,
+    });
+    (system, contents)
+}
+
+/// Convert unified content into Gemini parts
+fn content_parts(content: &MessageContent) -> Vec<Part> {
+    match content {
+        MessageContent::Text(text) if text.is_empty() => Vec::new(),
+        MessageContent::Text(text) => vec![Part::Text { text: text.clone() }],
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(Part::Text { text: text.clone() }),
+                ContentPart::ImageBase64 { image_base64, .. } => {
+                    let (mime_type, data) = http::parse_data_url(image_base64)
+                        .unwrap_or_else(|| ("image/jpeg".to_string(), image_base64.clone()));
+                    Some(Part::InlineData {
+                        inline_data: Blob { mime_type, data },
+                    })
+                }
+                ContentPart::ImageUrl { image_url, .. } => match http::parse_data_url(image_url) {
+                    Some((mime_type, data)) => Some(Part::InlineData {
+                        inline_data: Blob { mime_type, data },
+                    }),
+                    None => {
+                        warn!("Gemini requires inline image data; skipping image URL");
+                        None
+                    }
+                },
+            })
+            .collect(),
+    }
+}
+
+/// Map a Gemini `finishReason`
+pub(crate) fn map_finish_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "STOP" => Some(FinishReason::Stop),
+        "MAX_TOKENS" => Some(FinishReason::Length),
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+            Some(FinishReason::ContentFilter)
+        }
+        _ => None,
+    }
+}
+
+/// `generateContent` request body
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GenerateContentRequest {
+    pub contents: Vec<GoogleContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<GoogleContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GenerationConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<SafetySetting>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<GoogleTool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+impl GenerationConfig {
+    fn is_empty(&self) -> bool {
+        self.temperature.is_none()
+            && self.top_p.is_none()
+            && self.top_k.is_none()
+            && self.max_output_tokens.is_none()
+            && self.stop_sequences.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GoogleTool {
+    pub function_declarations: Vec<FunctionDeclaration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct FunctionDeclaration {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+}
+
+/// A `Content` object: a role and its parts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct GoogleContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<Part>,
+}
+
+/// A single content part
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Part {
+    Text {
+        text: String,
+    },
+    InlineData {
+        #[serde(rename = "inlineData", alias = "inline_data")]
+        inline_data: Blob,
+    },
+    FunctionCall {
+        #[serde(rename = "functionCall", alias = "function_call")]
+        function_call: FunctionCall,
+    },
+    FunctionResponse {
+        #[serde(rename = "functionResponse", alias = "function_response")]
+        function_response: FunctionResponse,
+    },
+    /// Part types this client does not interpret
+    Other(Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Blob {
+    #[serde(alias = "mime_type")]
+    pub mime_type: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FunctionResponse {
+    pub name: String,
+    pub response: Value,
+}
+
+/// `generateContent` response body
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GenerateContentResponse {
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+    #[serde(default)]
+    pub usage_metadata: Option<UsageMetadata>,
+    #[serde(default)]
+    pub prompt_feedback: Option<PromptFeedback>,
+    #[serde(default)]
+    pub model_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Candidate {
+    #[serde(default)]
+    pub content: Option<GoogleContent>,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+    #[serde(default)]
+    pub index: u32,
+    #[serde(default)]
+    pub safety_ratings: Vec<SafetyRating>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptFeedback {
+    #[serde(default)]
+    pub block_reason: Option<String>,
+    #[serde(default)]
+    pub safety_ratings: Vec<SafetyRating>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SafetyRating {
+    pub category: String,
+    pub probability: String,
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UsageMetadata {
+    #[serde(default)]
+    pub prompt_token_count: u32,
+    #[serde(default)]
+    pub candidates_token_count: u32,
+}
+
+impl From<UsageMetadata> for Usage {
+    fn from(usage: UsageMetadata) -> Self {
+        Usage::new(usage.prompt_token_count, usage.candidates_token_count)
+    }
+}
+
+/// Describe safety ratings for error messages
+fn describe_ratings(ratings: &[SafetyRating]) -> String {
+    ratings
+        .iter()
+        .filter(|rating| rating.blocked || rating.probability != "NEGLIGIBLE")
+        .map(|rating| format!("{}={}", rating.category, rating.probability))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl PromptFeedback {
+    /// Error for a blocked prompt, if the prompt was blocked
+    pub(crate) fn block_error(&self) -> Option<ConnectorError> {
+        let reason = self.block_reason.as_deref()?;
+        let ratings = describe_ratings(&self.safety_ratings);
+        Some(ConnectorError::ContentFiltered(ProviderError::new(
+            "google",
+            if ratings.is_empty() {
+                format!("Prompt blocked by Gemini ({})", reason)
+            } else {
+                format!("Prompt blocked by Gemini ({}): {}", reason, ratings)
+            },
+        )))
+    }
+}
+
+impl Candidate {
+    /// Error for a candidate that was blocked before producing any output
+    pub(crate) fn block_error(&self, has_output: bool) -> Option<ConnectorError> {
+        let reason = self.finish_reason.as_deref()?;
+        if has_output || map_finish_reason(reason) != Some(FinishReason::ContentFilter) {
+            return None;
+        }
+        let ratings = describe_ratings(&self.safety_ratings);
+        Some(ConnectorError::ContentFiltered(ProviderError::new(
+            "google",
+            if ratings.is_empty() {
+                format!("Response blocked by Gemini ({})", reason)
+            } else {
+                format!("Response blocked by Gemini ({}): {}", reason, ratings)
+            },
+        )))
+    }
+
+    /// Text and tool calls carried by this candidate
+    pub(crate) fn output(&self) -> (String, Vec<ToolCall>) {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for part in self.content.iter().flat_map(|content| &content.parts) {
+            match part {
+                Part::Text { text: t } => text.push_str(t),
+                Part::FunctionCall { function_call } => tool_calls.push(ToolCall::function(
+                    format!("call_{}_{}", self.index, tool_calls.len()),
+                    function_call.name.clone(),
+                    function_call.args.to_string(),
+                )),
+                _ => {}
+            }
+        }
+        (text, tool_calls)
+    }
+}
+
+impl GenerateContentResponse {
+    fn into_unified(self, provider: &str, model: &str) -> Result<CompletionResponse> {
+        if let Some(error) = self
+            .prompt_feedback
+            .as_ref()
+            .and_then(PromptFeedback::block_error)
+        {
+            return Err(error);
+        }
+        if self.candidates.is_empty() {
+            return Err(ConnectorError::Internal(
+                "Gemini response contained no candidates".to_string(),
+            ));
+        }
+
+        let mut choices = Vec::with_capacity(self.candidates.len());
+        for candidate in &self.candidates {
+            let (text, tool_calls) = candidate.output();
+            let has_output = !text.is_empty() || !tool_calls.is_empty();
+            if let Some(error) = candidate.block_error(has_output) {
+                return Err(error);
+            }
+
+            let finish_reason = if tool_calls.is_empty() {
+                candidate
+                    .finish_reason
+                    .as_deref()
+                    .and_then(map_finish_reason)
+            } else {
+                Some(FinishReason::ToolCalls)
+            };
+            choices.push(Choice {
+                index: candidate.index,
+                message: Message::assistant(text).with_tool_calls(tool_calls),
+                finish_reason,
+            });
+        }
+
+        let model = self.model_version.unwrap_or_else(|| model.to_string());
+        Ok(CompletionResponse {
+            id: format!("gemini-{}", uuid::Uuid::new_v4()),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: model.clone(),
+            choices,
+            usage: self.usage_metadata.map(Usage::from).unwrap_or_default(),
+            metadata: Some(ProviderMetadata {
+                provider: provider.to_string(),
+                model,
+                ..Default::default()
+            }),
+        })
+    }
+}
+
+/// `GET /models` response body
+#[derive(Debug, Clone, Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ModelEntry {
+    name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_request_mapping() {
+        let provider = GoogleProvider::new("key");
+        let request = CompletionRequest::builder("gemini-pro")
+            .message(Message::system("Be brief."))
+            .message(Message::user("Hello"))
+            .message(Message::assistant("Hi!"))
+            .message(Message::user("Weather?"))
+            .temperature(0.2)
+            .max_tokens(64)
+            .stop("END")
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(provider.build_request(&request)).unwrap();
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be brief.");
+        assert_eq!(body["contents"].as_array().unwrap().len(), 3);
+        assert_eq!(body["contents"][1]["role"], "model");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(body["generationConfig"]["stopSequences"][0], "END");
+        assert_eq!(
+            body["safetySettings"][0],
+            json!({"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH"})
+        );
+    }
+
+    #[test]
+    fn test_function_call_mapping() {
+        let provider = GoogleProvider::new("key");
+        let request = CompletionRequest::new(
+            "gemini-pro",
+            vec![
+                Message::user("Weather in Paris?"),
+                Message::assistant("").with_tool_calls(vec![ToolCall::function(
+                    "call_0_0",
+                    "get_weather",
+                    "{\"city\":\"Paris\"}",
+                )]),
+                Message::tool("call_0_0", "{\"temp\":18}"),
+                Message::tool("call_0_0", "sunny"),
+            ],
+        );
+
+        let body = serde_json::to_value(provider.build_request(&request)).unwrap();
+        assert_eq!(
+            body["contents"][1]["parts"][0]["functionCall"]["args"]["city"],
+            "Paris"
+        );
+        let responses = &body["contents"][2]["parts"];
+        assert_eq!(responses[0]["functionResponse"]["name"], "get_weather");
+        assert_eq!(responses[0]["functionResponse"]["response"]["temp"], 18);
+        assert_eq!(
+            responses[1]["functionResponse"]["response"]["content"],
+            "sunny"
+        );
+    }
+
+    #[test]
+    fn test_prompt_block_is_content_filtered() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true},
+                    {"category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE"}
+                ]
+            }
+        }))
+        .unwrap();
+
+        let err = response.into_unified("google", "gemini-pro").unwrap_err();
+        match err {
+            ConnectorError::ContentFiltered(ProviderError { message, .. }) => {
+                assert!(message.contains("HARM_CATEGORY_DANGEROUS_CONTENT=HIGH"));
+                assert!(!message.contains("HARASSMENT"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_candidate_finish_reasons() {
+        let blocked: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{"finishReason": "RECITATION", "index": 0}]
+        }))
+        .unwrap();
+        assert!(matches!(
+            blocked.into_unified("google", "gemini-pro"),
+            Err(ConnectorError::ContentFiltered(_))
+        ));
+
+        let partial: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Partial"}]},
+                "finishReason": "SAFETY"
+            }]
+        }))
+        .unwrap();
+        let response = partial.into_unified("google", "gemini-pro").unwrap();
+        assert_eq!(response.finish_reason(), Some(FinishReason::ContentFilter));
+
+        assert_eq!(map_finish_reason("MAX_TOKENS"), Some(FinishReason::Length));
+        assert_eq!(map_finish_reason("OTHER"), None);
+    }
+
+    #[tokio::test]
+    async fn test_complete_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-pro:generateContent"))
+            .and(header("x-goog-api-key", "g-key"))
+            .and(body_partial_json(json!({"contents": [{"role": "user", "parts": [{"text": "Hi"}]}]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": {
+                        "role": "model",
+                        "parts": [
+                            {"text": "Let me check. "},
+                            {"functionCall": {"name": "lookup", "args": {"q": "hi"}}}
+                        ]
+                    },
+                    "finishReason": "STOP",
+                    "index": 0
+                }],
+                "usageMetadata": {"promptTokenCount": 4, "candidatesTokenCount": 6, "totalTokenCount": 10}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = GoogleProvider::new("g-key").with_endpoint(server.uri());
+        let request = CompletionRequest::new("gemini-pro", vec![Message::user("Hi")]);
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Let me check. "));
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(
+            response.choices[0].message.tool_calls[0].function.arguments,
+            "{\"q\":\"hi\"}"
+        );
+        assert_eq!(response.usage, Usage::new(4, 6));
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-pro:streamGenerateContent"))
+            .and(header("x-goog-api-key", "g-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                "[{\"candidates\": [{\"content\": {\"role\": \"model\", \"parts\": [{\"text\": \"Hel\"}]}, \"index\": 0}]}\n",
+                ",{\"candidates\": [{\"content\": {\"role\": \"model\", \"parts\": [{\"text\": \"lo\"}]}, \"finishReason\": \"STOP\", \"index\": 0}],",
+                " \"usageMetadata\": {\"promptTokenCount\": 2, \"candidatesTokenCount\": 2}}\n",
+                "]",
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = GoogleProvider::new("g-key").with_endpoint(server.uri());
+        let request = CompletionRequest::new("gemini-pro", vec![Message::user("Hi")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = crate::streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hello"));
+        assert_eq!(response.model, "gemini-pro");
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_default_models_are_listed() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "models": [{"name": "models/gemini-pro"}, {"name": "models/gemini-ultra"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut adapter = ConfigAdapter::new();
+        let mut config = adapter.get_provider_config("google").unwrap().clone();
+        assert_eq!(config.endpoint.as_deref(), Some(DEFAULT_ENDPOINT));
+        config.api_key = Some("g-key".to_string());
+        config.endpoint = Some(server.uri());
+
+        let provider = GoogleProvider::from_config(&config).unwrap();
+        let models = provider.list_models().await.unwrap();
+        for model in &config.models {
+            assert!(models.contains(model), "missing {}", model);
+        }
+    }
+}
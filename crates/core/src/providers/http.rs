@@ -0,0 +1,306 @@
+//! Shared HTTP and telemetry plumbing for the native providers.
+
+use super::errors::ErrorMapper;
+use super::ProviderStream;
+use crate::adapters::telemetry::{SharedSpanAdapter, SpanKind};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::streaming::StreamAggregator;
+use crate::types::Usage;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use reqwest::header::HeaderMap;
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tracing::{debug, warn};
+
+/// Buffered HTTP response
+#[derive(Debug, Clone)]
+pub(crate) struct HttpResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body
+    pub body: Bytes,
+}
+
+impl HttpResponse {
+    /// Whether the status is 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Header value as a string, if present and valid
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Body as lossy UTF-8
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Decode the body as JSON
+    pub fn json<T: DeserializeOwned>(&self, provider: &str) -> Result<T> {
+        serde_json::from_slice(&self.body).map_err(|e| {
+            ConnectorError::Internal(format!("Invalid {} response body: {}", provider, e))
+        })
+    }
+}
+
+/// Send a request and buffer the full response
+pub(crate) async fn send(provider: &str, request: RequestBuilder) -> Result<HttpResponse> {
+    let response = open(provider, request).await?;
+    read(provider, response).await
+}
+
+/// Send a request without reading the body, for streaming responses
+pub(crate) async fn open(provider: &str, request: RequestBuilder) -> Result<reqwest::Response> {
+    request
+        .send()
+        .await
+        .map_err(|e| transport_error(provider, "request failed", &e))
+}
+
+/// Buffer the body of an opened response
+pub(crate) async fn read(provider: &str, response: reqwest::Response) -> Result<HttpResponse> {
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| transport_error(provider, "failed to read response", &e))?;
+
+    debug!(
+        provider = provider,
+        status = status,
+        "Received provider response"
+    );
+
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Classify a transport failure as a timeout or a network error
+fn transport_error(provider: &str, context: &str, e: &reqwest::Error) -> ConnectorError {
+    let error = ProviderError::new(provider, format!("{}: {}", context, e));
+    if e.is_timeout() {
+        ConnectorError::Timeout(error)
+    } else {
+        ConnectorError::Network(error)
+    }
+}
+
+/// Open a streaming request and decode its body
+///
+/// Reports the request on a telemetry span that finishes with the stream.
+/// `body` is the JSON already attached to `request`. A non-2xx response is
+/// buffered and converted with `map_error`.
+pub(crate) async fn send_stream<F>(
+    provider: &str,
+    telemetry: &ProviderTelemetry,
+    model: &str,
+    request: RequestBuilder,
+    body: &Value,
+    map_error: ErrorMapper,
+    decode: F,
+) -> Result<ProviderStream>
+where
+    F: FnOnce(reqwest::Response) -> ProviderStream,
+{
+    let span_id = telemetry.start(provider, model, body);
+    let result = async {
+        let response = open(provider, request).await?;
+        if !response.status().is_success() {
+            let response = read(provider, response).await?;
+            return Err(map_error(provider, &response));
+        }
+        // Shared format decoders report errors without a provider name
+        let provider = provider.to_string();
+        Ok(decode(response)
+            .map(move |item| item.map_err(|e| e.with_provider(&provider)))
+            .boxed())
+    }
+    .await;
+
+    match result {
+        Ok(stream) => Ok(telemetry.instrument(span_id, stream)),
+        Err(e) => {
+            telemetry.fail(span_id);
+            Err(e)
+        }
+    }
+}
+
+/// Join a base URL and a path without doubling slashes
+pub(crate) fn join_url(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Split a `data:<media type>;base64,<data>` URL into media type and data
+pub(crate) fn parse_data_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("data:")?;
+    let (media_type, data) = rest.split_once(";base64,")?;
+    Some((media_type.to_string(), data.to_string()))
+}
+
+/// Optional span reporting through a shared `SpanAdapter`
+#[derive(Clone, Default)]
+pub(crate) struct ProviderTelemetry {
+    adapter: Option<SharedSpanAdapter>,
+    kind: SpanKind,
+}
+
+impl ProviderTelemetry {
+    /// Report spans to the given adapter
+    pub fn new(adapter: SharedSpanAdapter) -> Self {
+        Self {
+            adapter: Some(adapter),
+            kind: SpanKind::Provider,
+        }
+    }
+
+    /// Mark started spans as measuring something other than one provider
+    /// operation
+    pub fn with_kind(mut self, kind: SpanKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Start a span and record the outgoing request
+    pub fn start(&self, provider: &str, model: &str, request: &Value) -> Option<String> {
+        let adapter = self.adapter.as_ref()?;
+        let mut adapter = adapter.lock().ok()?;
+
+        let span_id = adapter.start_provider_span(provider, model, None);
+        if span_id.is_empty() {
+            return None;
+        }
+        if let Err(e) = adapter.set_span_kind(&span_id, self.kind) {
+            warn!(error = %e, "Failed to set span kind");
+        }
+        if let Err(e) = adapter.record_request(&span_id, request) {
+            warn!(error = %e, "Failed to record request on span");
+        }
+        Some(span_id)
+    }
+
+    /// Record the response and usage, then finish the span successfully
+    pub fn succeed(&self, span_id: Option<String>, response: &Value, usage: &Usage) {
+        self.finish(span_id, |adapter, span_id| {
+            adapter.record_response(span_id, response)?;
+            adapter.record_usage(span_id, usage.prompt_tokens, usage.completion_tokens)?;
+            adapter.finish_span(span_id, true)
+        });
+    }
+
+    /// Finish the span as failed
+    pub fn fail(&self, span_id: Option<String>) {
+        self.finish(span_id, |adapter, span_id| {
+            adapter.finish_span(span_id, false)
+        });
+    }
+
+    /// Finish the span when the stream ends
+    ///
+    /// The span succeeds with the aggregated response once the stream is
+    /// exhausted, and fails on the first error or if the stream is dropped
+    /// early.
+    pub fn instrument(&self, span_id: Option<String>, stream: ProviderStream) -> ProviderStream {
+        if span_id.is_none() {
+            return stream;
+        }
+        Box::pin(InstrumentedStream {
+            inner: stream,
+            telemetry: self.clone(),
+            span_id,
+            aggregator: StreamAggregator::new(),
+        })
+    }
+
+    fn finish<F>(&self, span_id: Option<String>, f: F)
+    where
+        F: FnOnce(&mut crate::adapters::SpanAdapter, &str) -> Result<()>,
+    {
+        let (Some(adapter), Some(span_id)) = (self.adapter.as_ref(), span_id) else {
+            return;
+        };
+        if let Ok(mut adapter) = adapter.lock() {
+            if let Err(e) = f(&mut adapter, &span_id) {
+                warn!(span_id = %span_id, error = %e, "Failed to finish provider span");
+            }
+        }
+    }
+}
+
+/// Stream wrapper that reports its outcome to a span
+struct InstrumentedStream {
+    inner: ProviderStream,
+    telemetry: ProviderTelemetry,
+    span_id: Option<String>,
+    aggregator: StreamAggregator,
+}
+
+impl Stream for InstrumentedStream {
+    type Item = <ProviderStream as Stream>::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let item = futures::ready!(this.inner.as_mut().poll_next(cx));
+        match &item {
+            Some(Ok(event)) => this.aggregator.push(event.clone()),
+            Some(Err(_)) => this.telemetry.fail(this.span_id.take()),
+            None => {
+                if let Some(span_id) = this.span_id.take() {
+                    let response = std::mem::take(&mut this.aggregator).finish();
+                    let raw = serde_json::to_value(&response).unwrap_or_default();
+                    this.telemetry.succeed(Some(span_id), &raw, &response.usage);
+                }
+            }
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl Drop for InstrumentedStream {
+    fn drop(&mut self) {
+        self.telemetry.fail(self.span_id.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_url() {
+        assert_eq!(
+            join_url("https://api.openai.com/v1/", "/chat/completions"),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            join_url("http://localhost:8080", "models"),
+            "http://localhost:8080/models"
+        );
+    }
+
+    #[test]
+    fn test_parse_data_url() {
+        assert_eq!(
+            parse_data_url("data:image/png;base64,aGVsbG8="),
+            Some(("image/png".to_string(), "aGVsbG8=".to_string()))
+        );
+        assert_eq!(parse_data_url("https://example.com/cat.png"), None);
+        assert_eq!(parse_data_url("data:text/plain,hello"), None);
+    }
+}
@@ -0,0 +1,386 @@
+//! # Mistral Provider
+//!
+//! Native client for the Mistral AI API.
+//!
+//! Chat completions use the OpenAI wire format with a few differences:
+//!
+//! - The `user` field is not accepted and is dropped
+//! - `safe_prompt` injects Mistral's safety system prompt
+//!
+//! Codestral models additionally support fill-in-the-middle completion
+//! through [`MistralProvider::fim_complete`].
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::mistral::{FimRequest, MistralProvider};
+//!
+//! let mut config = ConfigAdapter::new();
+//! let provider = MistralProvider::from_adapter(&mut config)?;
+//! let response = provider.complete(&request).await?;
+//!
+//! let fim = FimRequest::new("codestral-latest", "def fib(n):").with_suffix("return a");
+//! let code = provider.fim_complete(&fim).await?;
+//! ```
+
+use super::errors;
+use super::http::{self, ProviderTelemetry};
+use super::openai::{self, ChatRequest, ChatResponse, ModelList};
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, Result};
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+/// Default Mistral API endpoint
+pub const DEFAULT_ENDPOINT: &str = "https://api.mistral.ai/v1";
+
+/// Fill-in-the-middle completion request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FimRequest {
+    /// Model name (a Codestral model)
+    pub model: String,
+    /// Code before the cursor
+    pub prompt: String,
+    /// Code after the cursor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// Sampling temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Maximum tokens to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Nucleus sampling probability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Stop sequences
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+}
+
+impl FimRequest {
+    /// Create a request completing after `prompt`
+    pub fn new(model: impl Into<String>, prompt: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            prompt: prompt.into(),
+            suffix: None,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            stop: Vec::new(),
+        }
+    }
+
+    /// Set the code following the insertion point
+    pub fn with_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+
+    /// Set maximum tokens to generate
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set sampling temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Append a stop sequence
+    pub fn with_stop(mut self, stop: impl Into<String>) -> Self {
+        self.stop.push(stop.into());
+        self
+    }
+}
+
+/// Mistral AI provider
+#[derive(Clone)]
+pub struct MistralProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    safe_prompt: bool,
+    telemetry: ProviderTelemetry,
+}
+
+impl MistralProvider {
+    /// Create a provider using the default endpoint
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            api_key: api_key.into(),
+            safe_prompt: false,
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// Recognized settings: `safe_prompt`.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let api_key = config.api_key.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("API key not configured for {}", config.provider))
+        })?;
+
+        let mut provider = Self::new(api_key);
+        if let Some(endpoint) = &config.endpoint {
+            provider.endpoint = endpoint.clone();
+        }
+        if let Some(safe_prompt) = config.settings.get("safe_prompt").and_then(Value::as_bool) {
+            provider.safe_prompt = safe_prompt;
+        }
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "mistral" entry of a config adapter
+    ///
+    /// The API key is read with `get_credential("mistral", "api_key")` if
+    /// the configuration does not carry one.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut config = adapter.get_provider_config("mistral")?.clone();
+        if config.api_key.is_none() {
+            config.api_key = Some(adapter.get_credential("mistral", "api_key")?);
+        }
+        Self::from_config(&config)
+    }
+
+    /// Override the API endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Enable Mistral's safety system prompt
+    pub fn with_safe_prompt(mut self, safe_prompt: bool) -> Self {
+        self.safe_prompt = safe_prompt;
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    /// Build the chat-completions request body
+    pub(crate) fn build_request(&self, request: &CompletionRequest) -> ChatRequest {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.user = None;
+        if self.safe_prompt {
+            chat.extra
+                .insert("safe_prompt".to_string(), Value::Bool(true));
+        }
+        chat
+    }
+
+    /// Run a fill-in-the-middle completion
+    pub async fn fim_complete(&self, request: &FimRequest) -> Result<CompletionResponse> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+        self.post(&request.model, "fim/completions", body).await
+    }
+
+    /// POST a body and decode a chat-completions shaped response
+    async fn post(&self, model: &str, path: &str, body: Value) -> Result<CompletionResponse> {
+        let span_id = self.telemetry.start(self.name(), model, &body);
+        let url = http::join_url(&self.endpoint, path);
+        debug!(url = %url, model = %model, "Sending Mistral request");
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.client
+                    .post(&url)
+                    .bearer_auth(&self.api_key)
+                    .json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::openai(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let chat: ChatResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+                ConnectorError::Internal(format!("Unexpected Mistral response: {}", e))
+            })?;
+            Ok((raw, chat.into_unified(self.name())))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for MistralProvider {
+    fn name(&self) -> &str {
+        "mistral"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: true,
+            vision: true,
+            json_mode: true,
+            max_tokens: None,
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let mut chat = self.build_request(request);
+        chat.stream = false;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+        self.post(&request.model, "chat/completions", body).await
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let mut chat = self.build_request(request);
+        chat.stream = true;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let url = http::join_url(&self.endpoint, "chat/completions");
+        debug!(url = %url, model = %request.model, "Streaming Mistral chat completion");
+        openai::stream_chat(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.client
+                .post(&url)
+                .bearer_auth(&self.api_key)
+                .json(&body),
+            &body,
+            errors::openai,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.endpoint, "models");
+        let response = http::send(
+            self.name(),
+            self.client.get(&url).bearer_auth(&self.api_key),
+        )
+        .await?;
+        if !response.is_success() {
+            return Err(errors::openai(self.name(), &response));
+        }
+        let models: ModelList = response.json(self.name())?;
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FinishReason, Message, Usage};
+    use serde_json::json;
+    use wiremock::matchers::{body_json, body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn chat_response(content: &str) -> Value {
+        json!({
+            "id": "cmpl-mistral",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "codestral-latest",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        })
+    }
+
+    #[test]
+    fn test_request_drops_user_and_sets_safe_prompt() {
+        let provider = MistralProvider::new("key").with_safe_prompt(true);
+        let request = CompletionRequest::builder("mistral-small-latest")
+            .message(Message::user("Hi"))
+            .user("user-42")
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(provider.build_request(&request)).unwrap();
+        assert!(body.get("user").is_none());
+        assert_eq!(body["safe_prompt"], true);
+    }
+
+    #[tokio::test]
+    async fn test_complete_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("authorization", "Bearer mistral-key"))
+            .and(body_partial_json(json!({"model": "mistral-small-latest"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("Bonjour")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = MistralProvider::new("mistral-key").with_endpoint(server.uri());
+        let request = CompletionRequest::new("mistral-small-latest", vec![Message::user("Hi")]);
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Bonjour"));
+        assert_eq!(response.metadata.unwrap().provider, "mistral");
+    }
+
+    #[tokio::test]
+    async fn test_fim_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/fim/completions"))
+            .and(body_json(json!({
+                "model": "codestral-latest",
+                "prompt": "def add(a, b):\n",
+                "suffix": "\nprint(add(1, 2))",
+                "max_tokens": 32
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(chat_response("    return a + b")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = MistralProvider::new("mistral-key").with_endpoint(server.uri());
+        let request = FimRequest::new("codestral-latest", "def add(a, b):\n")
+            .with_suffix("\nprint(add(1, 2))")
+            .with_max_tokens(32);
+
+        let response = provider.fim_complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("    return a + b"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(10, 5));
+    }
+}
@@ -0,0 +1,526 @@
+//! # Mock Provider
+//!
+//! Deterministic in-process provider for tests and benchmarks.
+//!
+//! A [`MockProvider`] replays a script of [`MockStep`]s, one per call to
+//! `complete` or `stream`. Each step can return a response, fail like a real
+//! HTTP backend would (429, 500, timeouts, malformed JSON), or stream a fixed
+//! event sequence, optionally after an injected latency. Once the script is
+//! exhausted the provider echoes the last user message.
+//!
+//! Latencies use `tokio::time`, so tests running with a paused clock
+//! (`#[tokio::test(start_paused = true)]`) stay fast and deterministic.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::mock::{MockFailure, MockProvider};
+//!
+//! let provider = MockProvider::new("openai")
+//!     .with_latency(Duration::from_millis(50))
+//!     .fail_with(MockFailure::RateLimited)
+//!     .respond_with_text("Hello!");
+//!
+//! assert!(provider.complete(&request).await.is_err());
+//! assert_eq!(provider.complete(&request).await?.text().as_deref(), Some("Hello!"));
+//! assert_eq!(provider.call_count(), 2);
+//! ```
+
+use super::errors;
+use super::http::HttpResponse;
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::streaming::{events_from_response, StreamAggregator, StreamEvent};
+use crate::types::{
+    Choice, CompletionRequest, CompletionResponse, FinishReason, Message, ProviderMetadata, Role,
+    Usage,
+};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Scripted failure, shaped like the errors real providers produce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockFailure {
+    /// HTTP 429
+    RateLimited,
+    /// HTTP 500
+    ServerError,
+    /// Arbitrary non-2xx status with a response body
+    Status {
+        /// HTTP status code
+        status: u16,
+        /// Response body
+        body: String,
+    },
+    /// The request never completes in time
+    Timeout,
+    /// A 200 response whose body is not valid JSON
+    MalformedJson,
+}
+
+/// What a scripted step returns
+#[derive(Debug, Clone)]
+pub enum MockReply {
+    /// A complete response
+    Response(CompletionResponse),
+    /// A failure
+    Failure(MockFailure),
+    /// An event sequence, optionally interrupted by a failure
+    Stream {
+        /// Events yielded in order
+        events: Vec<StreamEvent>,
+        /// Failure yielded after the events
+        error: Option<MockFailure>,
+    },
+}
+
+/// One scripted call
+#[derive(Debug, Clone)]
+pub struct MockStep {
+    /// Reply for the call
+    pub reply: MockReply,
+    /// Latency before the reply, overriding the provider default
+    pub latency: Option<Duration>,
+}
+
+impl MockStep {
+    /// Create a step with the provider's default latency
+    pub fn new(reply: MockReply) -> Self {
+        Self {
+            reply,
+            latency: None,
+        }
+    }
+
+    /// Override the latency for this step
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    script: VecDeque<MockStep>,
+    requests: Vec<CompletionRequest>,
+}
+
+/// Deterministic scripted provider
+#[derive(Debug, Clone)]
+pub struct MockProvider {
+    name: String,
+    latency: Duration,
+    chunk_delay: Duration,
+    models: Vec<String>,
+    capabilities: ProviderCapabilities,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockProvider {
+    /// Create a provider reporting the given name
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            latency: Duration::ZERO,
+            chunk_delay: Duration::ZERO,
+            models: vec!["mock-model".to_string()],
+            capabilities: ProviderCapabilities {
+                streaming: true,
+                function_calling: true,
+                ..Default::default()
+            },
+            state: Arc::default(),
+        }
+    }
+
+    /// Latency injected before every reply
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Delay between streamed events
+    pub fn with_chunk_delay(mut self, delay: Duration) -> Self {
+        self.chunk_delay = delay;
+        self
+    }
+
+    /// Models returned by `list_models`
+    pub fn with_models(mut self, models: Vec<String>) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Override the advertised capabilities
+    pub fn with_capabilities(mut self, capabilities: ProviderCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Append a step to the script
+    pub fn then(self, step: MockStep) -> Self {
+        self.push(step);
+        self
+    }
+
+    /// Append a text response to the script
+    pub fn respond_with_text(self, text: impl Into<String>) -> Self {
+        let response = self.response(String::new(), text.into(), None);
+        self.respond_with(response)
+    }
+
+    /// Append a full response to the script
+    pub fn respond_with(self, response: CompletionResponse) -> Self {
+        self.then(MockStep::new(MockReply::Response(response)))
+    }
+
+    /// Append a failure to the script
+    pub fn fail_with(self, failure: MockFailure) -> Self {
+        self.then(MockStep::new(MockReply::Failure(failure)))
+    }
+
+    /// Append an event sequence to the script
+    pub fn stream_events(self, events: Vec<StreamEvent>) -> Self {
+        self.then(MockStep::new(MockReply::Stream {
+            events,
+            error: None,
+        }))
+    }
+
+    /// Append an event sequence that fails after its last event
+    pub fn stream_then_fail(self, events: Vec<StreamEvent>, failure: MockFailure) -> Self {
+        self.then(MockStep::new(MockReply::Stream {
+            events,
+            error: Some(failure),
+        }))
+    }
+
+    /// Append a step to a provider that is already shared
+    pub fn push(&self, step: MockStep) {
+        self.lock().script.push_back(step);
+    }
+
+    /// Number of `complete`/`stream` calls received
+    pub fn call_count(&self) -> usize {
+        self.lock().requests.len()
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<CompletionRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// Scripted steps not yet consumed
+    pub fn remaining(&self) -> usize {
+        self.lock().script.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        // A panic in another test thread must not cascade through the mock
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record the request and take the next step (or an echo reply)
+    async fn next_step(&self, request: &CompletionRequest) -> MockReply {
+        let (step, call) = {
+            let mut state = self.lock();
+            state.requests.push(request.clone());
+            (state.script.pop_front(), state.requests.len())
+        };
+
+        let step = step.unwrap_or_else(|| {
+            let prompt = request
+                .messages
+                .iter()
+                .rev()
+                .find(|message| message.role == Role::User)
+                .map(Message::text)
+                .unwrap_or_default();
+            MockStep::new(MockReply::Response(self.response(
+                format!("mock-{}", call),
+                prompt,
+                Some(request),
+            )))
+        });
+
+        let latency = step.latency.unwrap_or(self.latency);
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        step.reply
+    }
+
+    /// Build a deterministic response
+    fn response(
+        &self,
+        id: String,
+        text: String,
+        request: Option<&CompletionRequest>,
+    ) -> CompletionResponse {
+        let model = request
+            .map(|request| request.model.clone())
+            .unwrap_or_else(|| self.models.first().cloned().unwrap_or_default());
+        let prompt_tokens = request
+            .map(|request| {
+                request
+                    .messages
+                    .iter()
+                    .map(|message| word_count(&message.text()))
+                    .sum()
+            })
+            .unwrap_or_default();
+
+        CompletionResponse {
+            id: if id.is_empty() {
+                "mock".to_string()
+            } else {
+                id
+            },
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: model.clone(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(text.clone()),
+                finish_reason: Some(FinishReason::Stop),
+            }],
+            usage: Usage::new(prompt_tokens, word_count(&text)),
+            metadata: Some(ProviderMetadata {
+                provider: self.name.clone(),
+                model,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Turn a scripted failure into the error a real provider would return
+    fn error(&self, failure: MockFailure) -> ConnectorError {
+        let (status, body) = match failure {
+            MockFailure::RateLimited => (429, "rate limit exceeded".to_string()),
+            MockFailure::ServerError => (500, "internal server error".to_string()),
+            MockFailure::Status { status, body } => (status, body),
+            MockFailure::Timeout => {
+                return ConnectorError::Timeout(ProviderError::new(&self.name, "request timed out"))
+            }
+            MockFailure::MalformedJson => {
+                let response = HttpResponse {
+                    status: 200,
+                    headers: Default::default(),
+                    body: "{\"id\": \"mock\", \"choices\": [".into(),
+                };
+                return match response.json::<Value>(&self.name) {
+                    Err(e) => e,
+                    Ok(_) => unreachable!("mock body is not valid JSON"),
+                };
+            }
+        };
+
+        let response = HttpResponse {
+            status,
+            headers: Default::default(),
+            body: body.into(),
+        };
+        errors::generic(&self.name, &response)
+    }
+
+    /// Aggregate scripted events into a single response for `complete`
+    ///
+    /// Scripted `Start` and `Usage` events override the mock defaults.
+    fn collect_events(
+        &self,
+        request: &CompletionRequest,
+        events: Vec<StreamEvent>,
+    ) -> CompletionResponse {
+        let mut aggregator = StreamAggregator::new().with_provider(self.name.clone());
+        aggregator.push(StreamEvent::Start {
+            id: "mock".to_string(),
+            model: request.model.clone(),
+        });
+        for event in events {
+            aggregator.push(event);
+        }
+
+        let mut response = aggregator.finish();
+        response.created = 0;
+        if response.usage == Usage::default() {
+            let defaults = self.response(
+                String::new(),
+                response.text().unwrap_or_default(),
+                Some(request),
+            );
+            response.usage = defaults.usage;
+        }
+        response
+    }
+}
+
+fn word_count(text: &str) -> u32 {
+    text.split_whitespace().count() as u32
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.capabilities.clone()
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        match self.next_step(request).await {
+            MockReply::Response(response) => Ok(response),
+            MockReply::Failure(failure) => Err(self.error(failure)),
+            MockReply::Stream {
+                error: Some(failure),
+                ..
+            } => Err(self.error(failure)),
+            MockReply::Stream {
+                events,
+                error: None,
+            } => Ok(self.collect_events(request, events)),
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let (events, error) = match self.next_step(request).await {
+            MockReply::Response(response) => (events_from_response(&response), None),
+            MockReply::Failure(failure) => return Err(self.error(failure)),
+            MockReply::Stream { events, error } => (events, error.map(|f| self.error(f))),
+        };
+
+        let delay = self.chunk_delay;
+        let items = events.into_iter().map(Ok).chain(error.map(Err));
+        Ok(stream::iter(items)
+            .then(move |item| async move {
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                item
+            })
+            .boxed())
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        Ok(self.models.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::{aggregate, ChunkDelta};
+    use futures::TryStreamExt;
+    use tokio::time::Instant;
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("mock-model", vec![Message::user("ping the mock")])
+    }
+
+    #[tokio::test]
+    async fn test_script_then_echo() {
+        let provider = MockProvider::new("mock").respond_with_text("scripted");
+
+        let first = provider.complete(&request()).await.unwrap();
+        assert_eq!(first.text().as_deref(), Some("scripted"));
+
+        let second = provider.complete(&request()).await.unwrap();
+        assert_eq!(second.text().as_deref(), Some("ping the mock"));
+        assert_eq!(second.id, "mock-2");
+        assert_eq!(second.usage, Usage::new(3, 3));
+        assert_eq!(provider.call_count(), 2);
+        assert_eq!(provider.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_failures() {
+        let provider = MockProvider::new("mock")
+            .fail_with(MockFailure::RateLimited)
+            .fail_with(MockFailure::ServerError)
+            .fail_with(MockFailure::Timeout)
+            .fail_with(MockFailure::MalformedJson);
+
+        let mut errors = Vec::new();
+        for _ in 0..4 {
+            errors.push(provider.complete(&request()).await.unwrap_err());
+        }
+        assert!(matches!(errors[0], ConnectorError::RateLimited { .. }));
+        assert!(matches!(errors[1], ConnectorError::ProviderUnavailable(_)));
+        assert_eq!(errors[1].status(), Some(500));
+        assert!(matches!(errors[2], ConnectorError::Timeout(_)));
+        assert!(errors.iter().take(3).all(ConnectorError::is_retryable));
+        assert!(errors[3].to_string().contains("Invalid mock response body"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_injected_latency() {
+        let provider = MockProvider::new("mock")
+            .with_latency(Duration::from_millis(200))
+            .then(
+                MockStep::new(MockReply::Failure(MockFailure::Timeout))
+                    .with_latency(Duration::from_secs(30)),
+            );
+
+        let start = Instant::now();
+        assert!(provider.complete(&request()).await.is_err());
+        assert_eq!(start.elapsed(), Duration::from_secs(30));
+
+        let start = Instant::now();
+        provider.complete(&request()).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_stream_events_and_mid_stream_failure() {
+        let provider = MockProvider::new("mock")
+            .stream_events(vec![StreamEvent::text("Hel"), StreamEvent::text("lo")])
+            .stream_then_fail(vec![StreamEvent::text("partial")], MockFailure::ServerError)
+            .respond_with_text("replayed");
+
+        let events: Vec<StreamEvent> = provider
+            .stream(&request())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![StreamEvent::text("Hel"), StreamEvent::text("lo")]
+        );
+
+        let items: Vec<Result<StreamEvent>> =
+            provider.stream(&request()).await.unwrap().collect().await;
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_ok());
+        assert!(items[1].is_err());
+
+        let stream = provider.stream(&request()).await.unwrap();
+        let response = aggregate(stream).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("replayed"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+    }
+
+    #[tokio::test]
+    async fn test_complete_collects_stream_steps() {
+        let provider = MockProvider::new("mock").stream_events(vec![
+            StreamEvent::text("Hel"),
+            StreamEvent::text("lo"),
+            StreamEvent::delta(ChunkDelta::Finish {
+                reason: FinishReason::Length,
+            }),
+        ]);
+
+        let response = provider.complete(&request()).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hello"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Length));
+        assert_eq!(response.model, "mock-model");
+        assert_eq!(response.usage, Usage::new(3, 1));
+        assert_eq!(provider.requests()[0].model, "mock-model");
+    }
+}
@@ -0,0 +1,304 @@
+//! # LLM Providers
+//!
+//! Provider abstraction and registry.
+//!
+//! A [`Provider`] performs the actual calls against an LLM backend using the
+//! unified types from [`crate::types`]. Providers are registered in a
+//! [`ProviderRegistry`] under the same names used by
+//! [`ConfigAdapter`](crate::adapters::ConfigAdapter) ("openai", "anthropic",
+//! "google", ...).
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::ProviderRegistry;
+//!
+//! let mut registry = ProviderRegistry::new();
+//! registry.register(Arc::new(my_provider));
+//!
+//! let provider = registry.resolve("openai")?;
+//! let response = provider.complete(&request).await?;
+//! ```
+
+pub mod anthropic;
+pub mod azure;
+pub mod bedrock;
+pub mod cohere;
+pub(crate) mod errors;
+pub mod google;
+pub(crate) mod http;
+pub mod mistral;
+pub mod mock;
+pub mod openai;
+pub mod openai_compatible;
+
+pub use anthropic::AnthropicProvider;
+pub use azure::{AzureAuth, AzureOpenAIProvider};
+pub use bedrock::BedrockProvider;
+pub use cohere::CohereProvider;
+pub use google::GoogleProvider;
+pub use mistral::MistralProvider;
+pub use mock::MockProvider;
+pub use openai::OpenAIProvider;
+pub use openai_compatible::OpenAICompatibleProvider;
+
+use crate::error::{ConnectorError, Result};
+use crate::streaming::{events_from_response, StreamEvent};
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Stream of completion events returned by [`Provider::stream`]
+pub type ProviderStream = BoxStream<'static, Result<StreamEvent>>;
+
+/// Features supported by a provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    /// Supports streaming responses
+    pub streaming: bool,
+    /// Supports tool/function calling
+    pub function_calling: bool,
+    /// Supports image inputs
+    pub vision: bool,
+    /// Supports JSON output mode
+    pub json_mode: bool,
+    /// Maximum output tokens, if limited
+    pub max_tokens: Option<u32>,
+    /// Accepts system messages
+    pub supports_system_message: bool,
+}
+
+impl Default for ProviderCapabilities {
+    fn default() -> Self {
+        Self {
+            streaming: false,
+            function_calling: false,
+            vision: false,
+            json_mode: false,
+            max_tokens: None,
+            supports_system_message: true,
+        }
+    }
+}
+
+/// Provider health status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Fully operational
+    Healthy,
+    /// Operational with reduced performance
+    Degraded,
+    /// Not operational
+    Unhealthy,
+}
+
+/// LLM provider
+///
+/// Implementations translate unified requests into provider-specific calls.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// Provider name (e.g., "openai", "anthropic")
+    fn name(&self) -> &str;
+
+    /// Features supported by this provider
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Perform a chat completion
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse>;
+
+    /// Perform a streaming chat completion
+    ///
+    /// The default implementation performs a regular completion and replays
+    /// it as events, for providers without native streaming.
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let response = self.complete(request).await?;
+        let events = events_from_response(&response);
+        Ok(stream::iter(events.into_iter().map(Ok)).boxed())
+    }
+
+    /// List models available from this provider
+    async fn list_models(&self) -> Result<Vec<String>>;
+
+    /// Check provider health
+    ///
+    /// The default implementation reports `Healthy` if `list_models` succeeds.
+    async fn health_check(&self) -> Result<HealthStatus> {
+        match self.list_models().await {
+            Ok(_) => Ok(HealthStatus::Healthy),
+            Err(e) => {
+                debug!(provider = self.name(), error = %e, "Health check failed");
+                Ok(HealthStatus::Unhealthy)
+            }
+        }
+    }
+}
+
+/// Registry of providers keyed by name
+#[derive(Default, Clone)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn Provider>>,
+}
+
+impl ProviderRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a provider under its own name
+    ///
+    /// Replaces any provider previously registered under that name.
+    pub fn register(&mut self, provider: Arc<dyn Provider>) {
+        let name = provider.name().to_string();
+        self.register_as(name, provider);
+    }
+
+    /// Register a provider under a custom name
+    pub fn register_as(&mut self, name: impl Into<String>, provider: Arc<dyn Provider>) {
+        let name = name.into();
+        debug!(provider = %name, "Registering provider");
+        self.providers.insert(name, provider);
+    }
+
+    /// Remove a provider
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Provider>> {
+        self.providers.remove(name)
+    }
+
+    /// Get a provider by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Provider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// Get a provider by name, failing if it is not registered
+    pub fn resolve(&self, name: &str) -> Result<Arc<dyn Provider>> {
+        self.get(name)
+            .ok_or_else(|| ConnectorError::Config(format!("Provider not registered: {}", name)))
+    }
+
+    /// Whether a provider is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.providers.contains_key(name)
+    }
+
+    /// Registered provider names, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.providers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Number of registered providers
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Whether the registry is empty
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl std::fmt::Debug for ProviderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderRegistry")
+            .field("providers", &self.names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Choice, FinishReason, Message, Usage};
+
+    struct EchoProvider {
+        name: &'static str,
+        healthy: bool,
+    }
+
+    #[async_trait]
+    impl Provider for EchoProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities::default()
+        }
+
+        async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+            Ok(CompletionResponse {
+                id: "echo-1".to_string(),
+                object: "chat.completion".to_string(),
+                created: 0,
+                model: request.model.clone(),
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message::assistant(request.messages[0].text()),
+                    finish_reason: Some(FinishReason::Stop),
+                }],
+                usage: Usage::new(3, 3),
+                metadata: None,
+            })
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>> {
+            if self.healthy {
+                Ok(vec!["echo".to_string()])
+            } else {
+                Err(ConnectorError::Internal("down".to_string()))
+            }
+        }
+    }
+
+    fn echo(name: &'static str, healthy: bool) -> Arc<dyn Provider> {
+        Arc::new(EchoProvider { name, healthy })
+    }
+
+    #[test]
+    fn test_registry_register_and_resolve() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(echo("openai", true));
+        registry.register(echo("anthropic", true));
+        registry.register_as("google", echo("echo", true));
+
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.names(), vec!["anthropic", "google", "openai"]);
+        assert_eq!(registry.resolve("google").unwrap().name(), "echo");
+        assert!(registry.resolve("unknown").is_err());
+
+        assert!(registry.unregister("openai").is_some());
+        assert!(!registry.contains("openai"));
+    }
+
+    #[tokio::test]
+    async fn test_default_stream_replays_response() {
+        let provider = echo("openai", true);
+        let request = CompletionRequest::new("echo", vec![Message::user("Hello")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = crate::streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.id, "echo-1");
+        assert_eq!(response.text().as_deref(), Some("Hello"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(3, 3));
+    }
+
+    #[tokio::test]
+    async fn test_default_health_check() {
+        assert_eq!(
+            echo("openai", true).health_check().await.unwrap(),
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            echo("openai", false).health_check().await.unwrap(),
+            HealthStatus::Unhealthy
+        );
+    }
+}
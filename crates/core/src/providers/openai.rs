@@ -0,0 +1,727 @@
+//! # OpenAI Provider
+//!
+//! Native client for the OpenAI chat-completions API.
+//!
+//! The wire types in this module are shared with other providers that speak
+//! the OpenAI format (Azure OpenAI, self-hosted OpenAI-compatible servers, ...).
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::openai::OpenAIProvider;
+//!
+//! let mut config = ConfigAdapter::new();
+//! let provider = OpenAIProvider::from_adapter(&mut config)?;
+//! let response = provider.complete(&request).await?;
+//! ```
+
+use super::errors::{self, ErrorMapper};
+use super::http::{self, ProviderTelemetry};
+use super::{Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, Result};
+use crate::streaming;
+use crate::types::{
+    Choice, CompletionRequest, CompletionResponse, ContentPart, FinishReason, ImageDetail, Message,
+    MessageContent, ProviderMetadata, Role, ToolCall, ToolDefinition, Usage,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::debug;
+
+/// Default OpenAI API endpoint
+pub const DEFAULT_ENDPOINT: &str = "https://api.openai.com/v1";
+
+/// OpenAI chat-completions provider
+#[derive(Clone)]
+pub struct OpenAIProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    organization: Option<String>,
+    telemetry: ProviderTelemetry,
+}
+
+impl OpenAIProvider {
+    /// Create a provider using the default endpoint
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            api_key: api_key.into(),
+            organization: None,
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// Uses `endpoint` (falling back to the public API), `api_key`, and the
+    /// optional `organization` setting.
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let api_key = config.api_key.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("API key not configured for {}", config.provider))
+        })?;
+
+        let mut provider = Self::new(api_key);
+        if let Some(endpoint) = &config.endpoint {
+            provider.endpoint = endpoint.clone();
+        }
+        provider.organization = config
+            .settings
+            .get("organization")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the "openai" entry of a config adapter
+    ///
+    /// The API key is read from the credential store if the configuration
+    /// does not carry one.
+    pub fn from_adapter(adapter: &mut ConfigAdapter) -> Result<Self> {
+        let mut config = adapter.get_provider_config("openai")?.clone();
+        if config.api_key.is_none() {
+            config.api_key = Some(adapter.get_credential("openai", "api_key")?);
+        }
+        Self::from_config(&config)
+    }
+
+    /// Override the API endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Set the `OpenAI-Organization` header
+    pub fn with_organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = request.bearer_auth(&self.api_key);
+        match &self.organization {
+            Some(organization) => request.header("OpenAI-Organization", organization),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAIProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            function_calling: true,
+            vision: true,
+            json_mode: true,
+            max_tokens: None,
+            supports_system_message: true,
+        }
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.stream = false;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = http::join_url(&self.endpoint, "chat/completions");
+        debug!(url = %url, model = %request.model, "Sending OpenAI chat completion");
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.authorize(self.client.post(&url)).json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::openai(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let chat: ChatResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+                ConnectorError::Internal(format!("Unexpected OpenAI response: {}", e))
+            })?;
+            Ok((raw, chat.into_unified(self.name())))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.stream = true;
+        chat.extra
+            .insert("stream_options".to_string(), json!({"include_usage": true}));
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let url = http::join_url(&self.endpoint, "chat/completions");
+        debug!(url = %url, model = %request.model, "Streaming OpenAI chat completion");
+        stream_chat(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.authorize(self.client.post(&url)).json(&body),
+            &body,
+            errors::openai,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.endpoint, "models");
+        let response = http::send(self.name(), self.authorize(self.client.get(&url))).await?;
+        if !response.is_success() {
+            return Err(errors::openai(self.name(), &response));
+        }
+        let models: ModelList = response.json(self.name())?;
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+}
+
+/// Send a streaming chat-completions request and decode the SSE response
+pub(crate) async fn stream_chat(
+    provider: &str,
+    telemetry: &ProviderTelemetry,
+    model: &str,
+    request: reqwest::RequestBuilder,
+    body: &Value,
+    map_error: ErrorMapper,
+) -> Result<ProviderStream> {
+    http::send_stream(
+        provider,
+        telemetry,
+        model,
+        request,
+        body,
+        map_error,
+        |response| streaming::openai::decode(response.bytes_stream()).boxed(),
+    )
+    .await
+}
+
+/// Chat-completions request body
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ChatRequest {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>,
+    /// Provider-specific parameters merged into the body
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ChatRequest {
+    /// Build a request body from a unified request
+    pub fn from_unified(request: &CompletionRequest) -> Self {
+        Self {
+            model: request.model.clone(),
+            messages: request
+                .messages
+                .iter()
+                .map(ChatMessage::from_unified)
+                .collect(),
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            top_p: request.top_p,
+            stop: request.stop.clone(),
+            stream: request.stream,
+            user: request.user.clone(),
+            tools: request.tools.clone(),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// Chat message in OpenAI wire format
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ChatMessage {
+    pub role: Role,
+    pub content: Option<ChatContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    fn from_unified(message: &Message) -> Self {
+        let content = match &message.content {
+            MessageContent::Text(text) => ChatContent::Text(text.clone()),
+            MessageContent::Parts(parts) => {
+                let has_images = parts
+                    .iter()
+                    .any(|part| !matches!(part, ContentPart::Text { .. }));
+                if has_images {
+                    ChatContent::Parts(parts.iter().map(ChatContentPart::from_unified).collect())
+                } else {
+                    let texts: Vec<&str> = parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            ContentPart::Text { text } => Some(text.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    ChatContent::Text(texts.join("\n"))
+                }
+            }
+        };
+
+        // Assistant messages that only carry tool calls send `null` content
+        let content = match content {
+            ChatContent::Text(text) if text.is_empty() && !message.tool_calls.is_empty() => None,
+            content => Some(content),
+        };
+
+        Self {
+            role: message.role,
+            content,
+            name: message.name.clone(),
+            tool_calls: message.tool_calls.clone(),
+            tool_call_id: message.tool_call_id.clone(),
+        }
+    }
+}
+
+/// Message content in OpenAI wire format
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub(crate) enum ChatContent {
+    Text(String),
+    Parts(Vec<ChatContentPart>),
+}
+
+/// Content part in OpenAI wire format
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ChatContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+impl ChatContentPart {
+    fn from_unified(part: &ContentPart) -> Self {
+        match part {
+            ContentPart::Text { text } => ChatContentPart::Text { text: text.clone() },
+            ContentPart::ImageUrl { image_url, detail } => ChatContentPart::ImageUrl {
+                image_url: ImageUrl {
+                    url: image_url.clone(),
+                    detail: *detail,
+                },
+            },
+            ContentPart::ImageBase64 {
+                image_base64,
+                detail,
+            } => {
+                let url = if image_base64.starts_with("data:") {
+                    image_base64.clone()
+                } else {
+                    format!("data:image/jpeg;base64,{}", image_base64)
+                };
+                ChatContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url,
+                        detail: *detail,
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Image reference in OpenAI wire format
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ImageUrl {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+/// Chat-completions response body
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ChatResponse {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: Option<String>,
+    #[serde(default)]
+    pub created: i64,
+    #[serde(default)]
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    #[serde(default)]
+    pub usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ChatChoice {
+    #[serde(default)]
+    pub index: u32,
+    pub message: ChatResponseMessage,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ChatResponseMessage {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCall>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub(crate) struct ChatUsage {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
+}
+
+impl From<ChatUsage> for Usage {
+    fn from(usage: ChatUsage) -> Self {
+        Usage::new(usage.prompt_tokens, usage.completion_tokens)
+    }
+}
+
+impl ChatResponse {
+    /// Convert into the unified response, tagging it with the serving provider
+    pub fn into_unified(self, provider: &str) -> CompletionResponse {
+        let choices = self
+            .choices
+            .into_iter()
+            .map(|choice| Choice {
+                index: choice.index,
+                message: Message::assistant(choice.message.content.unwrap_or_default())
+                    .with_tool_calls(choice.message.tool_calls.unwrap_or_default()),
+                finish_reason: choice.finish_reason.as_deref().and_then(map_finish_reason),
+            })
+            .collect();
+
+        CompletionResponse {
+            id: self.id,
+            object: self.object.unwrap_or_else(|| "chat.completion".to_string()),
+            created: self.created,
+            model: self.model.clone(),
+            choices,
+            usage: self.usage.map(Usage::from).unwrap_or_default(),
+            metadata: Some(ProviderMetadata {
+                provider: provider.to_string(),
+                model: self.model,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Map an OpenAI-style `finish_reason` string
+pub(crate) fn map_finish_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "stop" => Some(FinishReason::Stop),
+        "length" | "model_length" => Some(FinishReason::Length),
+        "tool_calls" => Some(FinishReason::ToolCalls),
+        "function_call" => Some(FinishReason::FunctionCall),
+        "content_filter" => Some(FinishReason::ContentFilter),
+        _ => None,
+    }
+}
+
+/// `GET /models` response body
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ModelList {
+    pub data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ModelEntry {
+    pub id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::SpanAdapter;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn chat_response() -> Value {
+        json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4-0613",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello there!"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15}
+        })
+    }
+
+    #[test]
+    fn test_request_body_mapping() {
+        let request = CompletionRequest::builder("gpt-4")
+            .message(Message::user(vec![
+                ContentPart::Text {
+                    text: "Describe".to_string(),
+                },
+                ContentPart::ImageBase64 {
+                    image_base64: "aGk=".to_string(),
+                    detail: None,
+                },
+            ]))
+            .message(
+                Message::assistant("")
+                    .with_tool_calls(vec![ToolCall::function("call_1", "lookup", "{}")]),
+            )
+            .message(Message::tool("call_1", "result"))
+            .top_k(5)
+            .build()
+            .unwrap();
+
+        let body = serde_json::to_value(ChatRequest::from_unified(&request)).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"][1]["image_url"]["url"],
+            "data:image/jpeg;base64,aGk="
+        );
+        assert_eq!(body["messages"][1]["content"], Value::Null);
+        assert_eq!(body["messages"][1]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(body["messages"][2]["tool_call_id"], "call_1");
+        assert!(body.get("top_k").is_none());
+    }
+
+    #[test]
+    fn test_response_mapping() {
+        let response: ChatResponse = serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "created": 1,
+            "model": "gpt-4",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "lookup", "arguments": "{\"q\":1}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }))
+        .unwrap();
+
+        let unified = response.into_unified("openai");
+        assert_eq!(unified.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(
+            unified.choices[0].message.tool_calls[0].function.name,
+            "lookup"
+        );
+        assert_eq!(unified.usage, Usage::default());
+        assert_eq!(unified.metadata.unwrap().provider, "openai");
+    }
+
+    #[tokio::test]
+    async fn test_complete_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(body_partial_json(
+                json!({"model": "gpt-4", "max_tokens": 50}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new("sk-test").with_endpoint(server.uri());
+        let request = CompletionRequest::builder("gpt-4")
+            .message(Message::user("Hi"))
+            .max_tokens(50)
+            .build()
+            .unwrap();
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.id, "chatcmpl-123");
+        assert_eq!(response.text().as_deref(), Some("Hello there!"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage.total_tokens, 15);
+    }
+
+    #[tokio::test]
+    async fn test_complete_reports_span() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response()))
+            .mount(&server)
+            .await;
+
+        let telemetry = Arc::new(Mutex::new(SpanAdapter::new()));
+        let provider = OpenAIProvider::new("sk-test")
+            .with_endpoint(server.uri())
+            .with_telemetry(telemetry.clone());
+        let request = CompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+
+        provider.complete(&request).await.unwrap();
+
+        // The span was started and finished (removed from the active set)
+        assert_eq!(telemetry.lock().unwrap().active_span_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_complete_http_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": {"message": "Incorrect API key provided", "type": "invalid_request_error"}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new("bad-key").with_endpoint(server.uri());
+        let request = CompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+
+        let err = provider.complete(&request).await.unwrap_err();
+        assert!(err.to_string().contains("401"));
+    }
+
+    #[tokio::test]
+    async fn test_list_models() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [{"id": "gpt-4", "object": "model"}, {"id": "gpt-3.5-turbo", "object": "model"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new("sk-test").with_endpoint(server.uri());
+        assert_eq!(
+            provider.list_models().await.unwrap(),
+            vec!["gpt-4".to_string(), "gpt-3.5-turbo".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock_server() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"id\":\"chatcmpl-7\",\"model\":\"gpt-4\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-7\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-7\",\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(json!({
+                "stream": true,
+                "stream_options": {"include_usage": true}
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let telemetry = Arc::new(Mutex::new(SpanAdapter::new()));
+        let provider = OpenAIProvider::new("sk-test")
+            .with_endpoint(server.uri())
+            .with_telemetry(telemetry.clone());
+        let request = CompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+
+        let stream = provider.stream(&request).await.unwrap();
+        let response = streaming::aggregate(stream).await.unwrap();
+        assert_eq!(response.id, "chatcmpl-7");
+        assert_eq!(response.text().as_deref(), Some("Hello"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(5, 2));
+        assert_eq!(telemetry.lock().unwrap().active_span_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_http_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("slow down"))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new("sk-test").with_endpoint(server.uri());
+        let request = CompletionRequest::new("gpt-4", vec![Message::user("Hi")]);
+        let err = provider.stream(&request).await.err().unwrap();
+        assert!(err.to_string().contains("429"));
+    }
+
+    #[test]
+    fn test_from_config_requires_api_key() {
+        let mut adapter = ConfigAdapter::new();
+        let mut config = adapter.get_provider_config("openai").unwrap().clone();
+        assert!(OpenAIProvider::from_config(&config).is_err());
+
+        config.api_key = Some("sk-test".to_string());
+        let provider = OpenAIProvider::from_config(&config).unwrap();
+        assert_eq!(provider.endpoint, DEFAULT_ENDPOINT);
+    }
+}
@@ -0,0 +1,426 @@
+//! # OpenAI-Compatible Provider
+//!
+//! Client for self-hosted and third-party servers that speak the OpenAI
+//! chat-completions wire format (vLLM, Ollama, LM Studio, llama.cpp, ...).
+//!
+//! The provider is configured purely from a [`ProviderConfig`] and reports
+//! the configured provider name, so several instances can be registered side
+//! by side under arbitrary names:
+//!
+//! - `endpoint` is required (e.g. `http://localhost:8000/v1`)
+//! - `api_key` is optional; servers without auth get no `Authorization` header
+//! - `settings["headers"]` is an object of extra headers sent on every request
+//! - `models` is returned by `list_models` instead of querying the server;
+//!   health checks always query `/models`
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::providers::OpenAICompatibleProvider;
+//!
+//! let mut config = ConfigAdapter::new();
+//! let provider = OpenAICompatibleProvider::from_adapter(&mut config, "my-vllm")?;
+//! registry.register(Arc::new(provider));
+//! ```
+
+use super::errors;
+use super::http::{self, ProviderTelemetry};
+use super::openai::{self, ChatRequest, ChatResponse, ModelList};
+use super::{HealthStatus, Provider, ProviderCapabilities, ProviderStream};
+use crate::adapters::config::{ConfigAdapter, ProviderConfig};
+use crate::adapters::telemetry::SharedSpanAdapter;
+use crate::error::{ConnectorError, Result};
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::debug;
+
+/// Provider for OpenAI-compatible endpoints
+#[derive(Clone)]
+pub struct OpenAICompatibleProvider {
+    name: String,
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+    headers: Vec<(String, String)>,
+    models: Vec<String>,
+    capabilities: ProviderCapabilities,
+    telemetry: ProviderTelemetry,
+}
+
+impl OpenAICompatibleProvider {
+    /// Create a provider named `name` for the given base URL
+    pub fn new(name: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            client: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+            api_key: None,
+            headers: Vec::new(),
+            models: Vec::new(),
+            capabilities: ProviderCapabilities {
+                streaming: true,
+                function_calling: true,
+                ..Default::default()
+            },
+            telemetry: ProviderTelemetry::default(),
+        }
+    }
+
+    /// Create a provider from a loaded provider configuration
+    ///
+    /// The provider takes the configuration's name. Recognized settings:
+    /// `headers` (object of header name to value).
+    pub fn from_config(config: &ProviderConfig) -> Result<Self> {
+        let endpoint = config.endpoint.clone().ok_or_else(|| {
+            ConnectorError::Config(format!("Endpoint not configured for {}", config.provider))
+        })?;
+
+        let mut provider = Self::new(&config.provider, endpoint);
+        provider.api_key = config.api_key.clone();
+        provider.models = config.models.clone();
+
+        if let Some(headers) = config.settings.get("headers") {
+            let headers = headers.as_object().ok_or_else(|| {
+                ConnectorError::Config(format!(
+                    "headers setting for {} must be an object",
+                    config.provider
+                ))
+            })?;
+            for (name, value) in headers {
+                let value = value.as_str().ok_or_else(|| {
+                    ConnectorError::Config(format!(
+                        "Header {} for {} must be a string",
+                        name, config.provider
+                    ))
+                })?;
+                provider.headers.push((name.clone(), value.to_string()));
+            }
+        }
+
+        Ok(provider)
+    }
+
+    /// Create a provider from the `name` entry of a config adapter
+    ///
+    /// The API key is read with `get_credential(name, "api_key")` if the
+    /// configuration does not carry one; a missing key is not an error.
+    pub fn from_adapter(adapter: &mut ConfigAdapter, name: &str) -> Result<Self> {
+        let mut config = adapter.get_provider_config(name)?.clone();
+        if config.api_key.is_none() {
+            config.api_key = adapter.get_credential(name, "api_key").ok();
+        }
+        Self::from_config(&config)
+    }
+
+    /// Override the API endpoint
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Send a bearer token
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Send an extra header on every request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Report a fixed model list instead of querying `/models`
+    pub fn with_models(mut self, models: Vec<String>) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Override the advertised capabilities
+    pub fn with_capabilities(mut self, capabilities: ProviderCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Use a custom HTTP client (timeouts, proxies, ...)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Report spans through a shared telemetry adapter
+    pub fn with_telemetry(mut self, telemetry: SharedSpanAdapter) -> Self {
+        self.telemetry = ProviderTelemetry::new(telemetry);
+        self
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let mut request = match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        };
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    /// Query the server's `/models`
+    async fn fetch_models(&self) -> Result<Vec<String>> {
+        let url = http::join_url(&self.endpoint, "models");
+        let response = http::send(self.name(), self.authorize(self.client.get(&url))).await?;
+        if !response.is_success() {
+            return Err(errors::openai(self.name(), &response));
+        }
+        let models: ModelList = response.json(self.name())?;
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAICompatibleProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.capabilities.clone()
+    }
+
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.stream = false;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let span_id = self.telemetry.start(self.name(), &request.model, &body);
+        let url = http::join_url(&self.endpoint, "chat/completions");
+        debug!(
+            provider = %self.name,
+            url = %url,
+            model = %request.model,
+            "Sending OpenAI-compatible chat completion"
+        );
+
+        let result = async {
+            let response = http::send(
+                self.name(),
+                self.authorize(self.client.post(&url)).json(&body),
+            )
+            .await?;
+            if !response.is_success() {
+                return Err(errors::openai(self.name(), &response));
+            }
+            let raw: Value = response.json(self.name())?;
+            let chat: ChatResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+                ConnectorError::Internal(format!("Unexpected {} response: {}", self.name, e))
+            })?;
+            Ok((raw, chat.into_unified(self.name())))
+        }
+        .await;
+
+        match result {
+            Ok((raw, response)) => {
+                self.telemetry.succeed(span_id, &raw, &response.usage);
+                Ok(response)
+            }
+            Err(e) => {
+                self.telemetry.fail(span_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &CompletionRequest) -> Result<ProviderStream> {
+        let mut chat = ChatRequest::from_unified(request);
+        chat.stream = true;
+        let body = serde_json::to_value(chat)
+            .map_err(|e| ConnectorError::Internal(format!("Failed to encode request: {}", e)))?;
+
+        let url = http::join_url(&self.endpoint, "chat/completions");
+        debug!(
+            provider = %self.name,
+            url = %url,
+            model = %request.model,
+            "Streaming OpenAI-compatible chat completion"
+        );
+        openai::stream_chat(
+            self.name(),
+            &self.telemetry,
+            &request.model,
+            self.authorize(self.client.post(&url)).json(&body),
+            &body,
+            errors::openai,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        if !self.models.is_empty() {
+            return Ok(self.models.clone());
+        }
+        self.fetch_models().await
+    }
+
+    /// Reports `Healthy` if the server's `/models` answers, even when models
+    /// are configured
+    async fn health_check(&self) -> Result<HealthStatus> {
+        match self.fetch_models().await {
+            Ok(_) => Ok(HealthStatus::Healthy),
+            Err(e) => {
+                debug!(provider = self.name(), error = %e, "Health check failed");
+                Ok(HealthStatus::Unhealthy)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ProviderRegistry;
+    use crate::types::{Message, Usage};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(name: &str, endpoint: Option<&str>) -> ProviderConfig {
+        ProviderConfig {
+            provider: name.to_string(),
+            endpoint: endpoint.map(str::to_string),
+            api_key: None,
+            models: vec![],
+            settings: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_from_config_requires_endpoint() {
+        let err = OpenAICompatibleProvider::from_config(&config("my-vllm", None))
+            .err()
+            .unwrap();
+        assert!(matches!(err, ConnectorError::Config(_)));
+    }
+
+    #[test]
+    fn test_from_config_headers() {
+        let mut config = config("my-vllm", Some("http://localhost:8000/v1"));
+        config
+            .settings
+            .insert("headers".to_string(), json!({"X-Tenant": "research"}));
+        let provider = OpenAICompatibleProvider::from_config(&config).unwrap();
+        assert_eq!(provider.name(), "my-vllm");
+        assert_eq!(
+            provider.headers,
+            vec![("X-Tenant".to_string(), "research".to_string())]
+        );
+
+        config
+            .settings
+            .insert("headers".to_string(), json!({"X-Retries": 3}));
+        assert!(OpenAICompatibleProvider::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("X-Tenant", "research"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "cmpl-local",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": "llama3",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi there"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 4, "completion_tokens": 2, "total_tokens": 6}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAICompatibleProvider::new("ollama", format!("{}/v1", server.uri()))
+            .with_header("X-Tenant", "research");
+        let request = CompletionRequest::new("llama3", vec![Message::user("Hello")]);
+
+        let response = provider.complete(&request).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hi there"));
+        assert_eq!(response.usage, Usage::new(4, 2));
+        assert_eq!(response.metadata.unwrap().provider, "ollama");
+
+        // No key configured: no Authorization header was sent
+        let received = server.received_requests().await.unwrap();
+        assert!(!received[0].headers.contains_key("authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_prefers_configured_models() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer local-key"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"object": "list", "data": [{"id": "qwen2.5"}]})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider =
+            OpenAICompatibleProvider::new("lmstudio", server.uri()).with_api_key("local-key");
+        assert_eq!(provider.list_models().await.unwrap(), vec!["qwen2.5"]);
+
+        let provider = provider.with_models(vec!["pinned".to_string()]);
+        assert_eq!(provider.list_models().await.unwrap(), vec!["pinned"]);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_queries_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"object": "list", "data": [{"id": "llama3"}]})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Configured models do not stand in for a reachable server
+        let provider = OpenAICompatibleProvider::new("my-vllm", format!("{}/v1", server.uri()))
+            .with_models(vec!["llama3".to_string()]);
+        assert_eq!(
+            provider.health_check().await.unwrap(),
+            HealthStatus::Healthy
+        );
+
+        let provider = provider.with_endpoint(format!("{}/v2", server.uri()));
+        assert_eq!(
+            provider.health_check().await.unwrap(),
+            HealthStatus::Unhealthy
+        );
+    }
+
+    #[test]
+    fn test_register_under_custom_names() {
+        let mut adapter = ConfigAdapter::new();
+        adapter.register_provider_config(config("vllm-a", Some("http://a:8000/v1")));
+        adapter.register_provider_config(config("vllm-b", Some("http://b:8000/v1")));
+
+        let mut registry = ProviderRegistry::new();
+        for name in ["vllm-a", "vllm-b"] {
+            let provider = OpenAICompatibleProvider::from_adapter(&mut adapter, name).unwrap();
+            registry.register(Arc::new(provider));
+        }
+        assert_eq!(registry.names(), vec!["vllm-a", "vllm-b"]);
+    }
+}
@@ -0,0 +1,610 @@
+//! Provider selection.
+//!
+//! A [`ProviderSelector`] picks one of several providers for each request
+//! according to a [`LoadBalancingStrategy`]:
+//!
+//! - `RoundRobin` - smooth weighted round-robin: a provider of weight 3
+//!   gets three requests for every one of a provider of weight 1,
+//!   interleaved rather than in bursts
+//! - `Random` - random, in proportion to the weights
+//! - `LeastLatency` - lowest moving-average latency from a
+//!   [`LatencyTracker`]; each provider without samples is tried once, and
+//!   providers failing repeatedly come last
+//! - `CostOptimized` - lowest input plus output price per token from a
+//!   [`PricingTable`]; unpriced providers come last
+//! - `LeastOutstanding` - fewest requests in flight
+//! - `PowerOfTwoChoices` - fewer requests in flight of two providers picked
+//!   at random
+//!
+//! Requests are in flight while the returned [`Selection`] is alive.
+
+use super::health::HealthChecker;
+use super::latency::LatencyTracker;
+use crate::adapters::config::{LoadBalancingStrategy, RoutingPolicy};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Price of a provider's tokens, in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pricing {
+    /// Price of prompt tokens
+    pub input_per_million: f64,
+    /// Price of completion tokens
+    pub output_per_million: f64,
+}
+
+impl Pricing {
+    /// Create a price from USD per million prompt and completion tokens
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    /// Cost of a request in USD
+    pub fn cost(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (f64::from(prompt_tokens) * self.input_per_million
+            + f64::from(completion_tokens) * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Token prices per provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PricingTable {
+    prices: HashMap<String, Pricing>,
+}
+
+impl PricingTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a provider's price
+    pub fn with_price(mut self, provider: impl Into<String>, pricing: Pricing) -> Self {
+        self.insert(provider, pricing);
+        self
+    }
+
+    /// Set a provider's price
+    pub fn insert(&mut self, provider: impl Into<String>, pricing: Pricing) {
+        self.prices.insert(provider.into(), pricing);
+    }
+
+    /// A provider's price
+    pub fn get(&self, provider: &str) -> Option<Pricing> {
+        self.prices.get(provider).copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    provider: String,
+    weight: u32,
+}
+
+#[derive(Debug)]
+struct State {
+    /// Smooth round-robin current weights
+    current: Vec<i64>,
+    outstanding: Vec<usize>,
+    /// Whether `LeastLatency` has tried the provider
+    explored: Vec<bool>,
+    rng: StdRng,
+}
+
+/// Picks a provider per request according to a load balancing strategy
+#[derive(Debug)]
+pub struct ProviderSelector {
+    strategy: LoadBalancingStrategy,
+    candidates: Vec<Candidate>,
+    latency: Arc<LatencyTracker>,
+    failure_threshold: u32,
+    pricing: PricingTable,
+    health: Option<Arc<HealthChecker>>,
+    state: Mutex<State>,
+}
+
+impl ProviderSelector {
+    /// Create a selector without providers
+    pub fn new(strategy: LoadBalancingStrategy) -> Self {
+        Self {
+            strategy,
+            candidates: Vec::new(),
+            latency: Arc::new(LatencyTracker::new()),
+            failure_threshold: 3,
+            pricing: PricingTable::new(),
+            health: None,
+            state: Mutex::new(State {
+                current: Vec::new(),
+                outstanding: Vec::new(),
+                explored: Vec::new(),
+                rng: StdRng::from_entropy(),
+            }),
+        }
+    }
+
+    /// Create a selector over `providers` using a routing policy's strategy
+    pub fn from_policy<I, S>(policy: &RoutingPolicy, providers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        providers
+            .into_iter()
+            .fold(Self::new(policy.strategy), |selector, provider| {
+                selector.with_provider(provider)
+            })
+    }
+
+    /// Add a provider of weight 1
+    pub fn with_provider(self, provider: impl Into<String>) -> Self {
+        self.with_weighted_provider(provider, 1)
+    }
+
+    /// Add a provider with a weight (at least 1) for `RoundRobin` and
+    /// `Random`
+    pub fn with_weighted_provider(mut self, provider: impl Into<String>, weight: u32) -> Self {
+        self.candidates.push(Candidate {
+            provider: provider.into(),
+            weight: weight.max(1),
+        });
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        state.current.push(0);
+        state.outstanding.push(0);
+        state.explored.push(false);
+        self
+    }
+
+    /// Use a shared latency tracker for `LeastLatency`
+    pub fn with_latency_tracker(mut self, latency: Arc<LatencyTracker>) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set the consecutive failures (at least 1) after which `LeastLatency`
+    /// prefers every other provider; 3 by default
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Use a pricing table for `CostOptimized`
+    pub fn with_pricing(mut self, pricing: PricingTable) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Skip providers a health checker reports as unhealthy
+    pub fn with_health_checker(mut self, health: Arc<HealthChecker>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Seed the random choices, for reproducible selection
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        state.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Load balancing strategy
+    pub fn strategy(&self) -> LoadBalancingStrategy {
+        self.strategy
+    }
+
+    /// Providers in the order they were added
+    pub fn providers(&self) -> Vec<&str> {
+        self.candidates
+            .iter()
+            .map(|candidate| candidate.provider.as_str())
+            .collect()
+    }
+
+    /// Latency tracker used by `LeastLatency`
+    pub fn latency_tracker(&self) -> &Arc<LatencyTracker> {
+        &self.latency
+    }
+
+    /// Requests in flight to a provider
+    pub fn outstanding(&self, provider: &str) -> usize {
+        let state = self.lock();
+        self.candidates
+            .iter()
+            .position(|candidate| candidate.provider == provider)
+            .map_or(0, |index| state.outstanding[index])
+    }
+
+    /// Pick a provider, or `None` if none is available
+    pub fn select(&self) -> Option<Selection<'_>> {
+        self.select_where(|_| true)
+    }
+
+    /// Pick a provider among those for which `eligible` returns true, e.g.
+    /// to skip providers already tried
+    pub fn select_where(&self, eligible: impl Fn(&str) -> bool) -> Option<Selection<'_>> {
+        let eligible: Vec<usize> = (0..self.candidates.len())
+            .filter(|&index| {
+                let provider = &self.candidates[index].provider;
+                eligible(provider)
+                    && self
+                        .health
+                        .as_ref()
+                        .is_none_or(|h| h.is_available(provider))
+            })
+            .collect();
+        if eligible.is_empty() {
+            return None;
+        }
+
+        let mut state = self.lock();
+        let index = match self.strategy {
+            LoadBalancingStrategy::RoundRobin => self.round_robin(&mut state, &eligible),
+            LoadBalancingStrategy::Random => self.random(&mut state, &eligible),
+            LoadBalancingStrategy::LeastLatency => self.least_latency(&mut state, &eligible),
+            LoadBalancingStrategy::CostOptimized => self.cheapest(&eligible),
+            LoadBalancingStrategy::LeastOutstanding => *eligible
+                .iter()
+                .min_by_key(|&&index| state.outstanding[index])
+                .expect("eligible is not empty"),
+            LoadBalancingStrategy::PowerOfTwoChoices => {
+                let first = eligible[state.rng.gen_range(0..eligible.len())];
+                let second = match eligible.len() {
+                    1 => first,
+                    len => {
+                        // Uniform among the others
+                        let pick = state.rng.gen_range(0..len - 1);
+                        let pick = eligible.iter().filter(|&&index| index != first).nth(pick);
+                        *pick.expect("pick is in range")
+                    }
+                };
+                if state.outstanding[second] < state.outstanding[first] {
+                    second
+                } else {
+                    first
+                }
+            }
+        };
+        state.outstanding[index] += 1;
+
+        Some(Selection {
+            selector: self,
+            index,
+        })
+    }
+
+    /// Smooth weighted round-robin, as in nginx
+    fn round_robin(&self, state: &mut State, eligible: &[usize]) -> usize {
+        let mut total = 0;
+        let mut best = eligible[0];
+        for &index in eligible {
+            let weight = i64::from(self.candidates[index].weight);
+            state.current[index] += weight;
+            total += weight;
+            if state.current[index] > state.current[best] {
+                best = index;
+            }
+        }
+        state.current[best] -= total;
+        best
+    }
+
+    fn random(&self, state: &mut State, eligible: &[usize]) -> usize {
+        let total: u64 = eligible
+            .iter()
+            .map(|&index| u64::from(self.candidates[index].weight))
+            .sum();
+        let mut pick = state.rng.gen_range(0..total);
+        for &index in eligible {
+            let weight = u64::from(self.candidates[index].weight);
+            if pick < weight {
+                return index;
+            }
+            pick -= weight;
+        }
+        unreachable!("pick is below the total weight")
+    }
+
+    /// Fastest provider by moving average
+    ///
+    /// A provider without samples is tried once; after that, until it gets
+    /// one, it is estimated at the mean of the measured providers. Ties go
+    /// to the provider with fewer consecutive failures.
+    fn least_latency(&self, state: &mut State, eligible: &[usize]) -> usize {
+        let provider = |index: usize| self.candidates[index].provider.as_str();
+        let measured: Vec<Duration> = eligible
+            .iter()
+            .filter_map(|&index| self.latency.ewma(provider(index), None))
+            .collect();
+        let mean = match measured.len() {
+            0 => Duration::ZERO,
+            len => measured.iter().sum::<Duration>() / len as u32,
+        };
+
+        let index = *eligible
+            .iter()
+            .min_by_key(|&&index| {
+                let estimate = match self.latency.ewma(provider(index), None) {
+                    Some(latency) => latency,
+                    None if state.explored[index] => mean,
+                    None => Duration::ZERO,
+                };
+                let failures = self.latency.consecutive_failures(provider(index), None);
+                (failures >= self.failure_threshold, estimate, failures)
+            })
+            .expect("eligible is not empty");
+        state.explored[index] = true;
+        index
+    }
+
+    fn cheapest(&self, eligible: &[usize]) -> usize {
+        let price = |index: usize| {
+            self.pricing
+                .get(&self.candidates[index].provider)
+                .map_or(f64::INFINITY, |pricing| {
+                    pricing.input_per_million + pricing.output_per_million
+                })
+        };
+        *eligible
+            .iter()
+            .min_by(|&&a, &&b| price(a).total_cmp(&price(b)))
+            .expect("eligible is not empty")
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A selected provider, counted as in flight until dropped
+#[derive(Debug)]
+pub struct Selection<'a> {
+    selector: &'a ProviderSelector,
+    index: usize,
+}
+
+impl<'a> Selection<'a> {
+    /// Selected provider
+    pub fn provider(&self) -> &'a str {
+        &self.selector.candidates[self.index].provider
+    }
+}
+
+impl Drop for Selection<'_> {
+    fn drop(&mut self) {
+        let mut state = self.selector.lock();
+        state.outstanding[self.index] -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn three(strategy: LoadBalancingStrategy) -> ProviderSelector {
+        ProviderSelector::new(strategy)
+            .with_provider("openai")
+            .with_provider("anthropic")
+            .with_provider("google")
+            .with_seed(42)
+    }
+
+    fn picks(selector: &ProviderSelector, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| selector.select().unwrap().provider().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_round_robin() {
+        let selector = ProviderSelector::new(LoadBalancingStrategy::RoundRobin)
+            .with_weighted_provider("a", 5)
+            .with_weighted_provider("b", 1)
+            .with_weighted_provider("c", 1);
+        assert_eq!(picks(&selector, 7), vec!["a", "a", "b", "a", "c", "a", "a"]);
+
+        let selector = three(LoadBalancingStrategy::RoundRobin);
+        assert_eq!(
+            picks(&selector, 4),
+            vec!["openai", "anthropic", "google", "openai"]
+        );
+        let selection = selector.select_where(|provider| provider != "anthropic");
+        assert_eq!(selection.unwrap().provider(), "google");
+    }
+
+    #[test]
+    fn test_weighted_random() {
+        let selector = ProviderSelector::new(LoadBalancingStrategy::Random)
+            .with_weighted_provider("a", 3)
+            .with_weighted_provider("b", 1)
+            .with_seed(7);
+        let chosen = picks(&selector, 4000);
+        let a = chosen.iter().filter(|provider| *provider == "a").count();
+        assert!((2800..3200).contains(&a), "{} of 4000", a);
+
+        // Seeded selection is reproducible
+        let again = ProviderSelector::new(LoadBalancingStrategy::Random)
+            .with_weighted_provider("a", 3)
+            .with_weighted_provider("b", 1)
+            .with_seed(7);
+        assert_eq!(picks(&again, 4000), chosen);
+    }
+
+    #[test]
+    fn test_least_latency() {
+        let selector = three(LoadBalancingStrategy::LeastLatency);
+        let latency = selector.latency_tracker();
+        latency.record("openai", "gpt-4", Duration::from_millis(900));
+        latency.record("anthropic", "claude-3-opus", Duration::from_millis(400));
+
+        // Unmeasured providers first, then the fastest
+        assert_eq!(selector.select().unwrap().provider(), "google");
+        latency.record("google", "gemini-pro", Duration::from_millis(600));
+        assert_eq!(selector.select().unwrap().provider(), "anthropic");
+        let selection = selector.select_where(|provider| provider != "anthropic");
+        assert_eq!(selection.unwrap().provider(), "google");
+    }
+
+    #[test]
+    fn test_least_latency_avoids_failing_provider() {
+        let selector = ProviderSelector::new(LoadBalancingStrategy::LeastLatency)
+            .with_provider("broken")
+            .with_provider("openai");
+        let latency = selector.latency_tracker();
+
+        let mut chosen = Vec::new();
+        for _ in 0..20 {
+            let provider = selector.select().unwrap().provider().to_string();
+            if provider == "broken" {
+                latency.record_failure("broken", "gpt-4");
+            } else {
+                latency.record("openai", "gpt-4", Duration::from_millis(300));
+            }
+            chosen.push(provider);
+        }
+
+        // Explored once, then never chosen again
+        assert_eq!(chosen[..2], ["broken", "openai"]);
+        assert!(chosen[2..].iter().all(|provider| provider == "openai"));
+
+        // Repeated failures rank behind any latency
+        let selector = ProviderSelector::new(LoadBalancingStrategy::LeastLatency)
+            .with_provider("broken")
+            .with_provider("slow")
+            .with_failure_threshold(2);
+        let latency = selector.latency_tracker();
+        latency.record("broken", "gpt-4", Duration::from_millis(100));
+        latency.record("slow", "gpt-4", Duration::from_secs(5));
+        latency.record_failure("broken", "gpt-4");
+        assert_eq!(selector.select().unwrap().provider(), "broken");
+        latency.record_failure("broken", "gpt-4");
+        assert_eq!(selector.select().unwrap().provider(), "slow");
+    }
+
+    #[test]
+    fn test_cost_optimized() {
+        let selector = three(LoadBalancingStrategy::CostOptimized).with_pricing(
+            PricingTable::new()
+                .with_price("openai", Pricing::new(10.0, 30.0))
+                .with_price("anthropic", Pricing::new(3.0, 15.0)),
+        );
+        assert_eq!(selector.select().unwrap().provider(), "anthropic");
+        let selection = selector.select_where(|provider| provider != "anthropic");
+        assert_eq!(selection.unwrap().provider(), "openai");
+
+        assert_eq!(Pricing::new(3.0, 15.0).cost(1000, 100), 0.0045);
+    }
+
+    #[test]
+    fn test_least_outstanding() {
+        let selector = three(LoadBalancingStrategy::LeastOutstanding);
+        let first = selector.select().unwrap();
+        let second = selector.select().unwrap();
+        assert_eq!(
+            (first.provider(), second.provider()),
+            ("openai", "anthropic")
+        );
+        assert_eq!(selector.outstanding("openai"), 1);
+
+        drop(first);
+        assert_eq!(selector.outstanding("openai"), 0);
+        assert_eq!(selector.select().unwrap().provider(), "openai");
+    }
+
+    #[test]
+    fn test_power_of_two_choices() {
+        let selector = three(LoadBalancingStrategy::PowerOfTwoChoices);
+
+        // Holding selections skews load towards the least busy provider
+        let held: Vec<_> = (0..30).map(|_| selector.select().unwrap()).collect();
+        let counts: Vec<_> = ["openai", "anthropic", "google"]
+            .iter()
+            .map(|provider| selector.outstanding(provider))
+            .collect();
+        assert_eq!(counts.iter().sum::<usize>(), 30);
+        assert!(
+            counts.iter().all(|count| (9..=11).contains(count)),
+            "{:?}",
+            counts
+        );
+        drop(held);
+
+        // Never picks the busier of two providers
+        let selector = ProviderSelector::new(LoadBalancingStrategy::PowerOfTwoChoices)
+            .with_provider("a")
+            .with_provider("b")
+            .with_seed(1);
+        let _busy = selector.select_where(|provider| provider == "a").unwrap();
+        for _ in 0..10 {
+            assert_eq!(selector.select().unwrap().provider(), "b");
+        }
+    }
+
+    #[test]
+    fn test_thread_safety() {
+        let selector = Arc::new(three(LoadBalancingStrategy::RoundRobin));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let selector = selector.clone();
+                std::thread::spawn(move || picks(&selector, 300))
+            })
+            .collect();
+        let mut counts = HashMap::new();
+        for thread in threads {
+            for provider in thread.join().unwrap() {
+                *counts.entry(provider).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(counts.values().copied().collect::<Vec<_>>(), vec![400; 3]);
+        assert_eq!(selector.outstanding("openai"), 0);
+    }
+
+    #[test]
+    fn test_from_policy() {
+        let policy = RoutingPolicy {
+            strategy: LoadBalancingStrategy::LeastOutstanding,
+            ..Default::default()
+        };
+        let selector = ProviderSelector::from_policy(&policy, ["openai", "anthropic"]);
+        assert_eq!(selector.strategy(), LoadBalancingStrategy::LeastOutstanding);
+        assert_eq!(selector.providers(), vec!["openai", "anthropic"]);
+        assert!(ProviderSelector::new(LoadBalancingStrategy::Random)
+            .select()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_skips_unhealthy() {
+        use crate::providers::mock::{MockFailure, MockProvider};
+        use crate::providers::ProviderRegistry;
+        use crate::routing::{HealthCheckPolicy, Probe};
+
+        let mut registry = ProviderRegistry::new();
+        registry.register(Arc::new(
+            MockProvider::new("openai").fail_with(MockFailure::ServerError),
+        ));
+        let probe = Probe::Completion {
+            model: "gpt-4".to_string(),
+        };
+        let health = Arc::new(
+            HealthChecker::new(
+                registry,
+                HealthCheckPolicy::new().with_unhealthy_threshold(1),
+            )
+            .with_probe("openai", probe),
+        );
+        let selector = three(LoadBalancingStrategy::RoundRobin).with_health_checker(health.clone());
+        assert_eq!(selector.select().unwrap().provider(), "openai");
+
+        health.check_all().await;
+        assert_eq!(
+            picks(&selector, 3),
+            vec!["anthropic", "google", "anthropic"]
+        );
+    }
+}
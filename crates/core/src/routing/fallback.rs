@@ -0,0 +1,502 @@
+//! Fallback chains.
+//!
+//! A [`FallbackExecutor`] sends a request to its primary provider and, when
+//! that fails with a retryable error, re-issues it to each of the primary's
+//! fallbacks in order. Model names are translated for each fallback through
+//! a [`ModelMap`], since `gpt-4` means nothing to Anthropic.
+
+use super::health::HealthChecker;
+use crate::adapters::config::{ConfigAdapter, RoutingPolicy};
+use crate::adapters::telemetry::{SharedSpanAdapter, SpanKind};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::middleware::{Pipeline, RequestContext};
+use crate::providers::http::ProviderTelemetry;
+use crate::providers::ProviderRegistry;
+use crate::types::{CompletionRequest, CompletionResponse, ProviderMetadata};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Equivalent model names across providers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelMap {
+    /// (model, target provider) to the target provider's model
+    models: HashMap<(String, String), String>,
+}
+
+impl ModelMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `equivalent` when `model` is sent to `provider`
+    pub fn with_equivalent(
+        mut self,
+        model: impl Into<String>,
+        provider: impl Into<String>,
+        equivalent: impl Into<String>,
+    ) -> Self {
+        self.insert(model, provider, equivalent);
+        self
+    }
+
+    /// Use `equivalent` when `model` is sent to `provider`
+    pub fn insert(
+        &mut self,
+        model: impl Into<String>,
+        provider: impl Into<String>,
+        equivalent: impl Into<String>,
+    ) {
+        self.models
+            .insert((model.into(), provider.into()), equivalent.into());
+    }
+
+    /// Model to request from `provider` in place of `model`
+    ///
+    /// Models without an entry keep their name, for providers that serve
+    /// the same models (OpenAI and Azure, OpenAI-compatible servers, ...).
+    pub fn translate<'a>(&'a self, model: &'a str, provider: &str) -> &'a str {
+        self.models
+            .get(&(model.to_string(), provider.to_string()))
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+}
+
+/// Runs requests against a primary provider and its fallbacks
+///
+/// Each attempt runs through the executor's [`Pipeline`], so per-provider
+/// middleware such as retries or circuit breakers applies before falling
+/// back. With telemetry, the whole chain is one span: every fallback is
+/// recorded as a `fallback` event (`from`, `to`, `model`, `reason`), and the
+/// provider that answered as a `served_by` event (`provider`, `model`,
+/// `attempts`).
+///
+/// Responses are annotated the same way: `metadata.provider` names the
+/// provider that served the request, and `metadata.extra` holds the
+/// `requested_provider`, `requested_model` and number of `attempts`.
+pub struct FallbackExecutor {
+    registry: ProviderRegistry,
+    fallbacks: HashMap<String, Vec<String>>,
+    models: ModelMap,
+    pipeline: Pipeline,
+    telemetry: Option<SharedSpanAdapter>,
+    health: Option<Arc<HealthChecker>>,
+}
+
+impl FallbackExecutor {
+    /// Create an executor over the providers of a registry
+    pub fn new(registry: ProviderRegistry) -> Self {
+        Self {
+            registry,
+            fallbacks: HashMap::new(),
+            models: ModelMap::new(),
+            pipeline: Pipeline::new(),
+            telemetry: None,
+            health: None,
+        }
+    }
+
+    /// Create an executor using the `fallbacks` of each registered
+    /// provider's routing policy
+    pub fn from_config(config: &ConfigAdapter, registry: ProviderRegistry) -> Result<Self> {
+        let mut executor = Self::new(registry);
+        for provider in executor.registry.names() {
+            let policy = config.get_routing_policy(&provider)?;
+            executor = executor.with_policy(provider, &policy);
+        }
+        Ok(executor)
+    }
+
+    /// Set the fallbacks tried, in order, when `primary` fails
+    pub fn with_fallbacks<I, S>(mut self, primary: impl Into<String>, fallbacks: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let fallbacks = fallbacks.into_iter().map(Into::into).collect();
+        self.fallbacks.insert(primary.into(), fallbacks);
+        self
+    }
+
+    /// Use the fallbacks of a provider's routing policy
+    pub fn with_policy(self, primary: impl Into<String>, policy: &RoutingPolicy) -> Self {
+        self.with_fallbacks(primary, policy.fallbacks.iter().cloned())
+    }
+
+    /// Translate model names for fallbacks
+    pub fn with_model_map(mut self, models: ModelMap) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Run every attempt through a middleware pipeline
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Report one span per request, covering every attempt
+    pub fn with_telemetry(mut self, adapter: SharedSpanAdapter) -> Self {
+        self.telemetry = Some(adapter);
+        self
+    }
+
+    /// Skip providers a health checker reports as unhealthy
+    pub fn with_health_checker(mut self, health: Arc<HealthChecker>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Providers tried for a request to `primary`, in order
+    pub fn chain<'a>(&'a self, primary: &'a str) -> Vec<&'a str> {
+        let fallbacks = self.fallbacks.get(primary).into_iter().flatten();
+        std::iter::once(primary)
+            .chain(fallbacks.map(String::as_str).filter(|f| *f != primary))
+            .collect()
+    }
+
+    /// Send a request to `primary`, falling back on retryable failures
+    ///
+    /// With telemetry, the chain is reported as one span on the primary,
+    /// marked [`SpanKind::Routed`] so latency trackers ignore it.
+    pub async fn complete(
+        &self,
+        primary: &str,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse> {
+        let mut context = RequestContext::new(primary, request.clone());
+        let Some(adapter) = &self.telemetry else {
+            return self.execute(&mut context).await;
+        };
+
+        let telemetry = ProviderTelemetry::new(adapter.clone()).with_kind(SpanKind::Routed);
+        let raw = serde_json::to_value(request).unwrap_or_default();
+        let span_id = telemetry.start(primary, &request.model, &raw);
+        if let Some(span_id) = &span_id {
+            context = context.with_span(adapter.clone(), span_id);
+        }
+
+        let result = self.execute(&mut context).await;
+        match &result {
+            Ok(response) => {
+                let raw = serde_json::to_value(response).unwrap_or_default();
+                telemetry.succeed(span_id, &raw, &response.usage);
+            }
+            Err(_) => telemetry.fail(span_id),
+        }
+        result
+    }
+
+    /// Run the chain for `context.provider`, recording events on the
+    /// context's span
+    ///
+    /// Returns the first response, the first non-retryable error, or the
+    /// last provider's error once the chain is exhausted. Fallbacks that
+    /// are not registered, and providers reported unhealthy, are skipped.
+    /// `context.attempt` counts provider calls across the whole chain.
+    pub async fn execute(&self, context: &mut RequestContext) -> Result<CompletionResponse> {
+        let primary = context.provider.clone();
+        self.registry.resolve(&primary)?;
+
+        let mut previous: Option<(&str, ConnectorError)> = None;
+        let mut attempts = 0;
+        for name in self.chain(&primary) {
+            let Some(provider) = self.registry.get(name) else {
+                warn!(provider = name, "Skipping unregistered fallback provider");
+                continue;
+            };
+            if !self.health.as_ref().is_none_or(|h| h.is_available(name)) {
+                warn!(provider = name, "Skipping unhealthy provider");
+                continue;
+            }
+
+            let mut attempt = context.clone();
+            attempt.provider = name.to_string();
+            attempt.request.model = self
+                .models
+                .translate(&context.request.model, name)
+                .to_string();
+
+            if let Some((from, error)) = &previous {
+                info!(
+                    from = from,
+                    to = name,
+                    model = %attempt.request.model,
+                    code = error.code(),
+                    "Falling back to next provider"
+                );
+                context.record_event(
+                    "fallback",
+                    HashMap::from([
+                        ("from".to_string(), Value::from(*from)),
+                        ("to".to_string(), Value::from(name)),
+                        (
+                            "model".to_string(),
+                            Value::from(attempt.request.model.as_str()),
+                        ),
+                        ("reason".to_string(), Value::from(error.code())),
+                    ]),
+                );
+            }
+
+            attempts += 1;
+            let result = self.pipeline.execute(provider.as_ref(), &mut attempt).await;
+            context.attempt = attempt.attempt;
+            match result {
+                Ok(mut response) => {
+                    context.record_event(
+                        "served_by",
+                        HashMap::from([
+                            ("provider".to_string(), Value::from(name)),
+                            ("model".to_string(), Value::from(response.model.as_str())),
+                            ("attempts".to_string(), Value::from(attempts)),
+                        ]),
+                    );
+                    annotate(&mut response, name, &primary, &context.request, attempts);
+                    return Ok(response);
+                }
+                Err(e) if e.is_retryable() => previous = Some((name, e)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        match previous {
+            Some((_, error)) => Err(error),
+            None => Err(ConnectorError::ProviderUnavailable(ProviderError::new(
+                primary,
+                "no healthy provider in the fallback chain",
+            ))),
+        }
+    }
+}
+
+/// Record which provider served a response and what was asked for
+fn annotate(
+    response: &mut CompletionResponse,
+    provider: &str,
+    primary: &str,
+    request: &CompletionRequest,
+    attempts: u32,
+) {
+    let metadata = response.metadata.get_or_insert_with(|| ProviderMetadata {
+        model: response.model.clone(),
+        ..Default::default()
+    });
+    metadata.provider = provider.to_string();
+    metadata
+        .extra
+        .insert("requested_provider".to_string(), Value::from(primary));
+    metadata.extra.insert(
+        "requested_model".to_string(),
+        Value::from(request.model.as_str()),
+    );
+    metadata
+        .extra
+        .insert("attempts".to_string(), Value::from(attempts));
+}
+
+impl fmt::Debug for FallbackExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FallbackExecutor")
+            .field("registry", &self.registry)
+            .field("fallbacks", &self.fallbacks)
+            .field("models", &self.models)
+            .field("pipeline", &self.pipeline)
+            .field("health", &self.health)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use crate::providers::mock::{MockFailure, MockProvider};
+    use crate::routing::LatencyTracker;
+    use crate::types::Message;
+    use std::sync::{Arc, Mutex};
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("gpt-4", vec![Message::user("Hello")])
+    }
+
+    fn executor(providers: &[&Arc<MockProvider>]) -> FallbackExecutor {
+        let mut registry = ProviderRegistry::new();
+        for provider in providers {
+            registry.register((*provider).clone());
+        }
+        FallbackExecutor::new(registry)
+            .with_fallbacks("openai", ["azure", "anthropic"])
+            .with_model_map(ModelMap::new().with_equivalent("gpt-4", "anthropic", "claude-3-opus"))
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_in_order() {
+        let openai = Arc::new(MockProvider::new("openai").fail_with(MockFailure::ServerError));
+        let azure = Arc::new(MockProvider::new("azure").fail_with(MockFailure::RateLimited));
+        let anthropic = Arc::new(MockProvider::new("anthropic").respond_with_text("Hi"));
+        let executor = executor(&[&openai, &azure, &anthropic]);
+
+        let response = executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Hi"));
+        assert_eq!(azure.requests()[0].model, "gpt-4");
+        assert_eq!(anthropic.requests()[0].model, "claude-3-opus");
+
+        let metadata = response.metadata.unwrap();
+        assert_eq!(metadata.provider, "anthropic");
+        assert_eq!(metadata.extra["requested_provider"], Value::from("openai"));
+        assert_eq!(metadata.extra["requested_model"], Value::from("gpt-4"));
+        assert_eq!(metadata.extra["attempts"], Value::from(3));
+
+        // The primary answers when it can
+        let response = executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(response.metadata.unwrap().provider, "openai");
+        assert_eq!(anthropic.call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stops_on_non_retryable_error() {
+        let openai = Arc::new(MockProvider::new("openai").fail_with(MockFailure::Status {
+            status: 401,
+            body: r#"{"message": "invalid api key"}"#.to_string(),
+        }));
+        let azure = Arc::new(MockProvider::new("azure"));
+        let executor = executor(&[&openai, &azure]);
+
+        let error = executor.complete("openai", &request()).await.unwrap_err();
+        assert_eq!(error.code(), "authentication");
+        assert_eq!(azure.call_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_chain() {
+        let openai = Arc::new(MockProvider::new("openai").fail_with(MockFailure::ServerError));
+        let azure = Arc::new(MockProvider::new("azure").fail_with(MockFailure::Timeout));
+        let executor = executor(&[&openai, &azure]);
+
+        // "anthropic" is not registered and is skipped
+        assert_eq!(
+            executor.chain("openai"),
+            vec!["openai", "azure", "anthropic"]
+        );
+        let error = executor.complete("openai", &request()).await.unwrap_err();
+        assert_eq!(error.code(), "timeout");
+        assert_eq!(error.provider(), Some("azure"));
+
+        let error = executor.complete("cohere", &request()).await.unwrap_err();
+        assert_eq!(error.code(), "config");
+    }
+
+    #[tokio::test]
+    async fn test_from_config() {
+        let mut config = ConfigAdapter::new();
+        config.set_routing_policy(
+            "openai",
+            RoutingPolicy {
+                fallbacks: vec!["anthropic".to_string(), "openai".to_string()],
+                ..Default::default()
+            },
+        );
+        let mut registry = ProviderRegistry::new();
+        registry.register(Arc::new(MockProvider::new("openai")));
+        registry.register(Arc::new(MockProvider::new("anthropic")));
+
+        let executor = FallbackExecutor::from_config(&config, registry).unwrap();
+        assert_eq!(executor.chain("openai"), vec!["openai", "anthropic"]);
+        assert_eq!(executor.chain("anthropic"), vec!["anthropic"]);
+    }
+
+    #[tokio::test]
+    async fn test_records_span_events() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let openai = Arc::new(MockProvider::new("openai").fail_with(MockFailure::ServerError));
+        let anthropic = Arc::new(MockProvider::new("anthropic"));
+        let executor = executor(&[&openai, &anthropic]);
+
+        let span_id = adapter
+            .lock()
+            .unwrap()
+            .start_provider_span("openai", "gpt-4", None);
+        let mut context =
+            RequestContext::new("openai", request()).with_span(adapter.clone(), span_id.clone());
+        executor.execute(&mut context).await.unwrap();
+        assert_eq!(context.attempt, 2);
+
+        let adapter = adapter.lock().unwrap();
+        let events = adapter.span_events(&span_id).unwrap();
+        let names: Vec<_> = events.iter().map(|event| event.name.as_str()).collect();
+        assert_eq!(names, vec!["fallback", "served_by"]);
+        assert_eq!(events[0].attributes["from"], Value::from("openai"));
+        assert_eq!(events[0].attributes["to"], Value::from("anthropic"));
+        assert_eq!(events[0].attributes["model"], Value::from("claude-3-opus"));
+        assert_eq!(
+            events[0].attributes["reason"],
+            Value::from("provider_unavailable")
+        );
+        assert_eq!(events[1].attributes["provider"], Value::from("anthropic"));
+        assert_eq!(events[1].attributes["attempts"], Value::from(2));
+    }
+
+    #[tokio::test]
+    async fn test_chain_span() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let openai = Arc::new(MockProvider::new("openai").fail_with(MockFailure::ServerError));
+        let anthropic = Arc::new(MockProvider::new("anthropic"));
+        let executor = executor(&[&openai, &anthropic]).with_telemetry(adapter.clone());
+
+        let tracker = Arc::new(LatencyTracker::new());
+        let observer = tracker.clone();
+        adapter
+            .lock()
+            .unwrap()
+            .add_listener(move |span| observer.observe_span(span));
+
+        executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(adapter.lock().unwrap().active_span_count(), 0);
+        // The chain span is not credited to the primary
+        assert_eq!(tracker.sample_count("openai", None), 0);
+    }
+
+    #[tokio::test]
+    async fn test_skips_unhealthy_providers() {
+        use crate::routing::{HealthCheckPolicy, HealthChecker, Probe};
+
+        let openai = Arc::new(MockProvider::new("openai"));
+        let anthropic = Arc::new(MockProvider::new("anthropic"));
+        let mut registry = ProviderRegistry::new();
+        registry.register(Arc::new(
+            MockProvider::new("openai").fail_with(MockFailure::ServerError),
+        ));
+        let health = Arc::new(
+            HealthChecker::new(
+                registry,
+                HealthCheckPolicy::new().with_unhealthy_threshold(1),
+            )
+            .with_probe(
+                "openai",
+                Probe::Completion {
+                    model: "gpt-4".to_string(),
+                },
+            ),
+        );
+        health.check_all().await;
+
+        let routed = executor(&[&openai, &anthropic]).with_health_checker(health.clone());
+        let response = routed.complete("openai", &request()).await.unwrap();
+        assert_eq!(response.metadata.unwrap().provider, "anthropic");
+        assert_eq!(openai.call_count(), 0);
+
+        // With every provider in the chain unhealthy, nothing is called
+        let error = executor(&[&openai])
+            .with_health_checker(health)
+            .complete("openai", &request())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), "provider_unavailable");
+        assert_eq!(openai.call_count(), 0);
+    }
+}
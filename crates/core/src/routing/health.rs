@@ -0,0 +1,641 @@
+//! Active health checking.
+//!
+//! A [`HealthChecker`] probes every provider of a registry, on demand with
+//! [`HealthChecker::check_all`] or periodically from a background task
+//! started with [`HealthChecker::spawn`]. Each probe yields `Healthy`,
+//! `Degraded` (the provider says so, or the probe was slow) or `Unhealthy`
+//! (the probe failed or timed out).
+//!
+//! A provider's [`HealthStatus`] changes only after several consecutive
+//! probes agree, so a single failed probe does not take a provider out of
+//! rotation and a single success does not put it back. Providers start as
+//! `Healthy`.
+//!
+//! Routing consults the checker through
+//! [`FallbackExecutor::with_health_checker`] and
+//! [`ProviderSelector::with_health_checker`], which skip unhealthy
+//! providers.
+//!
+//! [`FallbackExecutor::with_health_checker`]: super::FallbackExecutor::with_health_checker
+//! [`ProviderSelector::with_health_checker`]: super::ProviderSelector::with_health_checker
+
+use crate::adapters::telemetry::{SharedSpanAdapter, SpanKind};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::providers::{HealthStatus, Provider, ProviderRegistry};
+use crate::types::{CompletionRequest, Message};
+use futures::future::join_all;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{debug, info, warn};
+
+/// How a provider is probed
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Probe {
+    /// The provider's own `health_check` (a models list call by default)
+    #[default]
+    HealthCheck,
+    /// A one-token completion with the given model
+    Completion {
+        /// Model to complete with
+        model: String,
+    },
+}
+
+/// Probe schedule and state change thresholds
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheckPolicy {
+    /// Time between probe rounds
+    pub interval: Duration,
+    /// Probes taking longer fail
+    pub timeout: Duration,
+    /// Probes taking at least this long report `Degraded`
+    pub degraded_latency: Option<Duration>,
+    /// Consecutive failed probes before a provider is `Unhealthy`
+    pub unhealthy_threshold: u32,
+    /// Consecutive agreeing probes before a provider becomes `Healthy` or
+    /// `Degraded`
+    pub healthy_threshold: u32,
+}
+
+impl Default for HealthCheckPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            degraded_latency: None,
+            unhealthy_threshold: 3,
+            healthy_threshold: 2,
+        }
+    }
+}
+
+impl HealthCheckPolicy {
+    /// Create the default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time between probe rounds
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the probe timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Report providers as `Degraded` when probes take this long
+    pub fn with_degraded_latency(mut self, latency: Duration) -> Self {
+        self.degraded_latency = Some(latency);
+        self
+    }
+
+    /// Set the consecutive failures that make a provider `Unhealthy`
+    pub fn with_unhealthy_threshold(mut self, threshold: u32) -> Self {
+        self.unhealthy_threshold = threshold.max(1);
+        self
+    }
+
+    /// Set the consecutive agreeing probes that make a provider `Healthy`
+    /// or `Degraded`
+    pub fn with_healthy_threshold(mut self, threshold: u32) -> Self {
+        self.healthy_threshold = threshold.max(1);
+        self
+    }
+}
+
+/// A change of provider health
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthTransition {
+    /// Provider name
+    pub provider: String,
+    /// Status before the change
+    pub from: HealthStatus,
+    /// Status after the change
+    pub to: HealthStatus,
+    /// Error of the probe that caused the change, if it failed
+    pub error: Option<String>,
+}
+
+/// Point-in-time health of a provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderHealth {
+    /// Provider name
+    pub provider: String,
+    /// Current status
+    pub status: HealthStatus,
+    /// Probes run so far
+    pub checks: u64,
+    /// Failed probes since the last successful one
+    pub consecutive_failures: u32,
+    /// Duration of the last probe
+    pub last_latency: Option<Duration>,
+    /// Error of the last probe, if it failed
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Tracked {
+    status: HealthStatus,
+    /// Probe outcome differing from `status`, and how many times in a row
+    /// it was seen
+    pending: Option<(HealthStatus, u32)>,
+    checks: u64,
+    consecutive_failures: u32,
+    last_latency: Option<Duration>,
+    last_error: Option<String>,
+}
+
+impl Default for Tracked {
+    fn default() -> Self {
+        Self {
+            status: HealthStatus::Healthy,
+            pending: None,
+            checks: 0,
+            consecutive_failures: 0,
+            last_latency: None,
+            last_error: None,
+        }
+    }
+}
+
+/// Probes providers and tracks their health
+pub struct HealthChecker {
+    registry: ProviderRegistry,
+    policy: HealthCheckPolicy,
+    probes: HashMap<String, Probe>,
+    telemetry: Option<SharedSpanAdapter>,
+    state: Mutex<HashMap<String, Tracked>>,
+}
+
+impl HealthChecker {
+    /// Create a checker for every provider of a registry
+    pub fn new(registry: ProviderRegistry, policy: HealthCheckPolicy) -> Self {
+        Self {
+            registry,
+            policy,
+            probes: HashMap::new(),
+            telemetry: None,
+            state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Probe a provider differently than with its `health_check`
+    pub fn with_probe(mut self, provider: impl Into<String>, probe: Probe) -> Self {
+        self.probes.insert(provider.into(), probe);
+        self
+    }
+
+    /// Report every probe as a [`SpanKind::Probe`] span, with state changes
+    /// as `health_state_change` events (`provider`, `from`, `to`, `error`)
+    pub fn with_telemetry(mut self, adapter: SharedSpanAdapter) -> Self {
+        self.telemetry = Some(adapter);
+        self
+    }
+
+    /// Health check policy
+    pub fn policy(&self) -> &HealthCheckPolicy {
+        &self.policy
+    }
+
+    /// Current status of a provider; `Healthy` until probed otherwise
+    pub fn status(&self, provider: &str) -> HealthStatus {
+        self.lock()
+            .get(provider)
+            .map_or(HealthStatus::Healthy, |tracked| tracked.status)
+    }
+
+    /// Whether routing may send requests to a provider
+    pub fn is_available(&self, provider: &str) -> bool {
+        self.status(provider) != HealthStatus::Unhealthy
+    }
+
+    /// Health of every registered provider, sorted by name
+    pub fn statuses(&self) -> Vec<ProviderHealth> {
+        let state = self.lock();
+        self.registry
+            .names()
+            .into_iter()
+            .map(|provider| {
+                let tracked = state.get(&provider);
+                ProviderHealth {
+                    status: tracked.map_or(HealthStatus::Healthy, |t| t.status),
+                    checks: tracked.map_or(0, |t| t.checks),
+                    consecutive_failures: tracked.map_or(0, |t| t.consecutive_failures),
+                    last_latency: tracked.and_then(|t| t.last_latency),
+                    last_error: tracked.and_then(|t| t.last_error.clone()),
+                    provider,
+                }
+            })
+            .collect()
+    }
+
+    /// Probe every registered provider concurrently, returning the state
+    /// changes
+    pub async fn check_all(&self) -> Vec<HealthTransition> {
+        let names = self.registry.names();
+        let checks = names.iter().map(|provider| self.check(provider));
+        join_all(checks)
+            .await
+            .into_iter()
+            .filter_map(|result| result.ok().flatten())
+            .collect()
+    }
+
+    /// Probe one provider, returning the state change it caused, if any
+    pub async fn check(&self, provider: &str) -> Result<Option<HealthTransition>> {
+        let instance = self.registry.resolve(provider)?;
+        let span_id = self.start_span(provider);
+
+        let started = Instant::now();
+        let outcome =
+            match tokio::time::timeout(self.policy.timeout, self.probe(provider, &*instance)).await
+            {
+                Ok(outcome) => outcome,
+                Err(_) => Err(ConnectorError::Timeout(ProviderError::new(
+                    provider,
+                    format!(
+                        "health probe timed out after {}ms",
+                        self.policy.timeout.as_millis()
+                    ),
+                ))),
+            };
+        let latency = started.elapsed();
+
+        let (status, error) = match outcome {
+            Ok(HealthStatus::Healthy)
+                if self
+                    .policy
+                    .degraded_latency
+                    .is_some_and(|threshold| latency >= threshold) =>
+            {
+                (HealthStatus::Degraded, None)
+            }
+            Ok(status) => (status, None),
+            Err(e) => (HealthStatus::Unhealthy, Some(e.to_string())),
+        };
+        debug!(
+            provider = provider,
+            status = ?status,
+            latency_ms = latency.as_millis() as u64,
+            "Health probe finished"
+        );
+
+        let transition = self.update(provider, status, latency, error);
+        if let Some(transition) = &transition {
+            log(transition);
+        }
+        self.finish_span(span_id, status, transition.as_ref());
+        Ok(transition)
+    }
+
+    /// Probe every provider every `interval` until the task is aborted
+    ///
+    /// The first round runs immediately.
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let checker = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(checker.policy.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                checker.check_all().await;
+            }
+        })
+    }
+
+    async fn probe(&self, provider: &str, instance: &dyn Provider) -> Result<HealthStatus> {
+        match self.probes.get(provider).unwrap_or(&Probe::HealthCheck) {
+            Probe::HealthCheck => instance.health_check().await,
+            Probe::Completion { model } => {
+                let request = CompletionRequest::builder(model.as_str())
+                    .message(Message::user("ping"))
+                    .max_tokens(1)
+                    .build()?;
+                instance.complete(&request).await?;
+                Ok(HealthStatus::Healthy)
+            }
+        }
+    }
+
+    /// Apply a probe outcome with hysteresis
+    fn update(
+        &self,
+        provider: &str,
+        status: HealthStatus,
+        latency: Duration,
+        error: Option<String>,
+    ) -> Option<HealthTransition> {
+        let mut state = self.lock();
+        let tracked = state.entry(provider.to_string()).or_default();
+        tracked.checks += 1;
+        tracked.last_latency = Some(latency);
+        if status == HealthStatus::Unhealthy {
+            tracked.consecutive_failures += 1;
+        } else {
+            tracked.consecutive_failures = 0;
+        }
+        tracked.last_error = error.clone();
+
+        if status == tracked.status {
+            tracked.pending = None;
+            return None;
+        }
+        let seen = match tracked.pending {
+            Some((pending, seen)) if pending == status => seen + 1,
+            _ => 1,
+        };
+        let threshold = match status {
+            HealthStatus::Unhealthy => self.policy.unhealthy_threshold,
+            _ => self.policy.healthy_threshold,
+        };
+        if seen < threshold {
+            tracked.pending = Some((status, seen));
+            return None;
+        }
+
+        let from = tracked.status;
+        tracked.status = status;
+        tracked.pending = None;
+        Some(HealthTransition {
+            provider: provider.to_string(),
+            from,
+            to: status,
+            error,
+        })
+    }
+
+    fn start_span(&self, provider: &str) -> Option<String> {
+        let mut adapter = self.telemetry.as_ref()?.lock().ok()?;
+        let span_id = adapter.start_provider_span(provider, "health_check", None);
+        if span_id.is_empty() {
+            return None;
+        }
+        if let Err(e) = adapter.set_span_kind(&span_id, SpanKind::Probe) {
+            warn!(span_id = %span_id, error = %e, "Failed to set span kind");
+        }
+        Some(span_id)
+    }
+
+    fn finish_span(
+        &self,
+        span_id: Option<String>,
+        status: HealthStatus,
+        transition: Option<&HealthTransition>,
+    ) {
+        let (Some(adapter), Some(span_id)) = (&self.telemetry, span_id) else {
+            return;
+        };
+        let Ok(mut adapter) = adapter.lock() else {
+            return;
+        };
+        if let Some(transition) = transition {
+            let attributes = HashMap::from([
+                (
+                    "provider".to_string(),
+                    Value::from(transition.provider.as_str()),
+                ),
+                (
+                    "from".to_string(),
+                    Value::from(status_name(transition.from)),
+                ),
+                ("to".to_string(), Value::from(status_name(transition.to))),
+                ("error".to_string(), Value::from(transition.error.clone())),
+            ]);
+            if let Err(e) = adapter.record_event(&span_id, "health_state_change", attributes) {
+                warn!(span_id = %span_id, error = %e, "Failed to record span event");
+            }
+        }
+        if let Err(e) = adapter.finish_span(&span_id, status != HealthStatus::Unhealthy) {
+            warn!(span_id = %span_id, error = %e, "Failed to finish health check span");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Tracked>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for HealthChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthChecker")
+            .field("registry", &self.registry)
+            .field("policy", &self.policy)
+            .field("probes", &self.probes)
+            .finish_non_exhaustive()
+    }
+}
+
+fn status_name(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+    }
+}
+
+fn log(transition: &HealthTransition) {
+    match transition.to {
+        HealthStatus::Unhealthy => warn!(
+            provider = %transition.provider,
+            from = status_name(transition.from),
+            error = transition.error.as_deref().unwrap_or_default(),
+            "Provider is unhealthy"
+        ),
+        to => info!(
+            provider = %transition.provider,
+            from = status_name(transition.from),
+            to = status_name(to),
+            "Provider health changed"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use crate::providers::mock::{MockFailure, MockProvider};
+    use crate::providers::openai_compatible::OpenAICompatibleProvider;
+    use crate::routing::LatencyTracker;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn registry(providers: Vec<Arc<dyn Provider>>) -> ProviderRegistry {
+        let mut registry = ProviderRegistry::new();
+        for provider in providers {
+            registry.register(provider);
+        }
+        registry
+    }
+
+    async fn respond(server: &MockServer, template: ResponseTemplate) {
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(template)
+            .mount(server)
+            .await;
+    }
+
+    fn models() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({"object": "list", "data": [{"id": "m"}]}))
+    }
+
+    #[tokio::test]
+    async fn test_probes_mock_server() {
+        let server = MockServer::start().await;
+        let provider = OpenAICompatibleProvider::new("local", server.uri());
+        let checker = HealthChecker::new(
+            registry(vec![Arc::new(provider)]),
+            HealthCheckPolicy::new()
+                .with_unhealthy_threshold(2)
+                .with_healthy_threshold(2),
+        );
+        respond(&server, models()).await;
+        assert!(checker.check_all().await.is_empty());
+        assert_eq!(checker.status("local"), HealthStatus::Healthy);
+
+        // One failure is not enough to take the provider down
+        respond(&server, ResponseTemplate::new(503)).await;
+        assert!(checker.check_all().await.is_empty());
+        assert!(checker.is_available("local"));
+        let transitions = checker.check_all().await;
+        assert_eq!(
+            transitions,
+            vec![HealthTransition {
+                provider: "local".to_string(),
+                from: HealthStatus::Healthy,
+                to: HealthStatus::Unhealthy,
+                error: None,
+            }]
+        );
+        assert!(!checker.is_available("local"));
+
+        // Nor is one success enough to bring it back
+        respond(&server, models()).await;
+        assert!(checker.check_all().await.is_empty());
+        assert_eq!(checker.status("local"), HealthStatus::Unhealthy);
+        assert_eq!(checker.check_all().await[0].to, HealthStatus::Healthy);
+
+        let health = &checker.statuses()[0];
+        assert_eq!(health.checks, 5);
+        assert_eq!(health.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_and_timeout() {
+        let server = MockServer::start().await;
+        let provider = OpenAICompatibleProvider::new("local", server.uri());
+        let checker = HealthChecker::new(
+            registry(vec![Arc::new(provider)]),
+            HealthCheckPolicy::new()
+                .with_timeout(Duration::from_millis(500))
+                .with_degraded_latency(Duration::from_millis(100))
+                .with_unhealthy_threshold(1)
+                .with_healthy_threshold(1),
+        );
+
+        respond(&server, models().set_delay(Duration::from_millis(150))).await;
+        assert_eq!(checker.check_all().await[0].to, HealthStatus::Degraded);
+
+        respond(&server, models().set_delay(Duration::from_secs(2))).await;
+        let transition = checker.check("local").await.unwrap().unwrap();
+        assert_eq!(transition.to, HealthStatus::Unhealthy);
+        assert!(transition.error.unwrap().contains("timed out after 500ms"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_background_completion_probes() {
+        let provider = Arc::new(
+            MockProvider::new("openai")
+                .fail_with(MockFailure::ServerError)
+                .fail_with(MockFailure::Timeout),
+        );
+        let checker = Arc::new(
+            HealthChecker::new(
+                registry(vec![provider.clone()]),
+                HealthCheckPolicy::new()
+                    .with_interval(Duration::from_secs(10))
+                    .with_unhealthy_threshold(2)
+                    .with_healthy_threshold(1),
+            )
+            .with_probe(
+                "openai",
+                Probe::Completion {
+                    model: "gpt-4".to_string(),
+                },
+            ),
+        );
+
+        let task = checker.spawn();
+        tokio::time::sleep(Duration::from_secs(15)).await;
+        assert_eq!(provider.call_count(), 2);
+        assert_eq!(checker.status("openai"), HealthStatus::Unhealthy);
+        assert_eq!(provider.requests()[0].max_tokens, Some(1));
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(checker.status("openai"), HealthStatus::Healthy);
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_reports_transitions() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let sink = finished.clone();
+        adapter
+            .lock()
+            .unwrap()
+            .add_listener(move |span| sink.lock().unwrap().push((span.success, span.kind)));
+
+        let provider = MockProvider::new("openai").fail_with(MockFailure::RateLimited);
+        let checker = HealthChecker::new(
+            registry(vec![Arc::new(provider)]),
+            HealthCheckPolicy::new().with_unhealthy_threshold(1),
+        )
+        .with_probe(
+            "openai",
+            Probe::Completion {
+                model: "gpt-4".to_string(),
+            },
+        )
+        .with_telemetry(adapter.clone());
+
+        let transition = checker.check("openai").await.unwrap().unwrap();
+        assert!(transition.error.unwrap().contains("Rate limited"));
+        assert_eq!(*finished.lock().unwrap(), vec![(false, SpanKind::Probe)]);
+        assert_eq!(adapter.lock().unwrap().active_span_count(), 0);
+        assert_eq!(checker.check("cohere").await.unwrap_err().code(), "config");
+    }
+
+    #[tokio::test]
+    async fn test_probes_are_not_tracked_as_latency() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let tracker = Arc::new(LatencyTracker::new());
+        let observer = tracker.clone();
+        adapter
+            .lock()
+            .unwrap()
+            .add_listener(move |span| observer.observe_span(span));
+
+        let checker = HealthChecker::new(
+            registry(vec![Arc::new(MockProvider::new("openai"))]),
+            HealthCheckPolicy::new(),
+        )
+        .with_telemetry(adapter.clone());
+
+        checker.check("openai").await.unwrap();
+        assert_eq!(checker.statuses()[0].checks, 1);
+        assert_eq!(tracker.sample_count("openai", None), 0);
+    }
+}
@@ -0,0 +1,567 @@
+//! Hedged requests.
+//!
+//! A [`HedgedExecutor`] sends a request to its primary provider and, if no
+//! answer has arrived after a delay, sends a duplicate to a hedge provider
+//! (by default the primary again). The first successful response wins and
+//! the other request is cancelled by dropping it.
+//!
+//! The delay is either fixed or a percentile of the primary's observed
+//! latency for the model, taken from the same [`LatencyTracker`] that
+//! drives `LeastLatency` selection: hedging at the p95 duplicates about
+//! one request in twenty.
+
+use super::fallback::ModelMap;
+use super::latency::LatencyTracker;
+use crate::adapters::telemetry::{SharedSpanAdapter, SpanKind};
+use crate::error::Result;
+use crate::middleware::{Pipeline, RequestContext};
+use crate::providers::http::ProviderTelemetry;
+use crate::providers::ProviderRegistry;
+use crate::types::{CompletionRequest, CompletionResponse, ProviderMetadata};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// When to send the duplicate request
+#[derive(Debug, Clone, PartialEq)]
+pub struct HedgePolicy {
+    /// Delay before hedging, when no percentile applies
+    pub delay: Duration,
+    /// Hedge at this percentile (0.0 to 1.0) of observed latency instead
+    pub quantile: Option<f64>,
+    /// Samples needed before the percentile is trusted
+    pub min_samples: usize,
+}
+
+impl HedgePolicy {
+    /// Hedge after a fixed delay
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            quantile: None,
+            min_samples: 20,
+        }
+    }
+
+    /// Hedge at a percentile of observed latency, e.g. 0.95 for the p95
+    pub fn with_quantile(mut self, quantile: f64) -> Self {
+        self.quantile = Some(quantile);
+        self
+    }
+
+    /// Set the samples needed before the percentile is used
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+}
+
+/// Hedging counters, for tuning cost against latency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HedgeStats {
+    /// Requests executed
+    pub requests: u64,
+    /// Requests for which a hedge was sent
+    pub hedged: u64,
+    /// Hedged requests served by the hedge
+    pub wins: u64,
+    /// Hedged requests served by the primary
+    pub losses: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    hedged: AtomicU64,
+    wins: AtomicU64,
+    losses: AtomicU64,
+}
+
+/// Sends a duplicate request when the primary is slow
+///
+/// Each request runs through the executor's [`Pipeline`]. With telemetry,
+/// both requests share one span: sending the hedge is recorded as a
+/// `hedge_sent` event (`primary`, `hedge`, `model`, `delay_ms`), and its
+/// outcome as a `hedge_win` or `hedge_loss` event (`primary`, `hedge`,
+/// `served_by`, `elapsed_ms`).
+///
+/// Responses to hedged requests have `"hedged": true` in
+/// `metadata.extra`.
+pub struct HedgedExecutor {
+    registry: ProviderRegistry,
+    policy: HedgePolicy,
+    hedges: HashMap<String, String>,
+    models: ModelMap,
+    latency: Arc<LatencyTracker>,
+    pipeline: Pipeline,
+    telemetry: Option<SharedSpanAdapter>,
+    counters: Counters,
+}
+
+impl HedgedExecutor {
+    /// Create an executor over the providers of a registry
+    pub fn new(registry: ProviderRegistry, policy: HedgePolicy) -> Self {
+        Self {
+            registry,
+            policy,
+            hedges: HashMap::new(),
+            models: ModelMap::new(),
+            latency: Arc::new(LatencyTracker::new()),
+            pipeline: Pipeline::new(),
+            telemetry: None,
+            counters: Counters::default(),
+        }
+    }
+
+    /// Hedge requests to `primary` with `hedge` instead of the primary itself
+    pub fn with_hedge_provider(
+        mut self,
+        primary: impl Into<String>,
+        hedge: impl Into<String>,
+    ) -> Self {
+        self.hedges.insert(primary.into(), hedge.into());
+        self
+    }
+
+    /// Translate model names for hedge providers
+    pub fn with_model_map(mut self, models: ModelMap) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Use a shared latency tracker for percentile delays
+    pub fn with_latency_tracker(mut self, latency: Arc<LatencyTracker>) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Run both requests through a middleware pipeline
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Report one span per request, covering the hedge
+    pub fn with_telemetry(mut self, adapter: SharedSpanAdapter) -> Self {
+        self.telemetry = Some(adapter);
+        self
+    }
+
+    /// Hedge policy
+    pub fn policy(&self) -> &HedgePolicy {
+        &self.policy
+    }
+
+    /// Counters since the executor was created
+    pub fn stats(&self) -> HedgeStats {
+        HedgeStats {
+            requests: self.counters.requests.load(Ordering::Relaxed),
+            hedged: self.counters.hedged.load(Ordering::Relaxed),
+            wins: self.counters.wins.load(Ordering::Relaxed),
+            losses: self.counters.losses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Provider that hedges requests to `primary`
+    pub fn hedge_provider<'a>(&'a self, primary: &'a str) -> &'a str {
+        self.hedges.get(primary).map_or(primary, String::as_str)
+    }
+
+    /// Delay before hedging a request for `model` to `primary`
+    pub fn delay(&self, primary: &str, model: &str) -> Duration {
+        let Some(quantile) = self.policy.quantile else {
+            return self.policy.delay;
+        };
+        if self.latency.sample_count(primary, Some(model)) < self.policy.min_samples {
+            return self.policy.delay;
+        }
+        self.latency
+            .percentile(primary, Some(model), quantile)
+            .unwrap_or(self.policy.delay)
+    }
+
+    /// Send a request to `primary`, hedging if it is slow
+    ///
+    /// With telemetry, the request is reported as one span on the primary,
+    /// marked [`SpanKind::Routed`] so latency trackers ignore it.
+    pub async fn complete(
+        &self,
+        primary: &str,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse> {
+        let mut context = RequestContext::new(primary, request.clone());
+        let Some(adapter) = &self.telemetry else {
+            return self.execute(&mut context).await;
+        };
+
+        let telemetry = ProviderTelemetry::new(adapter.clone()).with_kind(SpanKind::Routed);
+        let raw = serde_json::to_value(request).unwrap_or_default();
+        let span_id = telemetry.start(primary, &request.model, &raw);
+        if let Some(span_id) = &span_id {
+            context = context.with_span(adapter.clone(), span_id);
+        }
+
+        let result = self.execute(&mut context).await;
+        match &result {
+            Ok(response) => {
+                let raw = serde_json::to_value(response).unwrap_or_default();
+                telemetry.succeed(span_id, &raw, &response.usage);
+            }
+            Err(_) => telemetry.fail(span_id),
+        }
+        result
+    }
+
+    /// Send `context.request` to `context.provider`, hedging if it is slow,
+    /// and record events on the context's span
+    ///
+    /// A primary that fails before the delay is not hedged. Once hedged,
+    /// the first successful response is returned; if both fail, the
+    /// primary's error is.
+    pub async fn execute(&self, context: &mut RequestContext) -> Result<CompletionResponse> {
+        let primary_name = context.provider.clone();
+        let primary = self.registry.resolve(&primary_name)?;
+        let hedge_name = self.hedge_provider(&primary_name).to_string();
+        let hedge = self.registry.resolve(&hedge_name)?;
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+
+        let delay = self.delay(&primary_name, &context.request.model);
+        let started = Instant::now();
+        let mut first = context.clone();
+        let mut second = context.clone();
+        second.provider = hedge_name.clone();
+        second.request.model = self
+            .models
+            .translate(&context.request.model, &hedge_name)
+            .to_string();
+
+        let (result, hedge_won) = {
+            let primary_call = self.pipeline.execute(primary.as_ref(), &mut first);
+            tokio::pin!(primary_call);
+            let unhedged = tokio::select! {
+                biased;
+                result = &mut primary_call => Some(result),
+                _ = tokio::time::sleep(delay) => None,
+            };
+
+            match unhedged {
+                Some(result) => (result, None),
+                None => {
+                    self.counters.hedged.fetch_add(1, Ordering::Relaxed);
+                    debug!(
+                        primary = %primary_name,
+                        hedge = %hedge_name,
+                        delay_ms = delay.as_millis() as u64,
+                        "Sending hedged request"
+                    );
+                    context.record_event(
+                        "hedge_sent",
+                        HashMap::from([
+                            ("primary".to_string(), Value::from(primary_name.as_str())),
+                            ("hedge".to_string(), Value::from(hedge_name.as_str())),
+                            (
+                                "model".to_string(),
+                                Value::from(second.request.model.as_str()),
+                            ),
+                            (
+                                "delay_ms".to_string(),
+                                Value::from(delay.as_millis() as u64),
+                            ),
+                        ]),
+                    );
+
+                    let hedge_call = self.pipeline.execute(hedge.as_ref(), &mut second);
+                    tokio::pin!(hedge_call);
+                    // The loser is cancelled when both futures drop at the
+                    // end of this block
+                    let (result, won) = tokio::select! {
+                        biased;
+                        result = &mut primary_call => match result {
+                            Ok(response) => (Ok(response), false),
+                            Err(e) => ((&mut hedge_call).await.map_err(|_| e), true),
+                        },
+                        result = &mut hedge_call => match result {
+                            Ok(response) => (Ok(response), true),
+                            Err(_) => ((&mut primary_call).await, false),
+                        },
+                    };
+                    (result, Some(won))
+                }
+            }
+        };
+        // Both contexts started from `context.attempt`
+        context.attempt = first.attempt + second.attempt - context.attempt;
+        let Some(hedge_won) = hedge_won else {
+            return result;
+        };
+
+        let served_by = if hedge_won {
+            &hedge_name
+        } else {
+            &primary_name
+        };
+        if let Ok(response) = &result {
+            let (event, counter) = if hedge_won {
+                ("hedge_win", &self.counters.wins)
+            } else {
+                ("hedge_loss", &self.counters.losses)
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+            context.record_event(
+                event,
+                HashMap::from([
+                    ("primary".to_string(), Value::from(primary_name.as_str())),
+                    ("hedge".to_string(), Value::from(hedge_name.as_str())),
+                    ("served_by".to_string(), Value::from(served_by.as_str())),
+                    (
+                        "elapsed_ms".to_string(),
+                        Value::from(started.elapsed().as_millis() as u64),
+                    ),
+                ]),
+            );
+            debug!(
+                served_by = %served_by,
+                model = %response.model,
+                "Hedged request completed"
+            );
+        }
+        result.map(|mut response| {
+            let metadata = response.metadata.get_or_insert_with(|| ProviderMetadata {
+                provider: served_by.clone(),
+                model: response.model.clone(),
+                ..Default::default()
+            });
+            metadata
+                .extra
+                .insert("hedged".to_string(), Value::from(true));
+            response
+        })
+    }
+}
+
+impl fmt::Debug for HedgedExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HedgedExecutor")
+            .field("registry", &self.registry)
+            .field("policy", &self.policy)
+            .field("hedges", &self.hedges)
+            .field("models", &self.models)
+            .field("pipeline", &self.pipeline)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use crate::providers::mock::{MockFailure, MockProvider, MockReply, MockStep};
+    use crate::types::{Choice, Message, Usage};
+    use std::sync::Mutex;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("gpt-4", vec![Message::user("Hello")])
+    }
+
+    fn hedger(providers: &[&Arc<MockProvider>], policy: HedgePolicy) -> HedgedExecutor {
+        let mut registry = ProviderRegistry::new();
+        for provider in providers {
+            registry.register((*provider).clone());
+        }
+        HedgedExecutor::new(registry, policy)
+    }
+
+    fn fail_after(latency: u64) -> MockStep {
+        MockStep::new(MockReply::Failure(MockFailure::ServerError)).with_latency(ms(latency))
+    }
+
+    fn reply_after(text: &str, latency: u64) -> MockStep {
+        let response = CompletionResponse {
+            id: text.to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "gpt-4".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(text),
+                finish_reason: None,
+            }],
+            usage: Usage::default(),
+            metadata: None,
+        };
+        MockStep::new(MockReply::Response(response)).with_latency(ms(latency))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fast_primary_is_not_hedged() {
+        let openai = Arc::new(MockProvider::new("openai").with_latency(ms(50)));
+        let executor = hedger(&[&openai], HedgePolicy::new(ms(100)));
+
+        let start = Instant::now();
+        let response = executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(start.elapsed(), ms(50));
+        assert!(!response.metadata.unwrap().extra.contains_key("hedged"));
+        assert_eq!(openai.call_count(), 1);
+        assert_eq!(executor.stats().hedged, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedge_wins() {
+        let openai = Arc::new(MockProvider::new("openai").with_latency(ms(500)));
+        let anthropic = Arc::new(MockProvider::new("anthropic").with_latency(ms(50)));
+        let executor = hedger(&[&openai, &anthropic], HedgePolicy::new(ms(100)))
+            .with_hedge_provider("openai", "anthropic")
+            .with_model_map(ModelMap::new().with_equivalent("gpt-4", "anthropic", "claude-3-opus"));
+
+        let start = Instant::now();
+        let response = executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(start.elapsed(), ms(150));
+        let metadata = response.metadata.unwrap();
+        assert_eq!(metadata.provider, "anthropic");
+        assert_eq!(metadata.extra["hedged"], Value::from(true));
+        assert_eq!(anthropic.requests()[0].model, "claude-3-opus");
+        assert_eq!(
+            executor.stats(),
+            HedgeStats {
+                requests: 1,
+                hedged: 1,
+                wins: 1,
+                losses: 0
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedge_loses_to_same_provider() {
+        // The first call is slow, the hedge to the same provider slower
+        let openai = Arc::new(
+            MockProvider::new("openai")
+                .then(reply_after("primary", 300))
+                .then(reply_after("hedge", 500)),
+        );
+        let executor = hedger(&[&openai], HedgePolicy::new(ms(100)));
+
+        let start = Instant::now();
+        let response = executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("primary"));
+        let metadata = response.metadata.unwrap();
+        assert_eq!(metadata.provider, "openai");
+        assert_eq!(metadata.extra["hedged"], Value::from(true));
+        assert_eq!(start.elapsed(), ms(300));
+        assert_eq!(openai.call_count(), 2);
+        assert_eq!(executor.stats().losses, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failures() {
+        // A hedged primary that fails leaves the hedge to answer
+        let openai = Arc::new(MockProvider::new("openai").then(fail_after(300)));
+        let azure = Arc::new(MockProvider::new("azure").with_latency(ms(400)));
+        let executor = hedger(&[&openai, &azure], HedgePolicy::new(ms(100)))
+            .with_hedge_provider("openai", "azure");
+        let start = Instant::now();
+        let response = executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(response.metadata.unwrap().provider, "azure");
+        assert_eq!(start.elapsed(), ms(500));
+
+        // Both fail: the primary's error
+        let openai = Arc::new(MockProvider::new("openai").then(fail_after(300)));
+        let azure = Arc::new(MockProvider::new("azure").fail_with(MockFailure::Timeout));
+        let executor = hedger(&[&openai, &azure], HedgePolicy::new(ms(100)))
+            .with_hedge_provider("openai", "azure");
+        let error = executor.complete("openai", &request()).await.unwrap_err();
+        assert_eq!(error.code(), "provider_unavailable");
+
+        // A primary failing before the delay is not hedged
+        let openai = Arc::new(MockProvider::new("openai").fail_with(MockFailure::ServerError));
+        let azure = Arc::new(MockProvider::new("azure"));
+        let executor = hedger(&[&openai, &azure], HedgePolicy::new(ms(100)))
+            .with_hedge_provider("openai", "azure");
+        assert!(executor.complete("openai", &request()).await.is_err());
+        assert_eq!(azure.call_count(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_percentile_delay() {
+        let latency = Arc::new(LatencyTracker::new());
+        let openai = Arc::new(MockProvider::new("openai"));
+        let executor = hedger(
+            &[&openai],
+            HedgePolicy::new(ms(1000))
+                .with_quantile(0.95)
+                .with_min_samples(10),
+        )
+        .with_latency_tracker(latency.clone());
+
+        for sample in 1..=9 {
+            latency.record("openai", "gpt-4", ms(sample * 100));
+        }
+        assert_eq!(executor.delay("openai", "gpt-4"), ms(1000));
+        latency.record("openai", "gpt-4", ms(1000));
+        latency.record("openai", "gpt-3.5-turbo", ms(5000));
+        assert_eq!(executor.delay("openai", "gpt-4"), ms(1000));
+        for _ in 0..10 {
+            latency.record("openai", "gpt-4", ms(50));
+        }
+        assert_eq!(executor.delay("openai", "gpt-4"), ms(900));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_records_span_events() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let openai = Arc::new(MockProvider::new("openai").with_latency(ms(500)));
+        let anthropic = Arc::new(MockProvider::new("anthropic").with_latency(ms(50)));
+        let executor = hedger(&[&openai, &anthropic], HedgePolicy::new(ms(100)))
+            .with_hedge_provider("openai", "anthropic");
+
+        let span_id = adapter
+            .lock()
+            .unwrap()
+            .start_provider_span("openai", "gpt-4", None);
+        let mut context =
+            RequestContext::new("openai", request()).with_span(adapter.clone(), span_id.clone());
+        executor.execute(&mut context).await.unwrap();
+        assert_eq!(context.attempt, 2);
+
+        let adapter = adapter.lock().unwrap();
+        let events = adapter.span_events(&span_id).unwrap();
+        let names: Vec<_> = events.iter().map(|event| event.name.as_str()).collect();
+        assert_eq!(names, vec!["hedge_sent", "hedge_win"]);
+        assert_eq!(events[0].attributes["delay_ms"], Value::from(100));
+        assert_eq!(events[0].attributes["hedge"], Value::from("anthropic"));
+        assert_eq!(events[1].attributes["served_by"], Value::from("anthropic"));
+        assert_eq!(events[1].attributes["elapsed_ms"], Value::from(150));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_span_not_credited_to_primary() {
+        let adapter = Arc::new(Mutex::new(SpanAdapter::new()));
+        let tracker = Arc::new(LatencyTracker::new());
+        let observer = tracker.clone();
+        adapter
+            .lock()
+            .unwrap()
+            .add_listener(move |span| observer.observe_span(span));
+
+        let openai = Arc::new(MockProvider::new("openai").with_latency(ms(500)));
+        let anthropic = Arc::new(MockProvider::new("anthropic").with_latency(ms(50)));
+        let executor = hedger(&[&openai, &anthropic], HedgePolicy::new(ms(100)))
+            .with_hedge_provider("openai", "anthropic")
+            .with_latency_tracker(tracker.clone())
+            .with_telemetry(adapter.clone());
+
+        executor.complete("openai", &request()).await.unwrap();
+        assert_eq!(adapter.lock().unwrap().active_span_count(), 0);
+        assert_eq!(tracker.sample_count("openai", None), 0);
+    }
+}
@@ -0,0 +1,255 @@
+//! Observed provider latency.
+//!
+//! A [`LatencyTracker`] keeps an exponentially weighted moving average and
+//! a window of recent samples for every provider, and for every model of a
+//! provider. It is typically fed by a [`SpanAdapter`] listener so every
+//! successful provider call counts:
+//!
+//! ```rust,ignore
+//! let tracker = Arc::new(LatencyTracker::new());
+//! let observer = tracker.clone();
+//! span_adapter.add_listener(move |span| observer.observe_span(span));
+//! ```
+//!
+//! [`SpanAdapter`]: crate::adapters::SpanAdapter
+
+use crate::adapters::telemetry::{FinishedSpan, SpanKind};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latency statistics for one provider or provider/model pair
+#[derive(Debug, Clone, Default)]
+struct Stats {
+    ewma: Option<f64>,
+    samples: VecDeque<Duration>,
+    /// Failed requests since the last successful one
+    failures: u32,
+}
+
+type Key = (String, Option<String>);
+
+/// Tracks latency per provider and per provider/model pair
+#[derive(Debug)]
+pub struct LatencyTracker {
+    alpha: f64,
+    window: usize,
+    stats: Mutex<HashMap<Key, Stats>>,
+}
+
+impl LatencyTracker {
+    /// Create a tracker weighting new samples at 0.3 and keeping the last
+    /// 100 samples for percentiles
+    pub fn new() -> Self {
+        Self {
+            alpha: 0.3,
+            window: 100,
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the weight (0.0 to 1.0) of a new sample in the moving average
+    pub fn with_alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha.clamp(f64::EPSILON, 1.0);
+        self
+    }
+
+    /// Set how many recent samples are kept for percentiles
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Record the latency of a completed request
+    pub fn record(&self, provider: &str, model: &str, latency: Duration) {
+        let mut stats = self.lock();
+        for key in [
+            (provider.to_string(), None),
+            (provider.to_string(), Some(model.to_string())),
+        ] {
+            let stats = stats.entry(key).or_default();
+            stats.failures = 0;
+            let sample = latency.as_secs_f64();
+            stats.ewma = Some(match stats.ewma {
+                Some(ewma) => self.alpha * sample + (1.0 - self.alpha) * ewma,
+                None => sample,
+            });
+            if stats.samples.len() == self.window {
+                stats.samples.pop_front();
+            }
+            stats.samples.push_back(latency);
+        }
+    }
+
+    /// Record a failed request
+    ///
+    /// Failures add no latency sample, since fast failures would make a
+    /// failing provider look fast, but are counted until the next success.
+    pub fn record_failure(&self, provider: &str, model: &str) {
+        let mut stats = self.lock();
+        for key in [
+            (provider.to_string(), None),
+            (provider.to_string(), Some(model.to_string())),
+        ] {
+            let stats = stats.entry(key).or_default();
+            stats.failures = stats.failures.saturating_add(1);
+        }
+    }
+
+    /// Record the outcome of a provider span
+    ///
+    /// Routed spans, which cover several provider calls and are started on
+    /// the requested provider rather than the one that answered, and health
+    /// probe spans are ignored.
+    pub fn observe_span(&self, span: &FinishedSpan) {
+        if span.kind != SpanKind::Provider {
+            return;
+        }
+        if span.success {
+            self.record(&span.provider, &span.model, span.latency);
+        } else {
+            self.record_failure(&span.provider, &span.model);
+        }
+    }
+
+    /// Moving average latency of a provider, or of one of its models
+    pub fn ewma(&self, provider: &str, model: Option<&str>) -> Option<Duration> {
+        let stats = self.lock();
+        let ewma = stats.get(&key(provider, model))?.ewma?;
+        Duration::try_from_secs_f64(ewma).ok()
+    }
+
+    /// Latency below which a fraction `quantile` (0.0 to 1.0) of recent
+    /// requests completed, e.g. 0.95 for the p95
+    pub fn percentile(
+        &self,
+        provider: &str,
+        model: Option<&str>,
+        quantile: f64,
+    ) -> Option<Duration> {
+        let stats = self.lock();
+        let samples = &stats.get(&key(provider, model))?.samples;
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<_> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        // Nearest-rank: the smallest sample covering `quantile` of them
+        let rank = (quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+
+    /// Number of samples in the window
+    pub fn sample_count(&self, provider: &str, model: Option<&str>) -> usize {
+        let stats = self.lock();
+        stats
+            .get(&key(provider, model))
+            .map_or(0, |stats| stats.samples.len())
+    }
+
+    /// Failed requests to a provider, or one of its models, since the last
+    /// successful one
+    pub fn consecutive_failures(&self, provider: &str, model: Option<&str>) -> u32 {
+        let stats = self.lock();
+        stats
+            .get(&key(provider, model))
+            .map_or(0, |stats| stats.failures)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Key, Stats>> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn key(provider: &str, model: Option<&str>) -> Key {
+    (provider.to_string(), model.map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::telemetry::SpanAdapter;
+    use std::sync::Arc;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_ewma() {
+        let tracker = LatencyTracker::new().with_alpha(0.5);
+        assert_eq!(tracker.ewma("openai", None), None);
+
+        tracker.record("openai", "gpt-4", ms(100));
+        tracker.record("openai", "gpt-4", ms(300));
+        tracker.record("openai", "gpt-3.5-turbo", ms(100));
+        assert_eq!(tracker.ewma("openai", Some("gpt-4")), Some(ms(200)));
+        assert_eq!(tracker.ewma("openai", Some("gpt-3.5-turbo")), Some(ms(100)));
+        assert_eq!(tracker.ewma("openai", None), Some(ms(150)));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let tracker = LatencyTracker::new().with_window(20);
+        for latency in 1..=40 {
+            tracker.record("openai", "gpt-4", ms(latency * 10));
+        }
+
+        // Only the last 20 samples (210ms to 400ms) are kept
+        assert_eq!(tracker.sample_count("openai", Some("gpt-4")), 20);
+        assert_eq!(
+            tracker.percentile("openai", Some("gpt-4"), 0.5),
+            Some(ms(300))
+        );
+        assert_eq!(
+            tracker.percentile("openai", Some("gpt-4"), 0.95),
+            Some(ms(390))
+        );
+        assert_eq!(tracker.percentile("openai", None, 1.0), Some(ms(400)));
+        assert_eq!(
+            tracker.percentile("openai", Some("gpt-4"), 0.0),
+            Some(ms(210))
+        );
+        assert_eq!(tracker.percentile("anthropic", None, 0.95), None);
+    }
+
+    #[test]
+    fn test_consecutive_failures() {
+        let tracker = LatencyTracker::new();
+        tracker.record_failure("openai", "gpt-4");
+        tracker.record_failure("openai", "gpt-4");
+        assert_eq!(tracker.consecutive_failures("openai", None), 2);
+        assert_eq!(tracker.consecutive_failures("openai", Some("gpt-4")), 2);
+        assert_eq!(tracker.sample_count("openai", None), 0);
+
+        tracker.record("openai", "gpt-4", ms(100));
+        assert_eq!(tracker.consecutive_failures("openai", None), 0);
+    }
+
+    #[test]
+    fn test_fed_by_spans() {
+        let tracker = Arc::new(LatencyTracker::new());
+        let mut adapter = SpanAdapter::new();
+        let observer = tracker.clone();
+        adapter.add_listener(move |span| observer.observe_span(span));
+
+        let span_id = adapter.start_provider_span("openai", "gpt-4", None);
+        adapter.finish_span(&span_id, true).unwrap();
+        let span_id = adapter.start_provider_span("anthropic", "claude-3-opus", None);
+        adapter.finish_span(&span_id, false).unwrap();
+        let span_id = adapter.start_provider_span("openai", "gpt-4", None);
+        adapter.set_span_kind(&span_id, SpanKind::Routed).unwrap();
+        adapter.finish_span(&span_id, true).unwrap();
+
+        assert_eq!(tracker.sample_count("openai", Some("gpt-4")), 1);
+        assert_eq!(tracker.sample_count("anthropic", None), 0);
+        assert_eq!(tracker.consecutive_failures("anthropic", None), 1);
+        assert_eq!(tracker.consecutive_failures("openai", None), 0);
+    }
+}
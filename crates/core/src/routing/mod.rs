@@ -0,0 +1,50 @@
+//! # Routing
+//!
+//! Deciding which provider serves a request.
+//!
+//! - [`balancer`] - picking a provider per request by load balancing
+//!   strategy
+//! - [`fallback`] - re-issuing failed requests to fallback providers, with
+//!   model names translated between providers
+//! - [`health`] - background health probes that take unhealthy providers
+//!   out of routing
+//! - [`hedge`] - duplicating slow requests to cut tail latency
+//! - [`latency`] - moving-average and percentile latency per provider
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::routing::{FallbackExecutor, ModelMap};
+//!
+//! // Fallbacks come from each provider's `RoutingPolicy`
+//! let executor = FallbackExecutor::from_config(&config, registry)?
+//!     .with_model_map(ModelMap::new().with_equivalent("gpt-4", "anthropic", "claude-3-opus"))
+//!     .with_pipeline(Pipeline::new().with(RetryMiddleware::default()))
+//!     .with_telemetry(span_adapter);
+//!
+//! let response = executor.complete("openai", &request).await?;
+//! let served_by = response.metadata.map(|metadata| metadata.provider);
+//!
+//! // Spread requests by observed latency
+//! let selector = ProviderSelector::from_policy(&policy, ["openai", "anthropic"])
+//!     .with_latency_tracker(latency_tracker.clone());
+//! let selection = selector.select().expect("providers configured");
+//! let response = executor.complete(selection.provider(), &request).await?;
+//!
+//! // Probe providers in the background and route around unhealthy ones
+//! let health = Arc::new(HealthChecker::new(registry.clone(), HealthCheckPolicy::new()));
+//! let _task = health.spawn();
+//! let executor = executor.with_health_checker(health.clone());
+//! ```
+
+pub mod balancer;
+pub mod fallback;
+pub mod health;
+pub mod hedge;
+pub mod latency;
+
+pub use balancer::{Pricing, PricingTable, ProviderSelector, Selection};
+pub use fallback::{FallbackExecutor, ModelMap};
+pub use health::{HealthCheckPolicy, HealthChecker, HealthTransition, Probe, ProviderHealth};
+pub use hedge::{HedgePolicy, HedgeStats, HedgedExecutor};
+pub use latency::LatencyTracker;
@@ -0,0 +1,472 @@
+//! Anthropic Messages stream parsing.
+//!
+//! Anthropic streams named Server-Sent Events instead of OpenAI-style
+//! `choices[].delta` chunks:
+//!
+//! - `message_start` - response ID, model and input token usage
+//! - `content_block_start` / `content_block_stop` - a text or `tool_use` block
+//! - `content_block_delta` - `text_delta` or `input_json_delta` for a block
+//! - `message_delta` - stop reason and output token usage
+//! - `message_stop`, `ping`
+//! - `error` - a failure after the stream started (e.g. `overloaded_error`)
+//!
+//! Content blocks are numbered across text and tool use, so the parser
+//! renumbers `tool_use` blocks into tool-call indices.
+
+use super::delta::{ChunkDelta, StreamEvent};
+use super::sse::{self, SseEvent};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::providers::anthropic::map_stop_reason;
+use crate::providers::errors;
+use crate::types::{Role, Usage};
+use futures::stream::Stream;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Event on an Anthropic Messages stream
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicEvent {
+    /// Start of the message
+    MessageStart {
+        /// Message metadata
+        message: MessageStart,
+    },
+    /// Start of a content block
+    ContentBlockStart {
+        /// Block index
+        index: u32,
+        /// Initial block content
+        content_block: ContentBlockStart,
+    },
+    /// Incremental content for a block
+    ContentBlockDelta {
+        /// Block index
+        index: u32,
+        /// The content
+        delta: ContentDelta,
+    },
+    /// End of a content block
+    ContentBlockStop {
+        /// Block index
+        index: u32,
+    },
+    /// Top-level message changes
+    MessageDelta {
+        /// Stop reason and sequence
+        delta: MessageDeltaBody,
+        /// Cumulative usage
+        #[serde(default)]
+        usage: StreamUsage,
+    },
+    /// End of the message
+    MessageStop,
+    /// Keep-alive
+    Ping,
+    /// Error raised mid-stream
+    Error {
+        /// Error details
+        error: StreamError,
+    },
+    /// Event type this parser does not know
+    #[serde(other)]
+    Unknown,
+}
+
+/// `message` of a `message_start` event
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MessageStart {
+    /// Message ID
+    #[serde(default)]
+    pub id: String,
+    /// Model serving the request
+    #[serde(default)]
+    pub model: String,
+    /// Usage so far (input tokens)
+    #[serde(default)]
+    pub usage: StreamUsage,
+}
+
+/// Token usage reported on the stream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct StreamUsage {
+    /// Input tokens
+    #[serde(default)]
+    pub input_tokens: u32,
+    /// Output tokens
+    #[serde(default)]
+    pub output_tokens: u32,
+}
+
+/// `content_block` of a `content_block_start` event
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlockStart {
+    /// Text block, usually starting empty
+    Text {
+        /// Initial text
+        #[serde(default)]
+        text: String,
+    },
+    /// Tool invocation; the input arrives as `input_json_delta`s
+    ToolUse {
+        /// Tool use ID
+        id: String,
+        /// Tool name
+        name: String,
+    },
+    /// Other block types (thinking, ...)
+    #[serde(other)]
+    Other,
+}
+
+/// `delta` of a `content_block_delta` event
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentDelta {
+    /// Text to append to a text block
+    TextDelta {
+        /// Text fragment
+        text: String,
+    },
+    /// Fragment of a tool's JSON input
+    InputJsonDelta {
+        /// JSON fragment
+        partial_json: String,
+    },
+    /// Other delta types (thinking, signatures, ...)
+    #[serde(other)]
+    Other,
+}
+
+/// `delta` of a `message_delta` event
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MessageDeltaBody {
+    /// Why generation stopped
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    /// Stop sequence that was hit
+    #[serde(default)]
+    pub stop_sequence: Option<String>,
+}
+
+/// `error` of an `error` event
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct StreamError {
+    /// Error type (`overloaded_error`, `api_error`, ...)
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    /// Human-readable message
+    #[serde(default)]
+    pub message: String,
+}
+
+impl StreamError {
+    /// Convert into the matching connector error
+    ///
+    /// The provider is left unset: Anthropic and Bedrock share this format.
+    pub fn into_error(self) -> ConnectorError {
+        let error = ProviderError::new("", format!("{}: {}", self.kind, self.message));
+        errors::anthropic_error(&self.kind, error)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ToolBlock {
+    /// Tool-call index in the unified message
+    index: u32,
+    /// Whether any input JSON arrived
+    has_input: bool,
+}
+
+/// Converts Anthropic stream events into unified stream events
+#[derive(Debug, Clone, Default)]
+pub struct AnthropicStreamParser {
+    /// Open `tool_use` blocks by content block index
+    tool_blocks: HashMap<u32, ToolBlock>,
+    tool_calls: u32,
+    stopped: bool,
+}
+
+impl AnthropicStreamParser {
+    /// Create a parser at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `message_stop` has been seen
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Parse one Server-Sent Event
+    ///
+    /// The event type is taken from the JSON `type` field; the SSE `event:`
+    /// line carries the same name.
+    pub fn parse_event(&mut self, event: &SseEvent) -> Result<Vec<StreamEvent>> {
+        let parsed: AnthropicEvent = event.json()?;
+        self.parse(parsed)
+    }
+
+    /// Translate one typed event
+    ///
+    /// `error` events are returned as `Err`.
+    pub fn parse(&mut self, event: AnthropicEvent) -> Result<Vec<StreamEvent>> {
+        let events = match event {
+            AnthropicEvent::MessageStart { message } => {
+                self.tool_blocks.clear();
+                self.tool_calls = 0;
+                vec![
+                    StreamEvent::Start {
+                        id: message.id,
+                        model: message.model,
+                    },
+                    StreamEvent::delta(ChunkDelta::Role {
+                        role: Role::Assistant,
+                    }),
+                    usage_event(message.usage),
+                ]
+            }
+            AnthropicEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => match content_block {
+                ContentBlockStart::Text { text } if !text.is_empty() => {
+                    vec![StreamEvent::delta(ChunkDelta::Text { text })]
+                }
+                ContentBlockStart::ToolUse { id, name } => {
+                    let call = ToolBlock {
+                        index: self.tool_calls,
+                        has_input: false,
+                    };
+                    self.tool_calls += 1;
+                    self.tool_blocks.insert(index, call);
+                    vec![StreamEvent::delta(ChunkDelta::ToolCall {
+                        index: call.index,
+                        id: Some(id),
+                        name: Some(name),
+                        arguments: String::new(),
+                    })]
+                }
+                _ => Vec::new(),
+            },
+            AnthropicEvent::ContentBlockDelta { index, delta } => match delta {
+                ContentDelta::TextDelta { text } => {
+                    vec![StreamEvent::delta(ChunkDelta::Text { text })]
+                }
+                ContentDelta::InputJsonDelta { partial_json } => {
+                    let call = self.tool_blocks.get_mut(&index).ok_or_else(|| {
+                        ConnectorError::Internal(format!(
+                            "Anthropic input_json_delta for unknown block {}",
+                            index
+                        ))
+                    })?;
+                    call.has_input |= !partial_json.is_empty();
+                    vec![StreamEvent::delta(ChunkDelta::ToolCall {
+                        index: call.index,
+                        id: None,
+                        name: None,
+                        arguments: partial_json,
+                    })]
+                }
+                ContentDelta::Other => Vec::new(),
+            },
+            AnthropicEvent::ContentBlockStop { index } => match self.tool_blocks.remove(&index) {
+                // A tool without parameters streams no input; match the
+                // non-streaming `{}`
+                Some(call) if !call.has_input => {
+                    vec![StreamEvent::delta(ChunkDelta::ToolCall {
+                        index: call.index,
+                        id: None,
+                        name: None,
+                        arguments: "{}".to_string(),
+                    })]
+                }
+                _ => Vec::new(),
+            },
+            AnthropicEvent::MessageDelta { delta, usage } => {
+                let mut events = Vec::new();
+                if let Some(reason) = delta.stop_reason.as_deref().and_then(map_stop_reason) {
+                    events.push(StreamEvent::delta(ChunkDelta::Finish { reason }));
+                }
+                events.push(usage_event(usage));
+                events
+            }
+            AnthropicEvent::MessageStop => {
+                self.stopped = true;
+                Vec::new()
+            }
+            AnthropicEvent::Ping | AnthropicEvent::Unknown => Vec::new(),
+            AnthropicEvent::Error { error } => return Err(error.into_error()),
+        };
+        Ok(events)
+    }
+}
+
+fn usage_event(usage: StreamUsage) -> StreamEvent {
+    StreamEvent::delta(ChunkDelta::Usage {
+        usage: Usage::new(usage.input_tokens, usage.output_tokens),
+    })
+}
+
+/// Decode an SSE byte stream of Anthropic events
+///
+/// Ends at `message_stop` or at the first error, including `error` events.
+/// A stream ending before `message_stop` is reported as
+/// [`ConnectorError::StreamInterrupted`].
+pub fn decode<S, B, E>(bytes: S) -> impl Stream<Item = Result<StreamEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut parser = AnthropicStreamParser::new();
+    sse::decode_until(
+        bytes,
+        |event| event.event == "message_stop",
+        "message_stop",
+        move |event| parser.parse_event(event),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::delta::aggregate;
+    use crate::types::{FinishReason, ToolCall};
+    use futures::stream::{self, StreamExt};
+
+    const TOOL_STREAM: &str = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_01\",\"type\":\"message\",\"role\":\"assistant\",\"model\":\"claude-3-5-sonnet-20241022\",\"content\":[],\"stop_reason\":null,\"usage\":{\"input_tokens\":472,\"output_tokens\":2}}}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+        "event: ping\n",
+        "data: {\"type\":\"ping\"}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Let me check\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_01\",\"name\":\"get_weather\",\"input\":{}}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"location\\\": \\\"Par\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"is\\\"}\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":2,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_02\",\"name\":\"get_time\",\"input\":{}}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":2}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\",\"stop_sequence\":null},\"usage\":{\"output_tokens\":89}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    fn chunks(body: &str, size: usize) -> Vec<std::result::Result<Vec<u8>, String>> {
+        body.as_bytes()
+            .chunks(size)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_text_and_tool_use_stream() {
+        let response = aggregate(decode(stream::iter(chunks(TOOL_STREAM, 11))))
+            .await
+            .unwrap();
+
+        assert_eq!(response.id, "msg_01");
+        assert_eq!(response.model, "claude-3-5-sonnet-20241022");
+        assert_eq!(response.text().as_deref(), Some("Let me check"));
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            vec![
+                ToolCall::function("toolu_01", "get_weather", "{\"location\": \"Paris\"}"),
+                ToolCall::function("toolu_02", "get_time", "{}"),
+            ]
+        );
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(response.usage, Usage::new(472, 89));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_stream() {
+        let truncated = &TOOL_STREAM[..TOOL_STREAM.find("event: message_stop").unwrap()];
+        let items: Vec<Result<StreamEvent>> =
+            decode(stream::iter(chunks(truncated, 11))).collect().await;
+
+        let error = items.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.code(), "stream_interrupted");
+        assert!(error.to_string().contains("message_stop"));
+        assert!(aggregate(decode(stream::iter(chunks(truncated, 11))))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_typed_deltas() {
+        let mut parser = AnthropicStreamParser::new();
+        let event: AnthropicEvent = serde_json::from_str(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            AnthropicEvent::ContentBlockDelta {
+                index: 0,
+                delta: ContentDelta::TextDelta {
+                    text: "Hi".to_string()
+                },
+            }
+        );
+        assert_eq!(parser.parse(event).unwrap(), vec![StreamEvent::text("Hi")]);
+
+        let unknown: AnthropicEvent =
+            serde_json::from_str(r#"{"type":"some_future_event","x":1}"#).unwrap();
+        assert_eq!(unknown, AnthropicEvent::Unknown);
+        assert!(parser.parse(unknown).unwrap().is_empty());
+
+        parser.parse(AnthropicEvent::MessageStop).unwrap();
+        assert!(parser.is_stopped());
+    }
+
+    #[tokio::test]
+    async fn test_mid_stream_error_event() {
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_02\",\"model\":\"claude\",\"usage\":{\"input_tokens\":5}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "event: error\n",
+            "data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+        );
+        let items: Vec<Result<StreamEvent>> =
+            decode(stream::iter(chunks(body, 64))).collect().await;
+
+        let last = items.last().unwrap().as_ref().unwrap_err();
+        assert!(last.to_string().contains("overloaded_error"));
+        assert!(last.to_string().contains("Overloaded"));
+        let texts: Vec<&str> = items
+            .iter()
+            .filter_map(|item| item.as_ref().ok()?.as_text())
+            .collect();
+        assert_eq!(texts, vec!["Hel"]);
+
+        let error = StreamError {
+            kind: "rate_limit_error".to_string(),
+            message: "slow down".to_string(),
+        };
+        assert!(matches!(
+            error.into_error(),
+            ConnectorError::RateLimited { .. }
+        ));
+    }
+}
@@ -0,0 +1,325 @@
+//! Cohere Chat v2 stream parsing.
+//!
+//! Cohere streams named Server-Sent Events, each carrying its changes under
+//! `delta.message`:
+//!
+//! - `message-start` - response ID
+//! - `content-start` / `content-delta` / `content-end` - text content
+//! - `tool-plan-delta` - the model's reasoning before calling tools
+//! - `tool-call-start` / `tool-call-delta` / `tool-call-end` - a tool call
+//!   and fragments of its arguments
+//! - `citation-start` / `citation-end` - grounding citations
+//! - `message-end` - finish reason and usage
+//!
+//! Tool plans and citations have no place in [`StreamEvent`] and are
+//! skipped; use a non-streaming completion when citations are needed.
+
+use super::delta::{ChunkDelta, StreamEvent};
+use super::sse::{self, SseEvent};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::providers::cohere::{map_finish_reason, CohereUsage};
+use crate::types::{Role, Usage};
+use futures::stream::Stream;
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Event on a Cohere chat stream
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum CohereEvent {
+    MessageStart {
+        #[serde(default)]
+        id: String,
+    },
+    ContentDelta {
+        #[serde(default)]
+        delta: EventDelta,
+    },
+    ToolCallStart {
+        index: u32,
+        #[serde(default)]
+        delta: EventDelta,
+    },
+    ToolCallDelta {
+        index: u32,
+        #[serde(default)]
+        delta: EventDelta,
+    },
+    MessageEnd {
+        #[serde(default)]
+        delta: MessageEndDelta,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct EventDelta {
+    #[serde(default)]
+    message: DeltaMessage,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct DeltaMessage {
+    #[serde(default)]
+    content: Option<DeltaContent>,
+    #[serde(default)]
+    tool_calls: Option<DeltaToolCall>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct DeltaContent {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct DeltaToolCall {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<DeltaFunction>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct DeltaFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MessageEndDelta {
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<CohereUsage>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Converts Cohere chat stream events into unified stream events
+#[derive(Debug, Clone, Default)]
+pub struct CohereStreamParser {
+    model: String,
+    stopped: bool,
+}
+
+impl CohereStreamParser {
+    /// Create a parser for a stream of the requested model
+    ///
+    /// Cohere does not echo the model on the stream.
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            stopped: false,
+        }
+    }
+
+    /// Whether `message-end` has been seen
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Parse one Server-Sent Event
+    ///
+    /// A `message-end` with finish reason `ERROR` is returned as
+    /// [`ConnectorError::StreamInterrupted`].
+    pub fn parse_event(&mut self, event: &SseEvent) -> Result<Vec<StreamEvent>> {
+        let events = match event.json()? {
+            CohereEvent::MessageStart { id } => vec![
+                StreamEvent::Start {
+                    id,
+                    model: self.model.clone(),
+                },
+                StreamEvent::delta(ChunkDelta::Role {
+                    role: Role::Assistant,
+                }),
+            ],
+            CohereEvent::ContentDelta { delta } => delta
+                .message
+                .content
+                .and_then(|content| content.text)
+                .filter(|text| !text.is_empty())
+                .map(StreamEvent::text)
+                .into_iter()
+                .collect(),
+            CohereEvent::ToolCallStart { index, delta } => {
+                let call = delta.message.tool_calls.unwrap_or_default();
+                let function = call.function.unwrap_or_default();
+                vec![StreamEvent::delta(ChunkDelta::ToolCall {
+                    index,
+                    id: call.id,
+                    name: function.name,
+                    arguments: function.arguments.unwrap_or_default(),
+                })]
+            }
+            CohereEvent::ToolCallDelta { index, delta } => {
+                let function = delta
+                    .message
+                    .tool_calls
+                    .and_then(|call| call.function)
+                    .unwrap_or_default();
+                vec![StreamEvent::delta(ChunkDelta::ToolCall {
+                    index,
+                    id: None,
+                    name: None,
+                    arguments: function.arguments.unwrap_or_default(),
+                })]
+            }
+            CohereEvent::MessageEnd { delta } => {
+                self.stopped = true;
+                if delta.finish_reason.as_deref() == Some("ERROR") {
+                    let message = delta
+                        .error
+                        .unwrap_or_else(|| "generation failed".to_string());
+                    return Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                        "", message,
+                    )));
+                }
+                let mut events = Vec::new();
+                if let Some(reason) = delta.finish_reason.as_deref().and_then(map_finish_reason) {
+                    events.push(StreamEvent::delta(ChunkDelta::Finish { reason }));
+                }
+                // Prefer raw token counts; billed units exclude system overhead
+                if let Some(tokens) = delta
+                    .usage
+                    .and_then(|usage| usage.tokens.or(usage.billed_units))
+                {
+                    events.push(StreamEvent::delta(ChunkDelta::Usage {
+                        usage: Usage::new(tokens.input_tokens as u32, tokens.output_tokens as u32),
+                    }));
+                }
+                events
+            }
+            CohereEvent::Unknown => Vec::new(),
+        };
+        Ok(events)
+    }
+}
+
+/// Decode an SSE byte stream of Cohere chat events
+///
+/// Ends at `message-end` or at the first error. A stream ending before
+/// `message-end` is reported as [`ConnectorError::StreamInterrupted`].
+pub fn decode<S, B, E>(bytes: S, model: &str) -> impl Stream<Item = Result<StreamEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut parser = CohereStreamParser::new(model);
+    sse::decode_until(
+        bytes,
+        |event| event.event == "message-end",
+        "message-end",
+        move |event| parser.parse_event(event),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::delta::aggregate;
+    use crate::types::{FinishReason, ToolCall};
+    use futures::stream::{self, StreamExt};
+
+    const TOOL_STREAM: &str = concat!(
+        "event: message-start\n",
+        "data: {\"id\":\"29f14a5a\",\"type\":\"message-start\",\"delta\":{\"message\":{\"role\":\"assistant\",\"content\":[],\"tool_plan\":\"\",\"tool_calls\":[],\"citations\":[]}}}\n\n",
+        "event: tool-plan-delta\n",
+        "data: {\"type\":\"tool-plan-delta\",\"delta\":{\"message\":{\"tool_plan\":\"I will look up the weather.\"}}}\n\n",
+        "event: tool-call-start\n",
+        "data: {\"type\":\"tool-call-start\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"id\":\"get_weather_1byjy32y\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"\"}}}}}\n\n",
+        "event: tool-call-delta\n",
+        "data: {\"type\":\"tool-call-delta\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"function\":{\"arguments\":\"{\\\"location\\\":\"}}}}}\n\n",
+        "event: tool-call-delta\n",
+        "data: {\"type\":\"tool-call-delta\",\"index\":0,\"delta\":{\"message\":{\"tool_calls\":{\"function\":{\"arguments\":\"\\\"Toronto\\\"}\"}}}}}\n\n",
+        "event: tool-call-end\n",
+        "data: {\"type\":\"tool-call-end\",\"index\":0}\n\n",
+        "event: message-end\n",
+        "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"TOOL_CALL\",\"usage\":{\"billed_units\":{\"input_tokens\":37,\"output_tokens\":21},\"tokens\":{\"input_tokens\":913,\"output_tokens\":54}}}}\n\n",
+    );
+
+    const TEXT_STREAM: &str = concat!(
+        "event: message-start\n",
+        "data: {\"id\":\"c14c80c3\",\"type\":\"message-start\",\"delta\":{\"message\":{\"role\":\"assistant\",\"content\":[]}}}\n\n",
+        "event: content-start\n",
+        "data: {\"type\":\"content-start\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"type\":\"text\",\"text\":\"\"}}}}\n\n",
+        "event: content-delta\n",
+        "data: {\"type\":\"content-delta\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"text\":\"HQ is in \"}}}}\n\n",
+        "event: content-delta\n",
+        "data: {\"type\":\"content-delta\",\"index\":0,\"delta\":{\"message\":{\"content\":{\"text\":\"Toronto.\"}}}}\n\n",
+        "event: citation-start\n",
+        "data: {\"type\":\"citation-start\",\"index\":0,\"delta\":{\"message\":{\"citations\":{\"start\":9,\"end\":16,\"text\":\"Toronto\",\"sources\":[{\"type\":\"document\",\"id\":\"doc-hq\"}]}}}}\n\n",
+        "event: citation-end\n",
+        "data: {\"type\":\"citation-end\",\"index\":0}\n\n",
+        "event: content-end\n",
+        "data: {\"type\":\"content-end\",\"index\":0}\n\n",
+        "event: message-end\n",
+        "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"COMPLETE\",\"usage\":{\"tokens\":{\"input_tokens\":210,\"output_tokens\":5}}}}\n\n",
+    );
+
+    fn chunks(body: &str, size: usize) -> Vec<std::result::Result<Vec<u8>, String>> {
+        body.as_bytes()
+            .chunks(size)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_text_stream() {
+        let stream = decode(stream::iter(chunks(TEXT_STREAM, 13)), "command-r-plus");
+        let response = aggregate(stream).await.unwrap();
+
+        assert_eq!(response.id, "c14c80c3");
+        assert_eq!(response.model, "command-r-plus");
+        assert_eq!(response.text().as_deref(), Some("HQ is in Toronto."));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(response.usage, Usage::new(210, 5));
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_stream() {
+        let stream = decode(stream::iter(chunks(TOOL_STREAM, 7)), "command-r-plus");
+        let response = aggregate(stream).await.unwrap();
+
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            vec![ToolCall::function(
+                "get_weather_1byjy32y",
+                "get_weather",
+                "{\"location\":\"Toronto\"}"
+            )]
+        );
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(response.usage, Usage::new(913, 54));
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_truncation() {
+        let body = concat!(
+            "event: message-start\n",
+            "data: {\"id\":\"1\",\"type\":\"message-start\"}\n\n",
+            "event: message-end\n",
+            "data: {\"type\":\"message-end\",\"delta\":{\"finish_reason\":\"ERROR\",\"error\":\"internal failure\"}}\n\n",
+        );
+        let items: Vec<Result<StreamEvent>> = decode(stream::iter(chunks(body, 64)), "command-r")
+            .collect()
+            .await;
+        let error = items.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.code(), "stream_interrupted");
+        assert!(error.to_string().contains("internal failure"));
+
+        let truncated = &TEXT_STREAM[..TEXT_STREAM.find("event: citation-start").unwrap()];
+        let items: Vec<Result<StreamEvent>> =
+            decode(stream::iter(chunks(truncated, 64)), "command-r")
+                .collect()
+                .await;
+        let error = items.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.code(), "stream_interrupted");
+        assert!(error.to_string().contains("message-end"));
+        assert_eq!(items.iter().filter(|item| item.is_ok()).count(), 4);
+    }
+}
@@ -0,0 +1,448 @@
+//! Typed streaming deltas and response reconstruction.
+//!
+//! Providers translate their native stream formats into [`StreamEvent`]s.
+//! A [`StreamAggregator`] folds those events back into the same
+//! [`CompletionResponse`] a non-streaming call would have returned, including
+//! tool calls whose arguments arrive as interleaved fragments.
+
+use crate::error::Result;
+use crate::types::{
+    Choice, CompletionResponse, FinishReason, Message, ProviderMetadata, Role, ToolCall, Usage,
+};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Incremental change to a choice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChunkDelta {
+    /// Role of the message being generated
+    Role {
+        /// Message role
+        role: Role,
+    },
+    /// Text to append
+    Text {
+        /// Text fragment
+        text: String,
+    },
+    /// Fragment of a tool call
+    ///
+    /// Fragments with the same `index` belong to the same call; `id` and
+    /// `name` are usually only present on the first one.
+    ToolCall {
+        /// Position of the call within the message
+        index: u32,
+        /// Tool call ID
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        /// Function name
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Argument JSON fragment to append
+        #[serde(default)]
+        arguments: String,
+    },
+    /// Token usage; non-zero counts replace earlier ones
+    Usage {
+        /// Usage reported so far
+        usage: Usage,
+    },
+    /// Generation finished
+    Finish {
+        /// Reason the model stopped
+        reason: FinishReason,
+    },
+}
+
+/// Event yielded by a provider stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Response metadata, sent before the first delta
+    Start {
+        /// Response ID
+        id: String,
+        /// Model serving the request
+        model: String,
+    },
+    /// Change to the choice at `index`
+    Delta {
+        /// Choice index
+        index: u32,
+        /// The change
+        delta: ChunkDelta,
+    },
+}
+
+impl StreamEvent {
+    /// Delta for the first choice
+    pub fn delta(delta: ChunkDelta) -> Self {
+        StreamEvent::Delta { index: 0, delta }
+    }
+
+    /// Text delta for the first choice
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::delta(ChunkDelta::Text { text: text.into() })
+    }
+
+    /// Text carried by this event, if it is a text delta
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            StreamEvent::Delta {
+                delta: ChunkDelta::Text { text },
+                ..
+            } => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Events replaying a complete response, for providers without native
+/// streaming
+pub fn events_from_response(response: &CompletionResponse) -> Vec<StreamEvent> {
+    let mut events = vec![StreamEvent::Start {
+        id: response.id.clone(),
+        model: response.model.clone(),
+    }];
+
+    for choice in &response.choices {
+        let index = choice.index;
+        events.push(StreamEvent::Delta {
+            index,
+            delta: ChunkDelta::Role {
+                role: choice.message.role,
+            },
+        });
+        let text = choice.message.text();
+        if !text.is_empty() {
+            events.push(StreamEvent::Delta {
+                index,
+                delta: ChunkDelta::Text { text },
+            });
+        }
+        for (position, call) in choice.message.tool_calls.iter().enumerate() {
+            events.push(StreamEvent::Delta {
+                index,
+                delta: ChunkDelta::ToolCall {
+                    index: position as u32,
+                    id: Some(call.id.clone()),
+                    name: Some(call.function.name.clone()),
+                    arguments: call.function.arguments.clone(),
+                },
+            });
+        }
+        if let Some(reason) = choice.finish_reason {
+            events.push(StreamEvent::Delta {
+                index,
+                delta: ChunkDelta::Finish { reason },
+            });
+        }
+    }
+
+    events.push(StreamEvent::delta(ChunkDelta::Usage {
+        usage: response.usage,
+    }));
+    events
+}
+
+#[derive(Debug, Clone, Default)]
+struct ToolCallState {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ChoiceState {
+    role: Option<Role>,
+    text: String,
+    tool_calls: BTreeMap<u32, ToolCallState>,
+    finish_reason: Option<FinishReason>,
+}
+
+/// Rebuilds a [`CompletionResponse`] from stream events
+#[derive(Debug, Clone, Default)]
+pub struct StreamAggregator {
+    provider: Option<String>,
+    id: String,
+    model: String,
+    choices: BTreeMap<u32, ChoiceState>,
+    usage: Usage,
+}
+
+impl StreamAggregator {
+    /// Create an empty aggregator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the serving provider in the response metadata
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    /// Apply one event
+    pub fn push(&mut self, event: StreamEvent) {
+        let (index, delta) = match event {
+            StreamEvent::Start { id, model } => {
+                self.id = id;
+                self.model = model;
+                return;
+            }
+            StreamEvent::Delta { index, delta } => (index, delta),
+        };
+
+        if let ChunkDelta::Usage { usage } = delta {
+            if usage.prompt_tokens > 0 {
+                self.usage.prompt_tokens = usage.prompt_tokens;
+            }
+            if usage.completion_tokens > 0 {
+                self.usage.completion_tokens = usage.completion_tokens;
+            }
+            self.usage.total_tokens = self
+                .usage
+                .prompt_tokens
+                .saturating_add(self.usage.completion_tokens);
+            return;
+        }
+
+        let choice = self.choices.entry(index).or_default();
+        match delta {
+            ChunkDelta::Role { role } => choice.role = Some(role),
+            ChunkDelta::Text { text } => choice.text.push_str(&text),
+            ChunkDelta::ToolCall {
+                index,
+                id,
+                name,
+                arguments,
+            } => {
+                let call = choice.tool_calls.entry(index).or_default();
+                if let Some(id) = id {
+                    call.id = id;
+                }
+                if let Some(name) = name {
+                    call.name.push_str(&name);
+                }
+                call.arguments.push_str(&arguments);
+            }
+            ChunkDelta::Finish { reason } => choice.finish_reason = Some(reason),
+            ChunkDelta::Usage { .. } => unreachable!("handled above"),
+        }
+    }
+
+    /// Text generated so far for the first choice
+    pub fn text(&self) -> &str {
+        self.choices
+            .get(&0)
+            .map(|choice| choice.text.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Build the response
+    pub fn finish(self) -> CompletionResponse {
+        let mut choices: Vec<Choice> = self
+            .choices
+            .into_iter()
+            .map(|(index, choice)| {
+                let tool_calls = choice
+                    .tool_calls
+                    .into_values()
+                    .map(|call| ToolCall::function(call.id, call.name, call.arguments))
+                    .collect();
+                let mut message = Message::assistant(choice.text).with_tool_calls(tool_calls);
+                message.role = choice.role.unwrap_or(Role::Assistant);
+                Choice {
+                    index,
+                    message,
+                    finish_reason: choice.finish_reason,
+                }
+            })
+            .collect();
+        if choices.is_empty() {
+            choices.push(Choice {
+                index: 0,
+                message: Message::assistant(""),
+                finish_reason: None,
+            });
+        }
+
+        CompletionResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: self.model.clone(),
+            choices,
+            usage: self.usage,
+            metadata: self.provider.map(|provider| ProviderMetadata {
+                provider,
+                model: self.model,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Drain a stream into a complete response
+///
+/// Stops at the first error.
+pub async fn aggregate<S>(stream: S) -> Result<CompletionResponse>
+where
+    S: Stream<Item = Result<StreamEvent>>,
+{
+    let mut aggregator = StreamAggregator::new();
+    futures::pin_mut!(stream);
+    while let Some(event) = stream.next().await {
+        aggregator.push(event?);
+    }
+    Ok(aggregator.finish())
+}
+
+/// End a stream after its first error
+pub(crate) fn until_error<S>(stream: S) -> impl Stream<Item = Result<StreamEvent>>
+where
+    S: Stream<Item = Result<StreamEvent>>,
+{
+    stream.scan(false, |failed, item| {
+        let item = if *failed { None } else { Some(item) };
+        *failed = matches!(item, Some(Err(_)));
+        async move { item }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ConnectorError;
+    use futures::stream;
+
+    fn tool_fragment(
+        index: u32,
+        id: Option<&str>,
+        name: Option<&str>,
+        arguments: &str,
+    ) -> StreamEvent {
+        StreamEvent::delta(ChunkDelta::ToolCall {
+            index,
+            id: id.map(str::to_string),
+            name: name.map(str::to_string),
+            arguments: arguments.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_interleaved_tool_calls() {
+        let mut aggregator = StreamAggregator::new().with_provider("openai");
+        for event in [
+            StreamEvent::Start {
+                id: "chatcmpl-1".to_string(),
+                model: "gpt-4o".to_string(),
+            },
+            StreamEvent::delta(ChunkDelta::Role {
+                role: Role::Assistant,
+            }),
+            tool_fragment(0, Some("call_a"), Some("get_weather"), ""),
+            tool_fragment(1, Some("call_b"), Some("get_time"), "{\"tz\":"),
+            tool_fragment(0, None, None, "{\"city\":"),
+            tool_fragment(1, None, None, "\"UTC\"}"),
+            tool_fragment(0, None, None, "\"Paris\"}"),
+            StreamEvent::delta(ChunkDelta::Finish {
+                reason: FinishReason::ToolCalls,
+            }),
+            StreamEvent::delta(ChunkDelta::Usage {
+                usage: Usage::new(40, 12),
+            }),
+        ] {
+            aggregator.push(event);
+        }
+
+        let response = aggregator.finish();
+        assert_eq!(response.id, "chatcmpl-1");
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            vec![
+                ToolCall::function("call_a", "get_weather", "{\"city\":\"Paris\"}"),
+                ToolCall::function("call_b", "get_time", "{\"tz\":\"UTC\"}"),
+            ]
+        );
+        assert_eq!(response.usage, Usage::new(40, 12));
+        assert_eq!(response.metadata.unwrap().provider, "openai");
+    }
+
+    #[test]
+    fn test_text_and_split_usage() {
+        let mut aggregator = StreamAggregator::new();
+        aggregator.push(StreamEvent::delta(ChunkDelta::Usage {
+            usage: Usage::new(25, 1),
+        }));
+        aggregator.push(StreamEvent::text("Hel"));
+        aggregator.push(StreamEvent::text("lo"));
+        assert_eq!(aggregator.text(), "Hello");
+        aggregator.push(StreamEvent::delta(ChunkDelta::Usage {
+            usage: Usage::new(0, 15),
+        }));
+
+        let response = aggregator.finish();
+        assert_eq!(response.text().as_deref(), Some("Hello"));
+        assert_eq!(response.usage, Usage::new(25, 15));
+        assert_eq!(response.finish_reason(), None);
+
+        // Counts straight from the provider must not overflow the total
+        let mut aggregator = StreamAggregator::new();
+        aggregator.push(StreamEvent::delta(ChunkDelta::Usage {
+            usage: Usage::new(u32::MAX, u32::MAX),
+        }));
+        assert_eq!(aggregator.finish().usage.total_tokens, u32::MAX);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let mut aggregator = StreamAggregator::new().with_provider("mock");
+        aggregator.push(StreamEvent::Start {
+            id: "r1".to_string(),
+            model: "m".to_string(),
+        });
+        aggregator.push(StreamEvent::text("Hi"));
+        aggregator.push(tool_fragment(0, Some("c1"), Some("f"), "{}"));
+        aggregator.push(StreamEvent::delta(ChunkDelta::Finish {
+            reason: FinishReason::Stop,
+        }));
+        let original = aggregator.finish();
+
+        let mut replay = StreamAggregator::new().with_provider("mock");
+        for event in events_from_response(&original) {
+            replay.push(event);
+        }
+        let replayed = replay.finish();
+        assert_eq!(replayed.choices, original.choices);
+        assert_eq!(replayed.id, original.id);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_stops_at_error() {
+        let events = stream::iter(vec![
+            Ok(StreamEvent::text("partial")),
+            Err(ConnectorError::Internal("connection reset".to_string())),
+            Ok(StreamEvent::text("never")),
+        ]);
+        assert!(aggregate(events).await.is_err());
+
+        let events = stream::iter(vec![Ok(StreamEvent::text("a")), Ok(StreamEvent::text("b"))]);
+        assert_eq!(
+            aggregate(events).await.unwrap().text().as_deref(),
+            Some("ab")
+        );
+    }
+
+    #[test]
+    fn test_event_serde_shape() {
+        let value = serde_json::to_value(StreamEvent::text("x")).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"event": "delta", "index": 0, "delta": {"type": "text", "text": "x"}})
+        );
+    }
+}
@@ -0,0 +1,546 @@
+//! AWS event-stream decoding.
+//!
+//! Binary `application/vnd.amazon.eventstream` framing, used by Bedrock's
+//! `ConverseStream` and `InvokeModelWithResponseStream`. Each message is:
+//!
+//! ```text
+//! total length (u32) | headers length (u32) | prelude CRC32 (u32)
+//! headers | payload | message CRC32 (u32)
+//! ```
+//!
+//! All integers are big-endian. Both CRCs are verified; a mismatch is an
+//! error, since the stream can no longer be trusted.
+
+use crate::error::{ConnectorError, ProviderError, Result};
+use base64::Engine;
+use bytes::{Buf, Bytes, BytesMut};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Length of the prelude, including its CRC
+const PRELUDE_LEN: usize = 12;
+
+/// Smallest valid message: prelude plus message CRC
+const MIN_MESSAGE_LEN: usize = PRELUDE_LEN + 4;
+
+/// Largest message accepted (16 MiB), guarding against corrupt lengths
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Typed header value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderValue {
+    /// Boolean (types 0 and 1)
+    Bool(bool),
+    /// Signed byte
+    Byte(i8),
+    /// Signed 16-bit integer
+    Short(i16),
+    /// Signed 32-bit integer
+    Int(i32),
+    /// Signed 64-bit integer
+    Long(i64),
+    /// Byte array
+    Bytes(Bytes),
+    /// UTF-8 string
+    String(String),
+    /// Milliseconds since the Unix epoch
+    Timestamp(i64),
+    /// UUID
+    Uuid([u8; 16]),
+}
+
+impl HeaderValue {
+    /// String value, if this is a string header
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            HeaderValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// One decoded event-stream message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Headers in wire order
+    pub headers: Vec<(String, HeaderValue)>,
+    /// Raw payload
+    pub payload: Bytes,
+}
+
+impl Message {
+    /// First header with the given name
+    pub fn header(&self, name: &str) -> Option<&HeaderValue> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value)
+    }
+
+    /// String header value
+    pub fn header_str(&self, name: &str) -> Option<&str> {
+        self.header(name).and_then(HeaderValue::as_str)
+    }
+
+    /// Classify the message by its `:message-type` header
+    pub fn into_payload(self) -> Result<EventPayload> {
+        let header = |name: &str| self.header_str(name).unwrap_or_default().to_string();
+        match self.header_str(":message-type") {
+            Some("event") => {
+                let event_type = header(":event-type");
+                let payload = if event_type == "chunk" {
+                    unwrap_chunk(&self.payload)?
+                } else {
+                    self.payload
+                };
+                Ok(EventPayload::Event {
+                    event_type,
+                    payload,
+                })
+            }
+            Some("exception") => Ok(EventPayload::Exception {
+                exception_type: header(":exception-type"),
+                message: payload_message(&self.payload),
+            }),
+            Some("error") => Ok(EventPayload::Error {
+                code: header(":error-code"),
+                message: header(":error-message"),
+            }),
+            other => Err(ConnectorError::Internal(format!(
+                "Unknown event-stream message type: {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Message classified by type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventPayload {
+    /// A modeled event
+    ///
+    /// For `chunk` events the base64 `bytes` field is decoded, so `payload`
+    /// is the model's own JSON.
+    Event {
+        /// `:event-type` header
+        event_type: String,
+        /// Event payload
+        payload: Bytes,
+    },
+    /// A modeled exception (`throttlingException`, ...)
+    Exception {
+        /// `:exception-type` header
+        exception_type: String,
+        /// Exception message from the payload
+        message: String,
+    },
+    /// An unmodeled service error
+    Error {
+        /// `:error-code` header
+        code: String,
+        /// `:error-message` header
+        message: String,
+    },
+}
+
+/// `chunk` event payload
+#[derive(Deserialize)]
+struct Chunk {
+    bytes: String,
+}
+
+/// Decode the base64 `bytes` field of a `chunk` payload
+fn unwrap_chunk(payload: &[u8]) -> Result<Bytes> {
+    let chunk: Chunk = serde_json::from_slice(payload)
+        .map_err(|e| ConnectorError::Internal(format!("Invalid event-stream chunk: {}", e)))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(chunk.bytes)
+        .map(Bytes::from)
+        .map_err(|e| ConnectorError::Internal(format!("Invalid event-stream chunk bytes: {}", e)))
+}
+
+/// `message` (or `Message`) field of an exception payload
+fn payload_message(payload: &[u8]) -> String {
+    let value: serde_json::Value = serde_json::from_slice(payload).unwrap_or_default();
+    value
+        .get("message")
+        .or_else(|| value.get("Message"))
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| String::from_utf8_lossy(payload).into_owned())
+}
+
+/// Incremental event-stream decoder
+#[derive(Debug, Clone, Default)]
+pub struct EventStreamDecoder {
+    buffer: BytesMut,
+}
+
+impl EventStreamDecoder {
+    /// Create a decoder at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of buffered bytes not yet forming a complete message
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Feed bytes and return the messages they complete
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<Message>> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut messages = Vec::new();
+        while self.buffer.len() >= PRELUDE_LEN {
+            let total_len = read_u32(&self.buffer[0..4]) as usize;
+            let headers_len = read_u32(&self.buffer[4..8]) as usize;
+            let prelude_crc = read_u32(&self.buffer[8..12]);
+
+            let actual = crc32fast::hash(&self.buffer[..8]);
+            if actual != prelude_crc {
+                return Err(ConnectorError::Internal(format!(
+                    "Event-stream prelude CRC mismatch: expected {:08x}, got {:08x}",
+                    prelude_crc, actual
+                )));
+            }
+            if !(MIN_MESSAGE_LEN..=MAX_MESSAGE_LEN).contains(&total_len)
+                || headers_len > total_len - MIN_MESSAGE_LEN
+            {
+                return Err(ConnectorError::Internal(format!(
+                    "Invalid event-stream lengths: total {}, headers {}",
+                    total_len, headers_len
+                )));
+            }
+            if self.buffer.len() < total_len {
+                break;
+            }
+
+            let frame = self.buffer.split_to(total_len).freeze();
+            messages.push(parse_message(frame, headers_len)?);
+        }
+        Ok(messages)
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Parse a complete frame whose prelude has been checked
+fn parse_message(frame: Bytes, headers_len: usize) -> Result<Message> {
+    let body_end = frame.len() - 4;
+    let message_crc = read_u32(&frame[body_end..]);
+    let actual = crc32fast::hash(&frame[..body_end]);
+    if actual != message_crc {
+        return Err(ConnectorError::Internal(format!(
+            "Event-stream message CRC mismatch: expected {:08x}, got {:08x}",
+            message_crc, actual
+        )));
+    }
+
+    let headers_end = PRELUDE_LEN + headers_len;
+    let headers = parse_headers(frame.slice(PRELUDE_LEN..headers_end))?;
+    Ok(Message {
+        headers,
+        payload: frame.slice(headers_end..body_end),
+    })
+}
+
+fn parse_headers(mut bytes: Bytes) -> Result<Vec<(String, HeaderValue)>> {
+    let truncated = || ConnectorError::Internal("Truncated event-stream header".to_string());
+    let need = |bytes: &Bytes, len: usize| {
+        if bytes.remaining() < len {
+            Err(truncated())
+        } else {
+            Ok(())
+        }
+    };
+    let utf8 = |raw: Bytes| {
+        String::from_utf8(raw.to_vec()).map_err(|_| {
+            ConnectorError::Internal("Invalid UTF-8 in event-stream header".to_string())
+        })
+    };
+
+    let mut headers = Vec::new();
+    while bytes.has_remaining() {
+        let name_len = bytes.get_u8() as usize;
+        need(&bytes, name_len + 1)?;
+        let name = utf8(bytes.split_to(name_len))?;
+
+        let value = match bytes.get_u8() {
+            0 => HeaderValue::Bool(true),
+            1 => HeaderValue::Bool(false),
+            2 => {
+                need(&bytes, 1)?;
+                HeaderValue::Byte(bytes.get_i8())
+            }
+            3 => {
+                need(&bytes, 2)?;
+                HeaderValue::Short(bytes.get_i16())
+            }
+            4 => {
+                need(&bytes, 4)?;
+                HeaderValue::Int(bytes.get_i32())
+            }
+            5 => {
+                need(&bytes, 8)?;
+                HeaderValue::Long(bytes.get_i64())
+            }
+            kind @ (6 | 7) => {
+                need(&bytes, 2)?;
+                let len = bytes.get_u16() as usize;
+                need(&bytes, len)?;
+                let raw = bytes.split_to(len);
+                if kind == 6 {
+                    HeaderValue::Bytes(raw)
+                } else {
+                    HeaderValue::String(utf8(raw)?)
+                }
+            }
+            8 => {
+                need(&bytes, 8)?;
+                HeaderValue::Timestamp(bytes.get_i64())
+            }
+            9 => {
+                need(&bytes, 16)?;
+                let mut uuid = [0u8; 16];
+                bytes.copy_to_slice(&mut uuid);
+                HeaderValue::Uuid(uuid)
+            }
+            other => {
+                return Err(ConnectorError::Internal(format!(
+                    "Unknown event-stream header type {} for {}",
+                    other, name
+                )))
+            }
+        };
+        headers.push((name, value));
+    }
+    Ok(headers)
+}
+
+/// Decode a byte stream into event-stream messages
+///
+/// Ends at the first error. Trailing bytes that do not form a complete
+/// message when the stream ends are reported as an error.
+pub fn decode<S, B, E>(bytes: S) -> impl Stream<Item = Result<Message>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut decoder = EventStreamDecoder::new();
+    let mut failed = false;
+    bytes
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .flat_map(move |chunk| {
+            let items: Vec<Result<Message>> = if failed {
+                Vec::new()
+            } else {
+                match chunk {
+                    Some(Ok(chunk)) => match decoder.push(chunk.as_ref()) {
+                        Ok(messages) => messages.into_iter().map(Ok).collect(),
+                        Err(e) => vec![Err(e)],
+                    },
+                    Some(Err(e)) => vec![Err(ConnectorError::StreamInterrupted(
+                        ProviderError::new("", format!("Event stream error: {}", e)),
+                    ))],
+                    None if decoder.buffered() > 0 => {
+                        vec![Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                            "",
+                            format!(
+                                "Event stream ended inside a message ({} bytes buffered)",
+                                decoder.buffered()
+                            ),
+                        )))]
+                    }
+                    None => Vec::new(),
+                }
+            };
+            failed |= items.iter().any(|item| item.is_err());
+            stream::iter(items)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode a hex fixture, ignoring whitespace
+    fn hex_fixture(hex: &str) -> Vec<u8> {
+        let hex: String = hex.split_whitespace().collect();
+        hex::decode(hex).unwrap()
+    }
+
+    /// Frame with no headers and an empty payload (AWS reference vector)
+    const EMPTY_FRAME: &str = "00000010 00000000 05c248eb 7d98c8ff";
+
+    /// One header of every type and a small JSON payload
+    const ALL_HEADERS_FRAME: &str = "
+        0000009d 00000082 4f02dec4 09626f6f 6c2d7472 7565000a 626f6f6c 2d66616c
+        73650104 62797465 02cf0573 686f7274 03fb2e03 696e7404 075bcd15 046c6f6e
+        6705ffff fffdb34f e9160562 79746573 06000200 ff067374 72696e67 07000668
+        c3a96c6c 6f097469 6d657374 616d7008 0000018b cfe56800 04757569 64090001
+        02030405 06070809 0a0b0c0d 0e0f7b22 6f6b223a 74727565 7d77c2be cc";
+
+    /// ConverseStream `contentBlockDelta` event
+    const CONVERSE_DELTA_FRAME: &str = "
+        000000a2 00000057 9acad7c8 0b3a6576 656e742d 74797065 07001163 6f6e7465
+        6e74426c 6f636b44 656c7461 0d3a636f 6e74656e 742d7479 70650700 10617070
+        6c696361 74696f6e 2f6a736f 6e0d3a6d 65737361 67652d74 79706507 00056576
+        656e747b 22636f6e 74656e74 426c6f63 6b496e64 6578223a 302c2264 656c7461
+        223a7b22 74657874 223a2248 656c6c6f 227d2c22 70223a22 61626364 227d1ee2
+        dd14";
+
+    /// InvokeModelWithResponseStream `chunk` wrapping a base64 Anthropic
+    /// `content_block_delta`
+    const INVOKE_CHUNK_FRAME: &str = "
+        000000e2 0000004b d638d3ce 0b3a6576 656e742d 74797065 07000563 68756e6b
+        0d3a636f 6e74656e 742d7479 70650700 10617070 6c696361 74696f6e 2f6a736f
+        6e0d3a6d 65737361 67652d74 79706507 00056576 656e747b 22627974 6573223a
+        2265794a 30655842 6c496a6f 69593239 75644756 75644639 69624739 6a613139
+        6b5a5778 30595349 73496d6c 755a4756 34496a6f 774c434a 6b5a5778 30595349
+        3665794a 30655842 6c496a6f 69644756 34644639 6b5a5778 30595349 73496e52
+        6c654851 694f694a 4961534a 3966513d 3d222c22 70223a22 61626364 227d528e
+        cb0b";
+
+    /// `throttlingException` with a JSON message payload
+    const EXCEPTION_FRAME: &str = "
+        00000090 00000061 8e91a9b7 0f3a6578 63657074 696f6e2d 74797065 07001374
+        68726f74 746c696e 67457863 65707469 6f6e0d3a 636f6e74 656e742d 74797065
+        07001061 70706c69 63617469 6f6e2f6a 736f6e0d 3a6d6573 73616765 2d747970
+        65070009 65786365 7074696f 6e7b226d 65737361 6765223a 22546f6f 206d616e
+        79207265 71756573 7473227d e67f8005";
+
+    fn decode_all(bytes: &[u8]) -> Result<Vec<Message>> {
+        EventStreamDecoder::new().push(bytes)
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        let messages = decode_all(&hex_fixture(EMPTY_FRAME)).unwrap();
+        assert_eq!(
+            messages,
+            vec![Message {
+                headers: Vec::new(),
+                payload: Bytes::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_all_header_types() {
+        let message = decode_all(&hex_fixture(ALL_HEADERS_FRAME))
+            .unwrap()
+            .remove(0);
+        let expected = vec![
+            ("bool-true", HeaderValue::Bool(true)),
+            ("bool-false", HeaderValue::Bool(false)),
+            ("byte", HeaderValue::Byte(-49)),
+            ("short", HeaderValue::Short(-1234)),
+            ("int", HeaderValue::Int(123456789)),
+            ("long", HeaderValue::Long(-9876543210)),
+            (
+                "bytes",
+                HeaderValue::Bytes(Bytes::from_static(&[0x00, 0xff])),
+            ),
+            ("string", HeaderValue::String("héllo".to_string())),
+            ("timestamp", HeaderValue::Timestamp(1700000000000)),
+            ("uuid", HeaderValue::Uuid(std::array::from_fn(|i| i as u8))),
+        ];
+        let expected: Vec<(String, HeaderValue)> = expected
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        assert_eq!(message.headers, expected);
+        assert_eq!(message.payload, Bytes::from_static(b"{\"ok\":true}"));
+        assert_eq!(message.header_str("string"), Some("héllo"));
+    }
+
+    #[test]
+    fn test_event_payload_split_anywhere() {
+        let frame = hex_fixture(CONVERSE_DELTA_FRAME);
+        for cut in 0..=frame.len() {
+            let mut decoder = EventStreamDecoder::new();
+            let mut messages = decoder.push(&frame[..cut]).unwrap();
+            messages.extend(decoder.push(&frame[cut..]).unwrap());
+            assert_eq!(messages.len(), 1, "split at byte {}", cut);
+            assert_eq!(decoder.buffered(), 0);
+
+            match messages.remove(0).into_payload().unwrap() {
+                EventPayload::Event {
+                    event_type,
+                    payload,
+                } => {
+                    assert_eq!(event_type, "contentBlockDelta");
+                    let value: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+                    assert_eq!(value["delta"]["text"], "Hello");
+                }
+                other => panic!("unexpected payload {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunk_bytes_are_unwrapped() {
+        let message = decode_all(&hex_fixture(INVOKE_CHUNK_FRAME))
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            message.into_payload().unwrap(),
+            EventPayload::Event {
+                event_type: "chunk".to_string(),
+                payload: Bytes::from_static(
+                    br#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_exception_payload() {
+        let message = decode_all(&hex_fixture(EXCEPTION_FRAME)).unwrap().remove(0);
+        assert_eq!(
+            message.into_payload().unwrap(),
+            EventPayload::Exception {
+                exception_type: "throttlingException".to_string(),
+                message: "Too many requests".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_crc_mismatches() {
+        let mut frame = hex_fixture(CONVERSE_DELTA_FRAME);
+        let last = frame.len() - 10;
+        frame[last] ^= 0x01;
+        let err = decode_all(&frame).unwrap_err();
+        assert!(err.to_string().contains("message CRC mismatch"));
+
+        let mut frame = hex_fixture(CONVERSE_DELTA_FRAME);
+        frame[3] ^= 0x01;
+        let err = decode_all(&frame).unwrap_err();
+        assert!(err.to_string().contains("prelude CRC mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_stream_reports_truncation() {
+        let mut bytes = hex_fixture(EMPTY_FRAME);
+        bytes.extend(hex_fixture(CONVERSE_DELTA_FRAME));
+        bytes.truncate(bytes.len() - 5);
+        let chunks: Vec<std::result::Result<Vec<u8>, String>> =
+            bytes.chunks(9).map(|chunk| Ok(chunk.to_vec())).collect();
+
+        let items: Vec<Result<Message>> = decode(stream::iter(chunks)).collect().await;
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_ok());
+        assert!(items[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("ended inside a message"));
+    }
+}
@@ -0,0 +1,480 @@
+//! Gemini `streamGenerateContent` parsing.
+//!
+//! Without `alt=sse`, Gemini streams a single JSON array whose elements are
+//! `GenerateContentResponse` objects, delivered as they are generated:
+//!
+//! ```text
+//! [{"candidates": [...]}
+//! ,{"candidates": [...]}
+//! ]
+//! ```
+//!
+//! [`JsonArrayDecoder`] yields each element as soon as it is complete and
+//! [`GeminiStreamParser`] translates elements into [`StreamEvent`]s.
+
+use super::delta::{until_error, ChunkDelta, StreamEvent};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::providers::errors;
+use crate::providers::google::{map_finish_reason, GenerateContentResponse, Part};
+use crate::types::{FinishReason, Role, Usage};
+use bytes::{Buf, Bytes, BytesMut};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// Largest array element buffered (16 MiB), guarding against unterminated
+/// elements
+const MAX_ELEMENT_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum State {
+    /// Before the opening `[`
+    #[default]
+    Start,
+    /// Expecting an element, or `]` if the array may end here
+    Element { may_close: bool },
+    /// Inside an element
+    Value,
+    /// After an element, expecting `,` or `]`
+    Separator,
+    /// After the closing `]`
+    Done,
+}
+
+/// Incremental splitter for a streamed JSON array
+///
+/// Only the element being received is buffered, up to 16 MiB. Elements
+/// must be objects or arrays; they are returned as raw JSON, not validated
+/// beyond bracket and string structure.
+#[derive(Debug, Clone, Default)]
+pub struct JsonArrayDecoder {
+    buffer: BytesMut,
+    state: State,
+    /// Bytes of the current element already scanned
+    scanned: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl JsonArrayDecoder {
+    /// Create a decoder at the start of an array
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the closing `]` has been seen
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Feed bytes, returning the elements they complete
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<Bytes>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut elements = Vec::new();
+        let mut i = self.scanned;
+
+        while i < self.buffer.len() {
+            let byte = self.buffer[i];
+            match self.state {
+                State::Value => {
+                    if self.in_string {
+                        match byte {
+                            _ if self.escaped => self.escaped = false,
+                            b'\\' => self.escaped = true,
+                            b'"' => self.in_string = false,
+                            _ => {}
+                        }
+                    } else {
+                        match byte {
+                            b'"' => self.in_string = true,
+                            b'{' | b'[' => self.depth += 1,
+                            b'}' | b']' => {
+                                self.depth -= 1;
+                                if self.depth == 0 {
+                                    elements.push(self.buffer.split_to(i + 1).freeze());
+                                    self.state = State::Separator;
+                                    i = 0;
+                                    continue;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ if byte.is_ascii_whitespace() => {}
+                State::Start if byte == b'[' => self.state = State::Element { may_close: true },
+                State::Element { may_close: true } | State::Separator if byte == b']' => {
+                    self.state = State::Done
+                }
+                State::Element { .. } if byte == b'{' || byte == b'[' => {
+                    // Drop the whitespace and separators before the element
+                    self.buffer.advance(i);
+                    i = 0;
+                    self.state = State::Value;
+                    self.depth = 1;
+                }
+                State::Separator if byte == b',' => {
+                    self.state = State::Element { may_close: false }
+                }
+                state => {
+                    return Err(ConnectorError::Internal(format!(
+                        "Unexpected byte {:?} in JSON array stream ({:?})",
+                        char::from(byte),
+                        state
+                    )))
+                }
+            }
+            i += 1;
+        }
+
+        if self.state == State::Value {
+            if self.buffer.len() > MAX_ELEMENT_LEN {
+                return Err(ConnectorError::Internal(format!(
+                    "JSON array stream element exceeds {} bytes",
+                    MAX_ELEMENT_LEN
+                )));
+            }
+            self.scanned = self.buffer.len();
+        } else {
+            self.buffer.clear();
+            self.scanned = 0;
+        }
+        Ok(elements)
+    }
+}
+
+/// One array element: a response, or an error raised mid-stream
+#[derive(Debug, Clone, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    error: Option<StreamError>,
+    #[serde(flatten)]
+    response: GenerateContentResponse,
+}
+
+/// Google API `error` object
+#[derive(Debug, Clone, Default, Deserialize)]
+struct StreamError {
+    #[serde(default)]
+    code: u16,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    status: String,
+}
+
+impl StreamError {
+    fn into_error(self) -> ConnectorError {
+        let mut error = ProviderError::new("google", format!("{}: {}", self.status, self.message));
+        if self.code > 0 {
+            error = error.with_status(self.code);
+        }
+        errors::google_status(&self.status, error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CandidateState {
+    has_output: bool,
+    tool_calls: u32,
+}
+
+/// Converts `GenerateContentResponse` elements into stream events
+#[derive(Debug, Clone)]
+pub struct GeminiStreamParser {
+    model: String,
+    started: bool,
+    candidates: BTreeMap<u32, CandidateState>,
+    stopped: bool,
+}
+
+impl GeminiStreamParser {
+    /// Create a parser for a stream from `model`
+    ///
+    /// The model is reported unless the stream carries a `modelVersion`.
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            started: false,
+            candidates: BTreeMap::new(),
+            stopped: false,
+        }
+    }
+
+    /// Whether a safety block has ended the stream
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Parse one array element
+    ///
+    /// A blocked prompt, or a candidate blocked before producing output, is
+    /// returned as [`ConnectorError::ContentFiltered`]. A candidate blocked
+    /// after producing output finishes with [`FinishReason::ContentFilter`]
+    /// and stops the parser.
+    pub fn parse(&mut self, element: &[u8]) -> Result<Vec<StreamEvent>> {
+        let chunk: StreamChunk = serde_json::from_slice(element)
+            .map_err(|e| ConnectorError::Internal(format!("Invalid Gemini stream chunk: {}", e)))?;
+        if let Some(error) = chunk.error {
+            return Err(error.into_error());
+        }
+        let response = chunk.response;
+        if let Some(error) = response
+            .prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_error())
+        {
+            return Err(error);
+        }
+
+        let mut events = Vec::new();
+        if !self.started {
+            self.started = true;
+            events.push(StreamEvent::Start {
+                id: format!("gemini-{}", uuid::Uuid::new_v4()),
+                model: response
+                    .model_version
+                    .clone()
+                    .unwrap_or_else(|| self.model.clone()),
+            });
+        }
+
+        for candidate in &response.candidates {
+            let index = candidate.index;
+            if !self.candidates.contains_key(&index) {
+                events.push(StreamEvent::Delta {
+                    index,
+                    delta: ChunkDelta::Role {
+                        role: Role::Assistant,
+                    },
+                });
+            }
+            let state = self.candidates.entry(index).or_default();
+
+            for part in candidate.content.iter().flat_map(|content| &content.parts) {
+                let delta = match part {
+                    Part::Text { text } if !text.is_empty() => {
+                        ChunkDelta::Text { text: text.clone() }
+                    }
+                    Part::FunctionCall { function_call } => {
+                        let call = state.tool_calls;
+                        state.tool_calls += 1;
+                        ChunkDelta::ToolCall {
+                            index: call,
+                            id: Some(format!("call_{}_{}", index, call)),
+                            name: Some(function_call.name.clone()),
+                            arguments: function_call.args.to_string(),
+                        }
+                    }
+                    _ => continue,
+                };
+                state.has_output = true;
+                events.push(StreamEvent::Delta { index, delta });
+            }
+
+            if let Some(error) = candidate.block_error(state.has_output) {
+                return Err(error);
+            }
+            let reason = match candidate
+                .finish_reason
+                .as_deref()
+                .and_then(map_finish_reason)
+            {
+                Some(FinishReason::ContentFilter) => {
+                    self.stopped = true;
+                    Some(FinishReason::ContentFilter)
+                }
+                Some(_) if state.tool_calls > 0 => Some(FinishReason::ToolCalls),
+                reason => reason,
+            };
+            if let Some(reason) = reason {
+                events.push(StreamEvent::Delta {
+                    index,
+                    delta: ChunkDelta::Finish { reason },
+                });
+            }
+        }
+
+        if let Some(usage) = response.usage_metadata {
+            events.push(StreamEvent::delta(ChunkDelta::Usage {
+                usage: Usage::from(usage),
+            }));
+        }
+        Ok(events)
+    }
+}
+
+/// Decode a `streamGenerateContent` JSON-array byte stream
+///
+/// Ends at the closing `]`, at a safety block or at the first error.
+pub fn decode<S, B, E>(bytes: S, model: &str) -> impl Stream<Item = Result<StreamEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let state = (JsonArrayDecoder::new(), GeminiStreamParser::new(model));
+    let events = bytes
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .scan(state, |(decoder, parser), chunk| {
+            if parser.is_stopped() {
+                return future::ready(None);
+            }
+            let items: Vec<Result<StreamEvent>> = match chunk {
+                Some(Ok(chunk)) => match decoder.push(chunk.as_ref()) {
+                    Ok(elements) => {
+                        let mut items = Vec::new();
+                        for element in elements {
+                            match parser.parse(&element) {
+                                Ok(events) => items.extend(events.into_iter().map(Ok)),
+                                Err(e) => items.push(Err(e)),
+                            }
+                            if parser.is_stopped() {
+                                break;
+                            }
+                        }
+                        items
+                    }
+                    Err(e) => vec![Err(e)],
+                },
+                Some(Err(e)) => vec![Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                    "google",
+                    format!("stream error: {}", e),
+                )))],
+                None if !decoder.is_complete() => {
+                    vec![Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                        "google",
+                        "stream ended before the closing ]",
+                    )))]
+                }
+                None => Vec::new(),
+            };
+            future::ready(Some(stream::iter(items)))
+        })
+        .flatten();
+    until_error(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::delta::aggregate;
+    use crate::types::ToolCall;
+
+    fn split(body: &str, size: usize) -> Vec<std::result::Result<&[u8], String>> {
+        body.as_bytes().chunks(size).map(Ok).collect()
+    }
+
+    #[test]
+    fn test_json_array_decoder_yields_elements_as_they_complete() {
+        let mut decoder = JsonArrayDecoder::new();
+        assert!(decoder.push(b" [{\"a\":\"}{[\\\"\"").unwrap().is_empty());
+        let elements = decoder.push(b", \"b\": [1, {}]}\r\n,").unwrap();
+        assert_eq!(elements.len(), 1);
+        assert_eq!(&elements[0][..], b"{\"a\":\"}{[\\\"\", \"b\": [1, {}]}");
+        assert!(!decoder.is_complete());
+
+        let elements = decoder.push(b"{}]").unwrap();
+        assert_eq!(elements, vec![Bytes::from_static(b"{}")]);
+        assert!(decoder.is_complete());
+        assert!(decoder.push(b"\n").unwrap().is_empty());
+
+        let mut empty = JsonArrayDecoder::new();
+        assert!(empty.push(b"[ ]").unwrap().is_empty());
+        assert!(empty.is_complete());
+
+        assert!(JsonArrayDecoder::new().push(b"{}").is_err());
+        assert!(JsonArrayDecoder::new().push(b"[{},]").is_err());
+        assert!(JsonArrayDecoder::new().push(b"[1]").is_err());
+    }
+
+    #[test]
+    fn test_json_array_decoder_limits_element_size() {
+        let mut decoder = JsonArrayDecoder::new();
+        decoder.push(b"[{\"text\": \"").unwrap();
+        let chunk = vec![b'a'; 1024 * 1024];
+        for _ in 0..15 {
+            decoder.push(&chunk).unwrap();
+        }
+        let error = decoder.push(&chunk).unwrap_err();
+        assert!(error.to_string().contains("exceeds 16777216 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_text_and_function_call() {
+        let body = concat!(
+            "[{\"candidates\": [{\"content\": {\"role\": \"model\", \"parts\": [{\"text\": \"Let me \"}]}, \"index\": 0}],\n",
+            "  \"usageMetadata\": {\"promptTokenCount\": 4}, \"modelVersion\": \"gemini-1.5-pro-002\"}\n",
+            ",\r\n{\"candidates\": [{\"content\": {\"role\": \"model\", \"parts\": [{\"text\": \"check.\"}, ",
+            "{\"functionCall\": {\"name\": \"lookup\", \"args\": {\"q\": \"[hi]\"}}}]}, \"finishReason\": \"STOP\", \"index\": 0}],\n",
+            "  \"usageMetadata\": {\"promptTokenCount\": 4, \"candidatesTokenCount\": 6, \"totalTokenCount\": 10}}\n",
+            "]",
+        );
+
+        let response = aggregate(decode(stream::iter(split(body, 11)), "gemini-pro"))
+            .await
+            .unwrap();
+        assert!(response.id.starts_with("gemini-"));
+        assert_eq!(response.model, "gemini-1.5-pro-002");
+        assert_eq!(response.text().as_deref(), Some("Let me check."));
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            vec![ToolCall::function("call_0_0", "lookup", "{\"q\":\"[hi]\"}")]
+        );
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(response.usage, Usage::new(4, 6));
+    }
+
+    #[tokio::test]
+    async fn test_decode_safety_block_terminates_stream() {
+        let body = concat!(
+            "[{\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Once upon\"}]}, \"index\": 0}]}",
+            ",{\"candidates\": [{\"finishReason\": \"SAFETY\", \"index\": 0, \"safetyRatings\": ",
+            "[{\"category\": \"HARM_CATEGORY_DANGEROUS_CONTENT\", \"probability\": \"HIGH\", \"blocked\": true}]}]}",
+            ",{\"candidates\": [{\"content\": {\"parts\": [{\"text\": \" never sent\"}]}, \"index\": 0}]}",
+        );
+
+        let items: Vec<Result<StreamEvent>> = decode(stream::iter(split(body, 16)), "gemini-pro")
+            .collect()
+            .await;
+        assert!(items.iter().all(|item| item.is_ok()));
+        let response = aggregate(stream::iter(items)).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("Once upon"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::ContentFilter));
+
+        // Blocked before any output
+        let body = "[{\"candidates\": [{\"finishReason\": \"SAFETY\", \"index\": 0}]}]";
+        let err = aggregate(decode(stream::iter(split(body, 5)), "gemini-pro"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::ContentFiltered(_)));
+
+        let body = "[{\"promptFeedback\": {\"blockReason\": \"SAFETY\"}}]";
+        let err = aggregate(decode(stream::iter(split(body, 5)), "gemini-pro"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::ContentFiltered(_)));
+    }
+
+    #[tokio::test]
+    async fn test_decode_errors() {
+        let body = "[{\"error\": {\"code\": 429, \"message\": \"Quota exceeded\", \"status\": \"RESOURCE_EXHAUSTED\"}}]";
+        let err = aggregate(decode(stream::iter(split(body, 9)), "gemini-pro"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ConnectorError::RateLimited { ref error, .. } if error.message.contains("Quota exceeded"))
+        );
+        assert_eq!(err.status(), Some(429));
+
+        let body = "[{\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hi\"}]}}]}";
+        let err = aggregate(decode(stream::iter(split(body, 9)), "gemini-pro"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("closing ]"));
+    }
+}
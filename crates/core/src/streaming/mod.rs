@@ -0,0 +1,46 @@
+//! # Streaming
+//!
+//! Incremental decoders for the wire formats providers stream responses in,
+//! and the typed [`StreamEvent`] model they produce.
+//!
+//! Decoders are push-based: feed them bytes as they arrive, in whatever
+//! chunks the transport delivers, and collect the complete events they
+//! yield. Each decoder also has a `Stream` adapter for byte streams such as
+//! `reqwest::Response::bytes_stream()`.
+//!
+//! Provider parsers turn those events into [`StreamEvent`]s, and a
+//! [`StreamAggregator`] folds a stream of them back into a complete
+//! [`CompletionResponse`](crate::types::CompletionResponse).
+//!
+//! - [`sse`] - Server-Sent Events (OpenAI, Anthropic, Mistral, ...)
+//! - [`openai`] - OpenAI chat-completion chunks
+//! - [`anthropic`] - Anthropic Messages events
+//! - [`cohere`] - Cohere Chat v2 events
+//! - [`eventstream`] - AWS binary event-stream framing (Bedrock)
+//! - [`gemini`] - Gemini `streamGenerateContent` JSON arrays
+//! - [`delta`] - `StreamEvent`/`ChunkDelta` and `StreamAggregator`
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::streaming::aggregate;
+//!
+//! let stream = provider.stream(&request).await?;
+//! let response = aggregate(stream).await?;
+//! ```
+
+pub mod anthropic;
+pub mod cohere;
+pub mod delta;
+pub mod eventstream;
+pub mod gemini;
+pub mod openai;
+pub mod sse;
+
+pub use anthropic::AnthropicStreamParser;
+pub use cohere::CohereStreamParser;
+pub use delta::{aggregate, events_from_response, ChunkDelta, StreamAggregator, StreamEvent};
+pub use eventstream::{EventPayload, EventStreamDecoder};
+pub use gemini::{GeminiStreamParser, JsonArrayDecoder};
+pub use openai::ChatStreamParser;
+pub use sse::{SseDecoder, SseEvent};
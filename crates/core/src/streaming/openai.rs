@@ -0,0 +1,273 @@
+//! OpenAI chat-completions stream parsing.
+//!
+//! Translates `chat.completion.chunk` objects, delivered as Server-Sent
+//! Events and terminated by `data: [DONE]`, into [`StreamEvent`]s. The same
+//! format is spoken by Azure OpenAI, Mistral and most self-hosted servers.
+
+use super::delta::{ChunkDelta, StreamEvent};
+use super::sse::{self, SseEvent};
+use crate::error::{ConnectorError, ProviderError, Result};
+use crate::providers::openai::map_finish_reason;
+use crate::types::{Role, Usage};
+use futures::stream::Stream;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::Display;
+
+/// One `chat.completion.chunk` object
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    usage: Option<ChunkUsage>,
+    /// Error reported mid-stream
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    index: u32,
+    #[serde(default)]
+    delta: ChoiceDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ChoiceDelta {
+    #[serde(default)]
+    role: Option<Role>,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCallDelta>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ToolCallDelta {
+    #[serde(default)]
+    index: u32,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<FunctionDelta>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct FunctionDelta {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct ChunkUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+/// Converts chat-completion chunks into stream events
+#[derive(Debug, Clone, Default)]
+pub struct ChatStreamParser {
+    started: bool,
+}
+
+impl ChatStreamParser {
+    /// Create a parser at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the JSON data of one chunk
+    ///
+    /// The first chunk with an `id` or choices also yields a
+    /// [`StreamEvent::Start`].
+    pub fn parse(&mut self, data: &str) -> Result<Vec<StreamEvent>> {
+        let chunk: ChatChunk = serde_json::from_str(data)
+            .map_err(|e| ConnectorError::Internal(format!("Invalid stream chunk: {}", e)))?;
+        if let Some(error) = chunk.error {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                "", message,
+            )));
+        }
+
+        let mut events = Vec::new();
+        // Azure leads with a `prompt_filter_results` chunk whose `id` and
+        // `model` are empty; take them from the first real chunk
+        if !self.started && (!chunk.id.is_empty() || !chunk.choices.is_empty()) {
+            self.started = true;
+            events.push(StreamEvent::Start {
+                id: chunk.id,
+                model: chunk.model,
+            });
+        }
+
+        for choice in chunk.choices {
+            let index = choice.index;
+            let delta = choice.delta;
+            if let Some(role) = delta.role {
+                events.push(StreamEvent::Delta {
+                    index,
+                    delta: ChunkDelta::Role { role },
+                });
+            }
+            if let Some(text) = delta.content.filter(|text| !text.is_empty()) {
+                events.push(StreamEvent::Delta {
+                    index,
+                    delta: ChunkDelta::Text { text },
+                });
+            }
+            for call in delta.tool_calls {
+                let function = call.function.unwrap_or_default();
+                events.push(StreamEvent::Delta {
+                    index,
+                    delta: ChunkDelta::ToolCall {
+                        index: call.index,
+                        id: call.id,
+                        name: function.name,
+                        arguments: function.arguments.unwrap_or_default(),
+                    },
+                });
+            }
+            if let Some(reason) = choice.finish_reason.as_deref().and_then(map_finish_reason) {
+                events.push(StreamEvent::Delta {
+                    index,
+                    delta: ChunkDelta::Finish { reason },
+                });
+            }
+        }
+
+        if let Some(usage) = chunk.usage {
+            events.push(StreamEvent::delta(ChunkDelta::Usage {
+                usage: Usage::new(usage.prompt_tokens, usage.completion_tokens),
+            }));
+        }
+        Ok(events)
+    }
+
+    /// Parse one Server-Sent Event; `[DONE]` yields nothing
+    pub fn parse_event(&mut self, event: &SseEvent) -> Result<Vec<StreamEvent>> {
+        if event.is_done() {
+            return Ok(Vec::new());
+        }
+        self.parse(&event.data)
+    }
+}
+
+/// Decode an SSE byte stream of chat-completion chunks
+///
+/// Ends at `[DONE]` or at the first error. A stream ending before `[DONE]`
+/// is reported as [`ConnectorError::StreamInterrupted`].
+pub fn decode<S, B, E>(bytes: S) -> impl Stream<Item = Result<StreamEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut parser = ChatStreamParser::new();
+    sse::decode_until(bytes, SseEvent::is_done, "[DONE]", move |event| {
+        parser.parse_event(event)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::delta::aggregate;
+    use crate::types::{FinishReason, ToolCall};
+    use futures::stream::{self, StreamExt};
+
+    #[tokio::test]
+    async fn test_decode_tool_call_stream() {
+        let body = concat!(
+            "data: {\"id\":\"chatcmpl-9\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":null}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-9\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"lookup\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-9\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"q\\\":\"}}]}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-9\",\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"1}\"}}]}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-9\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-9\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":4,\"total_tokens\":13}}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"ignored\": true}\n\n",
+        );
+        let chunks: Vec<std::result::Result<&[u8], String>> =
+            body.as_bytes().chunks(7).map(Ok).collect();
+
+        let response = aggregate(decode(stream::iter(chunks))).await.unwrap();
+        assert_eq!(response.id, "chatcmpl-9");
+        assert_eq!(response.model, "gpt-4o");
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(
+            response.choices[0].message.tool_calls,
+            vec![ToolCall::function("call_1", "lookup", "{\"q\":1}")]
+        );
+        assert_eq!(response.usage.total_tokens, 13);
+    }
+
+    #[tokio::test]
+    async fn test_decode_azure_stream() {
+        let body = concat!(
+            "data: {\"choices\":[],\"created\":0,\"id\":\"\",\"model\":\"\",\"object\":\"\",\"prompt_filter_results\":[{\"prompt_index\":0,\"content_filter_results\":{\"hate\":{\"filtered\":false,\"severity\":\"safe\"},\"self_harm\":{\"filtered\":false,\"severity\":\"safe\"},\"sexual\":{\"filtered\":false,\"severity\":\"safe\"},\"violence\":{\"filtered\":false,\"severity\":\"safe\"}}}]}\n\n",
+            "data: {\"choices\":[{\"content_filter_results\":{},\"delta\":{\"content\":\"\",\"role\":\"assistant\"},\"finish_reason\":null,\"index\":0,\"logprobs\":null}],\"created\":1727000000,\"id\":\"chatcmpl-AzA1\",\"model\":\"gpt-4o-2024-05-13\",\"object\":\"chat.completion.chunk\",\"system_fingerprint\":\"fp_67802d9a6d\"}\n\n",
+            "data: {\"choices\":[{\"content_filter_results\":{\"hate\":{\"filtered\":false,\"severity\":\"safe\"}},\"delta\":{\"content\":\"Hello from Azure\"},\"finish_reason\":null,\"index\":0,\"logprobs\":null}],\"created\":1727000000,\"id\":\"chatcmpl-AzA1\",\"model\":\"gpt-4o-2024-05-13\",\"object\":\"chat.completion.chunk\",\"system_fingerprint\":\"fp_67802d9a6d\"}\n\n",
+            "data: {\"choices\":[{\"content_filter_results\":{},\"delta\":{},\"finish_reason\":\"stop\",\"index\":0,\"logprobs\":null}],\"created\":1727000000,\"id\":\"chatcmpl-AzA1\",\"model\":\"gpt-4o-2024-05-13\",\"object\":\"chat.completion.chunk\",\"system_fingerprint\":\"fp_67802d9a6d\"}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let chunks: Vec<std::result::Result<&[u8], String>> =
+            body.as_bytes().chunks(19).map(Ok).collect();
+
+        let response = aggregate(decode(stream::iter(chunks))).await.unwrap();
+        assert_eq!(response.id, "chatcmpl-AzA1");
+        assert_eq!(response.model, "gpt-4o-2024-05-13");
+        assert_eq!(response.text().as_deref(), Some("Hello from Azure"));
+        assert_eq!(response.finish_reason(), Some(FinishReason::Stop));
+    }
+
+    #[tokio::test]
+    async fn test_decode_reports_missing_done() {
+        let body = concat!(
+            "data: {\"id\":\"c\",\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"id\":\"c\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        );
+        let chunks: Vec<std::result::Result<&[u8], String>> = vec![Ok(body.as_bytes())];
+        let items: Vec<Result<StreamEvent>> = decode(stream::iter(chunks)).collect().await;
+
+        assert_eq!(items.len(), 4);
+        let error = items[3].as_ref().unwrap_err();
+        assert_eq!(error.code(), "stream_interrupted");
+        assert!(error.to_string().contains("[DONE]"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_stops_after_error() {
+        let body = concat!(
+            "data: {\"id\":\"c\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi\"}}]}\n\n",
+            "data: {\"error\":{\"message\":\"overloaded\"}}\n\n",
+            "data: {\"id\":\"c\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"!\"}}]}\n\n",
+        );
+        let chunks: Vec<std::result::Result<&[u8], String>> = vec![Ok(body.as_bytes())];
+        let items: Vec<Result<StreamEvent>> = decode(stream::iter(chunks)).collect().await;
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].as_ref().unwrap().as_text(), Some("Hi"));
+        assert!(items[2]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("overloaded"));
+    }
+}
@@ -0,0 +1,396 @@
+//! Server-Sent Events decoding.
+//!
+//! Implements the event stream parsing rules of the HTML Living Standard
+//! (§9.2.6): `CRLF`, `LF` and `CR` line endings, `event:`/`data:`/`id:`/
+//! `retry:` fields, `:` comment lines, multi-line `data`, and a leading BOM.
+//!
+//! The decoder buffers raw bytes until a full line is available, so input
+//! may be split anywhere, including inside multi-byte UTF-8 sequences.
+
+use crate::error::{ConnectorError, ProviderError, Result};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+
+/// Event type used when an event has no `event:` field
+pub const DEFAULT_EVENT_TYPE: &str = "message";
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A dispatched Server-Sent Event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type (`message` if not set)
+    pub event: String,
+    /// Data lines joined with `\n`
+    pub data: String,
+    /// Last event ID at the time of dispatch
+    pub id: Option<String>,
+}
+
+impl SseEvent {
+    /// Whether this is the OpenAI-style `[DONE]` sentinel
+    pub fn is_done(&self) -> bool {
+        self.data == "[DONE]"
+    }
+
+    /// Decode the data as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_str(&self.data).map_err(|e| {
+            ConnectorError::Internal(format!("Invalid SSE {} event data: {}", self.event, e))
+        })
+    }
+}
+
+/// Incremental Server-Sent Events decoder
+#[derive(Debug, Clone, Default)]
+pub struct SseDecoder {
+    /// Bytes of the current, not yet terminated line
+    line: Vec<u8>,
+    /// The previous chunk ended with CR; a leading LF belongs to that line end
+    after_cr: bool,
+    /// Whether the start of the stream has been checked for a BOM
+    started: bool,
+    event_type: String,
+    data: String,
+    last_event_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseDecoder {
+    /// Create a decoder at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reconnection time in milliseconds, from the last valid `retry:` field
+    pub fn retry(&self) -> Option<u64> {
+        self.retry
+    }
+
+    /// Last event ID seen on the stream
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Feed bytes and return the events they complete
+    pub fn push(&mut self, mut bytes: &[u8]) -> Vec<SseEvent> {
+        if bytes.is_empty() {
+            return Vec::new();
+        }
+
+        if !self.started {
+            // Wait for enough bytes to tell whether the stream starts with a BOM
+            let pending = self.line.len() + bytes.len();
+            if pending < BOM.len() && BOM.starts_with(&[&self.line[..], bytes].concat()) {
+                self.line.extend_from_slice(bytes);
+                return Vec::new();
+            }
+            self.started = true;
+            let mut start = std::mem::take(&mut self.line);
+            start.extend_from_slice(bytes);
+            let start = start.strip_prefix(BOM).unwrap_or(&start).to_vec();
+            return self.push_lines(&start);
+        }
+
+        if self.after_cr {
+            self.after_cr = false;
+            if let Some(rest) = bytes.strip_prefix(b"\n") {
+                bytes = rest;
+            }
+        }
+        self.push_lines(bytes)
+    }
+
+    /// Split complete lines off `bytes` and process them
+    fn push_lines(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        let mut rest = bytes;
+
+        while let Some(end) = rest.iter().position(|&b| b == b'\n' || b == b'\r') {
+            self.line.extend_from_slice(&rest[..end]);
+            let line = std::mem::take(&mut self.line);
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+
+            let is_cr = rest[end] == b'\r';
+            rest = &rest[end + 1..];
+            if is_cr {
+                match rest.first() {
+                    Some(b'\n') => rest = &rest[1..],
+                    Some(_) => {}
+                    None => self.after_cr = true,
+                }
+            }
+        }
+
+        self.line.extend_from_slice(rest);
+        events
+    }
+
+    /// Interpret one line, returning an event if it was a blank line
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line[0] == b':' {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "event" => self.event_type = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event_type = std::mem::take(&mut self.event_type);
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let mut data = std::mem::take(&mut self.data);
+        data.pop();
+        Some(SseEvent {
+            event: if event_type.is_empty() {
+                DEFAULT_EVENT_TYPE.to_string()
+            } else {
+                event_type
+            },
+            data,
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+/// Decode a byte stream into Server-Sent Events
+///
+/// Transport errors are yielded as `ConnectorError::StreamInterrupted`. An
+/// event not terminated by a blank line when the stream ends is discarded,
+/// as the specification requires.
+pub fn decode<S, B, E>(bytes: S) -> impl Stream<Item = Result<SseEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut decoder = SseDecoder::new();
+    bytes.flat_map(move |chunk| {
+        let events: Vec<Result<SseEvent>> = match chunk {
+            Ok(chunk) => decoder.push(chunk.as_ref()).into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                "",
+                format!("SSE stream error: {}", e),
+            )))],
+        };
+        stream::iter(events)
+    })
+}
+
+/// Decode a byte stream of Server-Sent Events with `parse`, ending after
+/// the event `is_last` accepts
+///
+/// Ends at the first error. A byte stream ending before the last event is
+/// reported as `ConnectorError::StreamInterrupted`, naming the `missing`
+/// event.
+pub(crate) fn decode_until<S, B, E, T, L, P>(
+    bytes: S,
+    is_last: L,
+    missing: &'static str,
+    mut parse: P,
+) -> impl Stream<Item = Result<T>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+    L: Fn(&SseEvent) -> bool,
+    P: FnMut(&SseEvent) -> Result<Vec<T>>,
+{
+    decode(bytes)
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .scan(false, move |ended, event| {
+            if *ended {
+                return future::ready(None);
+            }
+            let items: Vec<Result<T>> = match event {
+                Some(Ok(event)) => {
+                    *ended = is_last(&event);
+                    match parse(&event) {
+                        Ok(items) => items.into_iter().map(Ok).collect(),
+                        Err(e) => vec![Err(e)],
+                    }
+                }
+                Some(Err(e)) => vec![Err(e)],
+                None => vec![Err(ConnectorError::StreamInterrupted(ProviderError::new(
+                    "",
+                    format!("stream ended before {}", missing),
+                )))],
+            };
+            *ended |= items.iter().any(Result::is_err);
+            future::ready(Some(stream::iter(items)))
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: &str, data: &str, id: Option<&str>) -> SseEvent {
+        SseEvent {
+            event: event.to_string(),
+            data: data.to_string(),
+            id: id.map(str::to_string),
+        }
+    }
+
+    fn decode_all(input: &[u8]) -> Vec<SseEvent> {
+        SseDecoder::new().push(input)
+    }
+
+    /// Decode `input` fed in pieces cut at the given offsets
+    fn decode_split(input: &[u8], cuts: &[usize]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        let mut start = 0;
+        for &cut in cuts.iter().chain(std::iter::once(&input.len())) {
+            events.extend(decoder.push(&input[start..cut]));
+            start = cut;
+        }
+        events
+    }
+
+    /// Small deterministic PRNG so split tests are reproducible
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) as usize) % bound
+        }
+    }
+
+    const SAMPLE: &str = "\u{FEFF}: keep-alive comment\r\n\
+        retry: 3000\r\n\
+        event: message_start\r\n\
+        id: 1\r\n\
+        data: {\"text\": \"héllo 👋\"}\r\n\
+        \r\n\
+        data: line one\n\
+        data:line two\n\
+        data\n\
+        \n\
+        event: ping\rdata: 日本語\r\r\
+        id\n\
+        data: [DONE]\n\n";
+
+    fn sample_events() -> Vec<SseEvent> {
+        vec![
+            event("message_start", "{\"text\": \"héllo 👋\"}", Some("1")),
+            event("message", "line one\nline two\n", Some("1")),
+            event("ping", "日本語", Some("1")),
+            event("message", "[DONE]", Some("")),
+        ]
+    }
+
+    #[test]
+    fn test_spec_fields() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(SAMPLE.as_bytes());
+        assert_eq!(events, sample_events());
+        assert_eq!(decoder.retry(), Some(3000));
+        assert!(events[3].is_done());
+    }
+
+    #[test]
+    fn test_blank_events_and_invalid_fields() {
+        let events = decode_all(b"event: only-type\n\nretry: 12a\nid: a\0b\nfoo: bar\ndata: x\n\n");
+        assert_eq!(events, vec![event("message", "x", None)]);
+    }
+
+    #[test]
+    fn test_unterminated_event_is_discarded() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(b"data: partial").is_empty());
+        assert!(decoder.push(b"\n").is_empty());
+    }
+
+    #[test]
+    fn test_every_single_split_point() {
+        let input = SAMPLE.as_bytes();
+        for cut in 0..=input.len() {
+            assert_eq!(
+                decode_split(input, &[cut]),
+                sample_events(),
+                "split at byte {}",
+                cut
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_multi_splits() {
+        let input = SAMPLE.as_bytes();
+        let mut rng = Lcg(0x5eed);
+        for round in 0..500 {
+            let mut cuts: Vec<usize> = (0..rng.next(12)).map(|_| rng.next(input.len())).collect();
+            cuts.sort_unstable();
+            assert_eq!(
+                decode_split(input, &cuts),
+                sample_events(),
+                "round {} cuts {:?}",
+                round,
+                cuts
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_at_a_time() {
+        let input = SAMPLE.as_bytes();
+        let cuts: Vec<usize> = (1..input.len()).collect();
+        assert_eq!(decode_split(input, &cuts), sample_events());
+    }
+
+    #[test]
+    fn test_json_data() {
+        let events = decode_all(b"data: {\"n\": 1}\n\n");
+        let value: serde_json::Value = events[0].json().unwrap();
+        assert_eq!(value["n"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_decode_stream() {
+        let chunks: Vec<std::result::Result<&[u8], String>> = vec![
+            Ok(b"data: a\n"),
+            Ok(b"\ndata: b\n\n"),
+            Err("connection reset".to_string()),
+        ];
+        let items: Vec<Result<SseEvent>> = decode(stream::iter(chunks)).collect().await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap().data, "a");
+        assert_eq!(items[1].as_ref().unwrap().data, "b");
+        assert!(items[2].is_err());
+    }
+}
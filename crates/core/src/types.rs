@@ -0,0 +1,761 @@
+//! # Core Types
+//!
+//! Unified request/response data model shared by every provider.
+//!
+//! These types mirror the `@llm-connector-hub/core` TypeScript package so that
+//! Rust and TypeScript callers exchange the same JSON shapes.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use connector_hub_core::types::{CompletionRequest, Message};
+//!
+//! let request = CompletionRequest::builder("gpt-4")
+//!     .message(Message::system("You are a helpful assistant"))
+//!     .message(Message::user("Hello!"))
+//!     .temperature(0.7)
+//!     .max_tokens(256)
+//!     .build()?;
+//! ```
+
+use crate::error::{ConnectorError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Connector metadata type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorMetadata {
+    /// Connector name
+    pub name: String,
+    /// Connector version
+    pub version: String,
+    /// Provider type (OpenAI, Anthropic, Google, etc.)
+    pub provider: String,
+}
+
+/// Message author role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// System instructions
+    System,
+    /// End-user input
+    User,
+    /// Model output
+    Assistant,
+    /// Legacy function result
+    Function,
+    /// Tool result
+    Tool,
+}
+
+/// Image detail level for vision inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    /// Let the provider decide
+    Auto,
+    /// Low resolution
+    Low,
+    /// High resolution
+    High,
+}
+
+/// A single part of a multi-part message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// Plain text
+    Text {
+        /// Text content
+        text: String,
+    },
+    /// Image referenced by URL
+    ImageUrl {
+        /// Image URL
+        image_url: String,
+        /// Requested detail level
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<ImageDetail>,
+    },
+    /// Inline base64-encoded image
+    ImageBase64 {
+        /// Base64 image data
+        image_base64: String,
+        /// Requested detail level
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<ImageDetail>,
+    },
+}
+
+/// Message content: either a plain string or a list of parts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    /// Plain text content
+    Text(String),
+    /// Multi-part content (text and images)
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// Concatenate all text parts, ignoring images
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(""),
+        }
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+/// Function invocation requested by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    /// Function name
+    pub name: String,
+    /// JSON-encoded arguments
+    pub arguments: String,
+}
+
+/// Tool call requested by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Tool call ID (echoed back in the tool result message)
+    pub id: String,
+    /// Tool type (always "function")
+    #[serde(rename = "type", default = "default_tool_type")]
+    pub kind: String,
+    /// Function invocation
+    pub function: FunctionCall,
+}
+
+impl ToolCall {
+    /// Create a function tool call
+    pub fn function(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            kind: default_tool_type(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments: arguments.into(),
+            },
+        }
+    }
+}
+
+fn default_tool_type() -> String {
+    "function".to_string()
+}
+
+/// Function definition exposed to the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    /// Function name
+    pub name: String,
+    /// Human-readable description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON Schema describing the parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+}
+
+/// Tool definition exposed to the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Tool type (always "function")
+    #[serde(rename = "type", default = "default_tool_type")]
+    pub kind: String,
+    /// Function definition
+    pub function: FunctionDefinition,
+}
+
+impl ToolDefinition {
+    /// Create a function tool definition
+    pub fn function(
+        name: impl Into<String>,
+        description: Option<String>,
+        parameters: Option<Value>,
+    ) -> Self {
+        Self {
+            kind: default_tool_type(),
+            function: FunctionDefinition {
+                name: name.into(),
+                description,
+                parameters,
+            },
+        }
+    }
+}
+
+/// Chat message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// Author role
+    pub role: Role,
+    /// Message content; `null` (tool-call-only turns) reads as empty
+    #[serde(default, deserialize_with = "nullable_content")]
+    pub content: MessageContent,
+    /// Optional author name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Tool calls requested by the assistant
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// ID of the tool call this message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+fn nullable_content<'de, D>(deserializer: D) -> std::result::Result<MessageContent, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<MessageContent>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl Message {
+    /// Create a message with the given role and content
+    pub fn new(role: Role, content: impl Into<MessageContent>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            name: None,
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    /// Create a system message
+    pub fn system(content: impl Into<MessageContent>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    /// Create a user message
+    pub fn user(content: impl Into<MessageContent>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    /// Create an assistant message
+    pub fn assistant(content: impl Into<MessageContent>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    /// Create a tool result message
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<MessageContent>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(Role::Tool, content)
+        }
+    }
+
+    /// Set the author name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Attach tool calls (assistant messages)
+    pub fn with_tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
+        self.tool_calls = tool_calls;
+        self
+    }
+
+    /// Text content of the message, ignoring non-text parts
+    pub fn text(&self) -> String {
+        self.content.text()
+    }
+}
+
+/// Unified chat completion request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletionRequest {
+    /// Model name
+    pub model: String,
+    /// Conversation messages
+    pub messages: Vec<Message>,
+    /// Sampling temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Maximum tokens to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Nucleus sampling probability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Top-k sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Stop sequences
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Whether to stream the response
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    /// End-user identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Tools available to the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>,
+    /// Grounding documents for providers with native RAG support
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub documents: Vec<Document>,
+}
+
+impl CompletionRequest {
+    /// Start building a request for the given model
+    pub fn builder(model: impl Into<String>) -> CompletionRequestBuilder {
+        CompletionRequestBuilder::new(model)
+    }
+
+    /// Create a request with default parameters
+    pub fn new(model: impl Into<String>, messages: Vec<Message>) -> Self {
+        Self {
+            model: model.into(),
+            messages,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            top_k: None,
+            stop: Vec::new(),
+            stream: false,
+            user: None,
+            tools: Vec::new(),
+            documents: Vec::new(),
+        }
+    }
+}
+
+/// Grounding document for retrieval-augmented generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// Identifier echoed back in citations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Document fields (e.g. `title`, `text`)
+    pub data: serde_json::Map<String, Value>,
+}
+
+impl Document {
+    /// Create a document with a single `text` field
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            data: serde_json::Map::new(),
+        }
+        .with_field("text", text.into())
+    }
+
+    /// Set the document identifier
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Add a field
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.data.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Builder for [`CompletionRequest`]
+#[derive(Debug, Clone)]
+pub struct CompletionRequestBuilder {
+    request: CompletionRequest,
+}
+
+impl CompletionRequestBuilder {
+    /// Create a builder for the given model
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            request: CompletionRequest::new(model, Vec::new()),
+        }
+    }
+
+    /// Append a message
+    pub fn message(mut self, message: Message) -> Self {
+        self.request.messages.push(message);
+        self
+    }
+
+    /// Append several messages
+    pub fn messages(mut self, messages: impl IntoIterator<Item = Message>) -> Self {
+        self.request.messages.extend(messages);
+        self
+    }
+
+    /// Set sampling temperature
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.request.temperature = Some(temperature);
+        self
+    }
+
+    /// Set maximum tokens to generate
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.request.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set nucleus sampling probability
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.request.top_p = Some(top_p);
+        self
+    }
+
+    /// Set top-k sampling
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.request.top_k = Some(top_k);
+        self
+    }
+
+    /// Append a stop sequence
+    pub fn stop(mut self, stop: impl Into<String>) -> Self {
+        self.request.stop.push(stop.into());
+        self
+    }
+
+    /// Enable or disable streaming
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.request.stream = stream;
+        self
+    }
+
+    /// Set end-user identifier
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.request.user = Some(user.into());
+        self
+    }
+
+    /// Append a tool definition
+    pub fn tool(mut self, tool: ToolDefinition) -> Self {
+        self.request.tools.push(tool);
+        self
+    }
+
+    /// Append a grounding document
+    pub fn document(mut self, document: Document) -> Self {
+        self.request.documents.push(document);
+        self
+    }
+
+    /// Validate and build the request
+    ///
+    /// Fails with `ConnectorError::Schema` if the model is empty, there are no
+    /// messages, or a sampling parameter is out of range.
+    pub fn build(self) -> Result<CompletionRequest> {
+        let request = self.request;
+
+        if request.model.trim().is_empty() {
+            return Err(ConnectorError::Schema(
+                "Model must not be empty".to_string(),
+            ));
+        }
+        if request.messages.is_empty() {
+            return Err(ConnectorError::Schema(
+                "Request must contain at least one message".to_string(),
+            ));
+        }
+        if let Some(temperature) = request.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(ConnectorError::Schema(format!(
+                    "Temperature must be between 0 and 2, got {}",
+                    temperature
+                )));
+            }
+        }
+        if let Some(top_p) = request.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(ConnectorError::Schema(format!(
+                    "top_p must be between 0 and 1, got {}",
+                    top_p
+                )));
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+/// Reason the model stopped generating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// Natural stop or stop sequence
+    Stop,
+    /// Token limit reached
+    Length,
+    /// Legacy function call
+    FunctionCall,
+    /// Model requested tool calls
+    ToolCalls,
+    /// Output blocked by content filter
+    ContentFilter,
+}
+
+/// Token usage for a completion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Tokens in the prompt
+    pub prompt_tokens: u32,
+    /// Tokens in the completion
+    pub completion_tokens: u32,
+    /// Total tokens
+    pub total_tokens: u32,
+}
+
+impl Usage {
+    /// Create usage from prompt and completion token counts
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens.saturating_add(completion_tokens),
+        }
+    }
+}
+
+/// A single completion choice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Choice {
+    /// Choice index
+    pub index: u32,
+    /// Generated message
+    pub message: Message,
+    /// Why generation stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// Provider-specific response metadata
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderMetadata {
+    /// Provider that served the request
+    pub provider: String,
+    /// Model reported by the provider
+    pub model: String,
+    /// Raw provider response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_response: Option<Value>,
+    /// Additional provider-specific fields
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Unified chat completion response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletionResponse {
+    /// Response ID
+    pub id: String,
+    /// Object type (e.g. "chat.completion")
+    #[serde(default = "default_object")]
+    pub object: String,
+    /// Unix timestamp (seconds)
+    pub created: i64,
+    /// Model that produced the response
+    pub model: String,
+    /// Completion choices
+    pub choices: Vec<Choice>,
+    /// Token usage
+    #[serde(default)]
+    pub usage: Usage,
+    /// Provider metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ProviderMetadata>,
+}
+
+fn default_object() -> String {
+    "chat.completion".to_string()
+}
+
+impl CompletionResponse {
+    /// Text of the first choice, if any
+    pub fn text(&self) -> Option<String> {
+        self.choices.first().map(|choice| choice.message.text())
+    }
+
+    /// Finish reason of the first choice, if any
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.choices.first().and_then(|choice| choice.finish_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_builder_sets_fields() {
+        let request = CompletionRequest::builder("gpt-4")
+            .message(Message::system("Be brief"))
+            .message(Message::user("Hello"))
+            .temperature(0.5)
+            .max_tokens(100)
+            .stop("\n")
+            .build()
+            .unwrap();
+
+        assert_eq!(request.model, "gpt-4");
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].role, Role::System);
+        assert_eq!(request.temperature, Some(0.5));
+        assert_eq!(request.max_tokens, Some(100));
+        assert_eq!(request.stop, vec!["\n".to_string()]);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        assert!(CompletionRequest::builder("gpt-4").build().is_err());
+        assert!(CompletionRequest::builder("")
+            .message(Message::user("Hi"))
+            .build()
+            .is_err());
+        assert!(CompletionRequest::builder("gpt-4")
+            .message(Message::user("Hi"))
+            .temperature(3.0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let request = CompletionRequest::builder("gpt-4")
+            .message(Message::user(vec![
+                ContentPart::Text {
+                    text: "What is in this image?".to_string(),
+                },
+                ContentPart::ImageUrl {
+                    image_url: "https://example.com/cat.png".to_string(),
+                    detail: Some(ImageDetail::High),
+                },
+            ]))
+            .tool(ToolDefinition::function(
+                "get_weather",
+                Some("Look up the weather".to_string()),
+                Some(json!({"type": "object"})),
+            ))
+            .build()
+            .unwrap();
+
+        let encoded = serde_json::to_value(&request).unwrap();
+        assert_eq!(encoded["messages"][0]["content"][1]["type"], "image_url");
+        assert_eq!(encoded["tools"][0]["type"], "function");
+        assert!(encoded.get("stream").is_none());
+
+        let decoded: CompletionRequest = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let raw = json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        });
+
+        let response: CompletionResponse = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(response.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(response.usage, Usage::new(10, 5));
+        assert_eq!(Usage::new(u32::MAX, 5).total_tokens, u32::MAX);
+        assert_eq!(
+            response.choices[0].message.tool_calls[0],
+            ToolCall::function("call_1", "get_weather", "{\"city\":\"Paris\"}")
+        );
+
+        assert_eq!(serde_json::to_value(&response).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_message_text_ignores_images() {
+        let message = Message::user(vec![
+            ContentPart::Text {
+                text: "Hello ".to_string(),
+            },
+            ContentPart::ImageBase64 {
+                image_base64: "aGVsbG8=".to_string(),
+                detail: None,
+            },
+            ContentPart::Text {
+                text: "world".to_string(),
+            },
+        ]);
+        assert_eq!(message.text(), "Hello world");
+        assert_eq!(
+            Message::tool("call_1", "42").tool_call_id.as_deref(),
+            Some("call_1")
+        );
+    }
+
+    #[test]
+    fn test_null_content() {
+        let message: Message = serde_json::from_value(json!({
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "get_weather", "arguments": "{}"}
+            }]
+        }))
+        .unwrap();
+        assert_eq!(message.content, MessageContent::default());
+        assert_eq!(message.tool_calls.len(), 1);
+
+        let message: Message = serde_json::from_value(json!({"role": "assistant"})).unwrap();
+        assert_eq!(message.text(), "");
+    }
+}